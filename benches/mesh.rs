@@ -0,0 +1,296 @@
+//! Timing of the main constructions on quad tori of 1k, 100k, and 1M half-edges,
+//! and of [umesh::from_faces] on a quad torus of 1M faces
+//!
+//! Run by `cargo bench`. Each case prints the median of several runs
+//! and the number of heap allocations of a run, compared with `benches/baseline.txt`.
+//! The bench fails if a case takes more than [TOLERANCE] times the time or the allocations
+//! of the baseline. The baseline depends on the machine, and
+//! `cargo bench --bench mesh -- --save-baseline` replaces it by the current results.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt::Write as _;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use umesh::permutation::from_face_iter;
+use umesh::{gather_faces, gather_vertices, shapes, Connection, Mesh};
+
+/// System allocator counting the allocations
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// Median of the elapsed time of `runs` calls, and the allocations of a call
+fn median<T>(runs: usize, mut f: impl FnMut() -> T) -> (Duration, usize) {
+    median_with(runs, || (), |()| f())
+}
+
+/// [median] of `f` on a fresh input of `setup`, which is not timed
+fn median_with<S, T>(
+    runs: usize,
+    mut setup: impl FnMut() -> S,
+    mut f: impl FnMut(S) -> T,
+) -> (Duration, usize) {
+    let input = setup();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f(input));
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    let mut times: Vec<Duration> = (0..runs)
+        .map(|_| {
+            let input = setup();
+            let start = Instant::now();
+            black_box(f(input));
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    (times[runs / 2], allocations)
+}
+
+/// Ratio to the baseline from which a case is a regression
+const TOLERANCE: f64 = 2.0;
+
+/// Cases faster than this in the baseline are compared by their allocations only,
+/// since their time is dominated by noise
+const MIN_TIME_MS: f64 = 1.0;
+
+const BASELINE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/baseline.txt");
+
+/// Result of a case
+struct Case {
+    name: String,
+    half_edges: usize,
+    time: Duration,
+    allocations: usize,
+}
+
+#[derive(Default)]
+struct Report {
+    cases: Vec<Case>,
+}
+
+impl Report {
+    fn report(&mut self, name: &str, half_edges: usize, (time, allocations): (Duration, usize)) {
+        println!(
+            "{:<36} {:>9} half-edges {:>12.3} ms {:>9} allocations",
+            name,
+            half_edges,
+            time.as_secs_f64() * 1e3,
+            allocations
+        );
+        self.cases.push(Case {
+            name: name.to_string(),
+            half_edges,
+            time,
+            allocations,
+        });
+    }
+
+    fn save(&self) {
+        let mut text = String::from("# name\thalf-edges\tms\tallocations\n");
+        for case in &self.cases {
+            writeln!(
+                text,
+                "{}\t{}\t{:.3}\t{}",
+                case.name,
+                case.half_edges,
+                case.time.as_secs_f64() * 1e3,
+                case.allocations
+            )
+            .unwrap();
+        }
+        std::fs::write(BASELINE, text).expect("Cannot write the baseline");
+        println!("baseline saved to {}", BASELINE);
+    }
+
+    /// Cases slower or allocating more than [TOLERANCE] times the baseline, see [MIN_TIME_MS]
+    fn regressions(&self) -> Vec<String> {
+        let baseline = std::fs::read_to_string(BASELINE).unwrap_or_default();
+        let mut regressions = Vec::new();
+        for line in baseline.lines().filter(|line| !line.starts_with('#')) {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [name, half_edges, ms, allocations] = fields[..] else {
+                panic!("Invalid line of the baseline: {}", line);
+            };
+            let half_edges: usize = half_edges.parse().unwrap();
+            let Some(case) = self
+                .cases
+                .iter()
+                .find(|case| case.name == name && case.half_edges == half_edges)
+            else {
+                continue;
+            };
+            let ms: f64 = ms.parse().unwrap();
+            let allocations: f64 = allocations.parse().unwrap();
+            let time = case.time.as_secs_f64() * 1e3;
+            let slower = ms >= MIN_TIME_MS && time > TOLERANCE * ms;
+            if slower || case.allocations as f64 > TOLERANCE * allocations {
+                regressions.push(format!(
+                    "{} ({} half-edges): {:.3} ms, {} allocations, baseline {:.3} ms, {} allocations",
+                    name, half_edges, time, case.allocations, ms, allocations
+                ));
+            }
+        }
+        regressions
+    }
+}
+
+fn main() {
+    let mut bench = Report::default();
+    // 4 * major * minor half-edges
+    for &(major, minor) in &[(25, 10), (250, 100), (500, 500)] {
+        let permutation = shapes::torus(major, minor).unwrap();
+        let n = permutation.len();
+        let runs = if n > 100_000 { 5 } else { 21 };
+
+        bench.report(
+            "gather_vertices",
+            n,
+            median(runs, || gather_vertices(&permutation)),
+        );
+        bench.report(
+            "gather_faces",
+            n,
+            median(runs, || gather_faces(&permutation)),
+        );
+        bench.report(
+            "Mesh::from_permutation",
+            n,
+            median(runs, || Mesh::from_permutation(&permutation)),
+        );
+
+        let pairs: Vec<(usize, usize)> = (0..n).map(|h| (h / 2, permutation[h] / 2)).collect();
+        bench.report(
+            "Connection::from_vec",
+            n,
+            median(runs, || Connection::from_vec(pairs.clone())),
+        );
+
+        let mesh = Mesh::from_permutation(&permutation);
+        let vertices: Vec<usize> = (0..mesh.num_vertices()).step_by(7).collect();
+        let selection = mesh.simplicies(&vertices, &[], &[]);
+        bench.report("Simplices::star", n, median(runs, || selection.star()));
+        bench.report(
+            "Simplices::closure",
+            n,
+            median(runs, || selection.closure()),
+        );
+        bench.report("Simplices::link", n, median(runs, || selection.link()));
+        let all = mesh.all_simplices();
+        let region = selection.star();
+        bench.report(
+            "Simplices::sub (dense)",
+            n,
+            median(runs, || all.clone() - region.clone()),
+        );
+        bench.report("Simplices::star (dense)", n, median(runs, || all.star()));
+        bench.report(
+            "Mesh::vertex_adjacency (first)",
+            n,
+            median_with(
+                runs,
+                || mesh.clone(),
+                |mesh| mesh.vertex_adjacency().shape(),
+            ),
+        );
+        mesh.vertex_adjacency();
+        bench.report(
+            "Mesh::vertex_adjacency (cached)",
+            n,
+            median(runs, || mesh.vertex_adjacency().shape()),
+        );
+    }
+
+    let faces = shapes::torus_faces(1000, 1000).unwrap();
+    let n = 4 * faces.len();
+    bench.report(
+        "from_faces",
+        n,
+        median(5, || from_face_iter(&faces).unwrap()),
+    );
+    let permutation = from_face_iter(&faces).unwrap();
+    bench.report(
+        "Mesh::from_permutation",
+        n,
+        median(5, || Mesh::from_permutation(&permutation)),
+    );
+
+    // half of the vertices of a torus of 2M faces
+    #[cfg(feature = "parallel")]
+    {
+        let permutation = shapes::torus(1000, 1000).unwrap();
+        let mesh = Mesh::from_permutation(&permutation);
+        let half: Vec<usize> = (0..mesh.num_vertices()).step_by(2).collect();
+        let selection = mesh.simplicies(&half, &[], &[]);
+        let n = permutation.len();
+        bench.report(
+            "Simplices::star (sequential)",
+            n,
+            median(5, || selection.star_with_threshold(usize::MAX)),
+        );
+        bench.report(
+            "Simplices::star (parallel)",
+            n,
+            median(5, || selection.star_with_threshold(0)),
+        );
+    }
+
+    // orbits of a torus of 2M half-edges
+    #[cfg(feature = "parallel")]
+    {
+        let permutation = shapes::torus(1000, 500).unwrap();
+        let n = permutation.len();
+        let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+        bench.report(
+            "gather_vertices (sequential)",
+            n,
+            median(5, || gather_vertices(&permutation)),
+        );
+        bench.report(
+            "gather_vertices (parallel)",
+            n,
+            median(5, || {
+                umesh::parallel::gather_vertices(&permutation, threads)
+            }),
+        );
+        bench.report(
+            "Mesh::from_permutation (sequential)",
+            n,
+            median(5, || {
+                Mesh::from_permutation_with_threshold(&permutation, usize::MAX)
+            }),
+        );
+        bench.report(
+            "Mesh::from_permutation (parallel)",
+            n,
+            median(5, || Mesh::from_permutation_with_threshold(&permutation, 0)),
+        );
+    }
+
+    if std::env::args().any(|arg| arg == "--save-baseline") {
+        bench.save();
+        return;
+    }
+    let regressions = bench.regressions();
+    if !regressions.is_empty() {
+        eprintln!("Regressions over {} times the baseline:", TOLERANCE);
+        for regression in &regressions {
+            eprintln!("  {}", regression);
+        }
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,39 @@
+//! Faces separated by `0xff`, each byte a vertex id
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use umesh::{builder::MeshBuilder, from_faces, permutation::validate, Mesh};
+
+fuzz_target!(|data: &[u8]| {
+    let faces: Vec<Vec<usize>> = data
+        .split(|&b| b == 0xff)
+        .map(|face| face.iter().map(|&v| v as usize).collect())
+        .collect();
+
+    let num_vertices = faces.iter().flatten().max().map_or(0, |&v| v + 1);
+    let mut builder = MeshBuilder::new(num_vertices);
+    for _ in 0..num_vertices {
+        builder.add_vertex();
+    }
+    let added = faces.iter().all(|face| builder.add_face(face).is_ok());
+    let built = builder.build();
+
+    match from_faces(&faces) {
+        Ok(permutation) => {
+            validate(&permutation).unwrap();
+            let mesh = Mesh::from_permutation(&permutation);
+            mesh.check_invariants().unwrap();
+            assert!(mesh.is_manifold());
+            // the builder agrees unless some vertex is isolated
+            if let Ok(built) = built {
+                assert!(added);
+                assert_eq!(built.permutation(), mesh.permutation());
+            }
+        }
+        Err(_) => {
+            if let Ok(built) = built {
+                built.check_invariants().unwrap();
+            }
+        }
+    }
+});
@@ -0,0 +1,32 @@
+//! Text parsers must return an error instead of panicking
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use umesh::{
+    io::{
+        faces::read_faces, graph6::*, permutation::parse_permutation, selection::parse_selection,
+    },
+    permutation::{from_face_iter, validate},
+    shapes, Mesh,
+};
+
+fuzz_target!(|data: &[u8]| {
+    let input = match std::str::from_utf8(data) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+    if let Ok(permutation) = parse_permutation(input) {
+        if validate(&permutation).is_ok() {
+            Mesh::from_permutation(&permutation)
+                .check_invariants()
+                .unwrap();
+        }
+    }
+    if let Ok(faces) = read_faces(input.as_bytes()) {
+        let _ = from_face_iter(&faces);
+    }
+    let _ = parse_graph6(input);
+    let _ = parse_sparse6(input);
+    let tetrahedron = Mesh::from_permutation(&shapes::tetrahedron());
+    let _ = parse_selection(&tetrahedron, input);
+});
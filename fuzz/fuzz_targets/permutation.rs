@@ -0,0 +1,35 @@
+//! Each byte is a half-edge, valid permutations must give a consistent closed mesh
+
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use std::collections::HashMap;
+use umesh::{gather_faces, gather_vertices, permutation::validate, Mesh};
+
+fuzz_target!(|data: &[u8]| {
+    let permutation: Vec<usize> = data.iter().map(|&b| b as usize).collect();
+    if validate(&permutation).is_err() {
+        return;
+    }
+    let mesh = Mesh::from_permutation(&permutation);
+    mesh.check_invariants().unwrap();
+    assert!(mesh.is_closed());
+    assert!(mesh.is_manifold());
+    assert!(mesh.genus().is_some());
+    assert_eq!(mesh.dual().dual().permutation(), mesh.permutation());
+
+    // d1 d0 = 0 with the edge `k` oriented along the half-edge `2k`
+    let mut origin = vec![0; permutation.len()];
+    for (v, orbit) in gather_vertices(&permutation).iter().enumerate() {
+        for h in orbit.indices() {
+            origin[h] = v;
+        }
+    }
+    for orbit in gather_faces(&permutation) {
+        let mut d1d0: HashMap<usize, isize> = HashMap::new();
+        for h in orbit.indices() {
+            *d1d0.entry(origin[h ^ 1]).or_default() += 1;
+            *d1d0.entry(origin[h]).or_default() -= 1;
+        }
+        assert!(d1d0.values().all(|&c| c == 0));
+    }
+});
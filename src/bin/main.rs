@@ -0,0 +1,1197 @@
+//! Command line interface of umesh
+//!
+//! ```text
+//! umesh dot tetrahedron.txt          # vertex graph in Dot format
+//! umesh dot --labels orbits tetrahedron.txt
+//! umesh stats - < tetrahedron.txt    # read from stdin
+//! umesh check -o report.txt 5 6 0 8 10 2 11 3 7 4 9 1
+//! umesh check --allow boundary,degenerate mesh.txt
+//! umesh render-svg --color --labels ids -o tetrahedron.svg tetrahedron.txt
+//! umesh generate torus --major 8 --minor 4 | umesh stats -
+//! umesh generate grid --nx 4 --ny 2 | umesh boundary -
+//! umesh dual --check cube.txt | umesh stats -
+//! umesh boundary --export loops.svg faces.txt
+//! umesh subdivide --scheme loop --steps 2 icosahedron.txt
+//! umesh stats --timings mesh.txt     # time of each stage on stderr
+//! umesh query --select "V 0" cube.txt link
+//! umesh curvature -o curvature.graphml icosahedron.txt
+//! umesh convert faces.txt > mesh.txt     # streams the faces without holding them
+//! umesh diff expected.txt actual.txt  # exit 0 if identical, 1 if isomorphic, 2 otherwise, 4 on errors
+//! umesh repair --remove-degenerate --fix-orientation --fill-holes 8 -o mesh.txt faces.txt
+//! ```
+//!
+//! JSON output
+//! -----------
+//! With `--format json`, the informational commands write a single JSON object to stdout.
+//! Every object has `"schema_version":1`, and later versions only add keys.
+//!
+//! ```text
+//! stats:      {"schema_version":1,"vertices":8,"edges":12,"faces":6,"euler_characteristic":2,
+//!              "genus":0,"closed":true,"manifold":true,"components":1,"boundary_loops":0,
+//!              "valence_histogram":{"3":8},"face_degree_histogram":{"4":6}}
+//! check:      {"schema_version":1,"passed":false,
+//!              "checks":[{"name":"permutation","passed":true,"allowed":false,"problems":[]},...]}
+//! boundary:   {"schema_version":1,"loops":[{"edges":4,"vertices":[0,1,5,4]},...]}
+//! components: {"schema_version":1,
+//!              "components":[{"vertices":4,"edges":6,"faces":4,"genus":0,"file":null},...]}
+//! query:      {"schema_version":1,"vertices":[1,2,3],"edges":[2,4,5],"faces":[]}
+//! ```
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::exit;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use umesh::{
+    builder::{BuildError, MeshBuilder},
+    check::{Check, CHECKS},
+    io::dot::*,
+    io::faces::*,
+    io::graphml::*,
+    io::permutation::*,
+    io::quote_json,
+    io::selection::*,
+    io::svg::*,
+    permutation,
+    repair::{repair, RepairOptions},
+    shapes, subdivision,
+    timing::{self, Timer},
+    Mesh, SimplexSet,
+};
+
+const USAGE: &str = "Usage: umesh <COMMAND> [OPTIONS] <INPUT>
+       umesh generate [OPTIONS] <SHAPE>
+       umesh query --select SELECTION [OPTIONS] <INPUT> <OPERATION>
+       umesh repair [OPTIONS] <FACES>
+       umesh convert [OPTIONS] <FACES>
+       umesh boundary [OPTIONS] <FACES>
+       umesh diff <INPUT> <INPUT>
+
+Commands:
+    dot         Print the vertex graph in Dot format
+    stats       Print the summary of the topology
+    check       Validate the input and report problems of the mesh
+    render-svg  Draw the mesh in the plane as SVG
+    generate    Write the permutation of a built-in shape, or the faces of a grid
+    dual        Write the permutation of the dual mesh
+    subdivide   Write the permutation of the subdivided mesh
+    boundary    List the boundary loops of a surface given by faces
+    components  List the connected components
+    query       Print the star, closure, link, or boundary of a selection
+    curvature   Write the curvature of vertices as a GraphML attribute
+    repair      Repair a list of faces, and write its permutation
+    diff        Compare two meshes
+    convert     Write the permutation of a closed surface given by faces
+
+Input:
+    FILE            Permutation on half-edges in a text file
+    -               Read the permutation from stdin
+    PERMUTATION...  Permutation given as arguments
+    FACES           File or '-' with the vertex ids of a face per line (repair, convert, boundary)
+
+Shapes:
+    tetrahedron, cube, icosahedron
+    icosphere [--subdiv N]                      default N = 1
+    torus [--major N] [--minor M]               default 8 x 4, at least 3 x 3
+    random-triangulation [--faces N] [--seed S] default 20 faces, even and at least 4
+    grid [--nx N] [--ny M]                      open disk of quads as faces, default 4 x 4
+
+Selection:
+    Groups of ids separated by '/', e.g. \"V 3 / E / F 10 12\"
+
+Options:
+    -o FILE                Write to FILE instead of stdout
+    --format FORMAT        text or json (stats, check, boundary, components, query)
+    --json                 Same as --format json
+    --color                Fill faces by the greedy coloring (render-svg)
+    --labels LABELS        orbits, ids, or none to label vertices by (dot, default ids),
+                           ids or none (render-svg, default none)
+    --check                Verify that the dual of the dual is the input (dual)
+    --timings              Print the time of each stage and the memory of the topology to stderr
+    --scheme SCHEME        loop, catmull-clark, or midpoint (subdivide, default loop)
+    --steps N              Number of subdivision steps (subdivide, default 1)
+    --split-prefix PREFIX  Write each component to PREFIX_000.txt, ... (components)
+    --export FILE          Also draw the loops as polylines in SVG (boundary)
+    --select SELECTION     Vertices, edges, and faces to operate on (query)
+    --kind KIND            Only gaussian, the angle defect with regular faces (curvature)
+    --remove-degenerate    Remove collapsed and duplicated faces (repair)
+    --fix-orientation      Flip faces to orient them consistently (repair)
+    --fill-holes N         Fill holes of at most N edges by a face (repair)
+    --strict               Write nothing unless the result passes the checks (repair)
+    --allow CHECKS         Comma-separated checks whose failures are allowed (check),
+                           from invariants, manifold, boundary, degenerate, euler
+
+Exit status:
+    1  Invalid command line
+    2  Invalid input data, or the check failed
+    3  Failed to read or write a file
+
+    diff exits with 0 if the permutations are identical,
+    1 if they are isomorphic, 2 if the surfaces are different, and 4 on any error";
+
+const FORMAT_HINT: &str =
+    "The permutation is non-negative integers separated by whitespace, and lines starting with '#' are comments";
+const PERMUTATION_HINT: &str =
+    "The h-th integer is the next half-edge of h, and each of 0..n must appear exactly once with an even n";
+const INPUT_HINT: &str = "Give a file, '-' to read stdin, or the permutation as integers";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Dot,
+    Stats,
+    Check,
+    RenderSvg,
+    Generate,
+    Dual,
+    Subdivide,
+    Boundary,
+    Components,
+    Query,
+    Curvature,
+    Repair,
+    Diff,
+    Convert,
+}
+
+/// Options with a value
+const PARAMETERS: [&str; 16] = [
+    "--subdiv",
+    "--nx",
+    "--ny",
+    "--major",
+    "--minor",
+    "--faces",
+    "--seed",
+    "--allow",
+    "--scheme",
+    "--steps",
+    "--split-prefix",
+    "--select",
+    "--kind",
+    "--fill-holes",
+    "--labels",
+    "--export",
+];
+
+/// Version of the schema of the JSON output
+const SCHEMA_VERSION: usize = 1;
+
+/// Exit status of diff on errors, which are distinct from its results 0, 1, and 2
+const DIFF_ERROR: i32 = 4;
+
+/// Number of problems printed per check
+const MAX_PROBLEMS: usize = 10;
+
+/// Largest number of faces `subdivide --steps` may produce
+const MAX_SUBDIVIDED_FACES: usize = 1 << 22;
+
+const SHAPES: [&str; 7] = [
+    "tetrahedron",
+    "cube",
+    "icosahedron",
+    "icosphere",
+    "torus",
+    "random-triangulation",
+    "grid",
+];
+
+/// Error of the CLI, reported as `error: <message>` and an exit code
+#[derive(Debug)]
+enum CliError {
+    /// Invalid command line
+    Usage(String),
+    /// Input is not a valid mesh
+    Data {
+        message: String,
+        hint: Option<&'static str>,
+    },
+    /// Failed to read or write a file
+    Io {
+        message: String,
+        hint: Option<&'static str>,
+    },
+}
+
+impl CliError {
+    fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 1,
+            CliError::Data { .. } => 2,
+            CliError::Io { .. } => 3,
+        }
+    }
+
+    fn hint(&self) -> Option<&'static str> {
+        match self {
+            CliError::Usage(_) => None,
+            CliError::Data { hint, .. } | CliError::Io { hint, .. } => *hint,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Usage(message)
+            | CliError::Data { message, .. }
+            | CliError::Io { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(e: io::Error) -> Self {
+        CliError::Io {
+            message: e.to_string(),
+            hint: None,
+        }
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::Usage(message)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Text,
+    Json,
+}
+
+struct Args {
+    command: Command,
+    output: Option<String>,
+    format: Format,
+    color: bool,
+    check: bool,
+    timings: bool,
+    remove_degenerate: bool,
+    fix_orientation: bool,
+    strict: bool,
+    /// Operation of the query command
+    operation: Option<String>,
+    parameters: Vec<(String, String)>,
+    input: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, String> {
+    let (command, rest) = args.split_first().ok_or("No command is given")?;
+    let command = match command.as_str() {
+        "dot" => Command::Dot,
+        "stats" => Command::Stats,
+        "check" => Command::Check,
+        "render-svg" => Command::RenderSvg,
+        "generate" => Command::Generate,
+        "dual" => Command::Dual,
+        "subdivide" => Command::Subdivide,
+        "boundary" => Command::Boundary,
+        "components" => Command::Components,
+        "query" => Command::Query,
+        "curvature" => Command::Curvature,
+        "repair" => Command::Repair,
+        "diff" => Command::Diff,
+        "convert" => Command::Convert,
+        _ => return Err(format!("Unknown command '{}'", command)),
+    };
+    let mut output = None;
+    let mut format = Format::Text;
+    let mut color = false;
+    let mut check = false;
+    let mut timings = false;
+    let mut remove_degenerate = false;
+    let mut fix_orientation = false;
+    let mut strict = false;
+    let mut parameters = Vec::new();
+    let mut input = Vec::new();
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        if arg == "-o" {
+            let path = rest.next().ok_or("-o requires a file name")?;
+            output = Some(path.clone());
+        } else if arg == "--json" {
+            format = Format::Json;
+        } else if arg == "--format" {
+            format = match rest.next().map(String::as_str) {
+                Some("text") => Format::Text,
+                Some("json") => Format::Json,
+                Some(value) => {
+                    return Err(format!("Unknown format '{}', expected text or json", value))
+                }
+                None => return Err("--format requires a value".to_string()),
+            };
+        } else if arg == "--color" {
+            color = true;
+        } else if arg == "--check" {
+            check = true;
+        } else if arg == "--timings" {
+            timings = true;
+        } else if arg == "--remove-degenerate" {
+            remove_degenerate = true;
+        } else if arg == "--fix-orientation" {
+            fix_orientation = true;
+        } else if arg == "--strict" {
+            strict = true;
+        } else if PARAMETERS.contains(&arg.as_str()) {
+            let value = rest
+                .next()
+                .ok_or_else(|| format!("{} requires a value", arg))?;
+            parameters.push((arg.clone(), value.clone()));
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option {}", arg));
+        } else {
+            input.push(arg.clone());
+        }
+    }
+    let operation = if command == Command::Query {
+        if !parameters.iter().any(|(key, _)| key == "--select") {
+            return Err("query requires --select".to_string());
+        }
+        input.pop()
+    } else {
+        None
+    };
+    if input.is_empty() {
+        return Err("No input is given".to_string());
+    }
+    Ok(Args {
+        command,
+        output,
+        format,
+        color,
+        check,
+        timings,
+        remove_degenerate,
+        fix_orientation,
+        strict,
+        operation,
+        parameters,
+        input,
+    })
+}
+
+fn read_input(args: &[String]) -> Result<Vec<usize>, CliError> {
+    match args {
+        [arg] if arg == "-" => {
+            read_permutation(io::stdin().lock()).map_err(|e| input_error("<stdin>", e))
+        }
+        [arg] if arg.parse::<usize>().is_err() => {
+            let file = File::open(arg).map_err(|e| CliError::Io {
+                message: format!("Cannot open '{}': {}", arg, e),
+                hint: Some(INPUT_HINT),
+            })?;
+            read_permutation(BufReader::new(file)).map_err(|e| input_error(arg, e))
+        }
+        _ => parse_permutation(&args.join(" ")).map_err(|e| input_error("<arguments>", e)),
+    }
+}
+
+fn input_error(source: &str, e: ReadError) -> CliError {
+    match e {
+        ReadError::Io(e) => CliError::Io {
+            message: format!("Cannot read {}: {}", source, e),
+            hint: None,
+        },
+        ReadError::Parse { .. } => CliError::Data {
+            message: format!("{}: {}", source, e),
+            hint: Some(FORMAT_HINT),
+        },
+    }
+}
+
+fn output(args: &Args) -> io::Result<Box<dyn Write>> {
+    Ok(match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout()),
+    })
+}
+
+/// Value of the option given last as `--name value`
+fn last_value<'a>(args: &'a Args, name: &str) -> Option<&'a str> {
+    args.parameters
+        .iter()
+        .rev()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Value of the option given as `--name value` parsed as an integer
+fn parameter<T: FromStr>(args: &Args, name: &str, default: T) -> Result<T, String> {
+    match last_value(args, name) {
+        Some(value) => value.parse().map_err(|_| {
+            format!(
+                "{} requires a non-negative integer, but got '{}'",
+                name, value
+            )
+        }),
+        None => Ok(default),
+    }
+}
+
+/// Name the option instead of the parameter of the library
+fn option_error(e: shapes::ShapeError) -> String {
+    format!("--{} {}, but got {}", e.parameter, e.requirement, e.value)
+}
+
+fn labels_error(labels: &str, expected: &str) -> CliError {
+    CliError::Usage(format!(
+        "Unknown labels '{}', expected one of {}",
+        labels, expected
+    ))
+}
+
+/// Number of faces after `steps` subdivisions of a mesh, or `None` on overflow
+///
+/// Every scheme turns a half-edge into four.
+fn subdivided_faces(
+    faces: usize,
+    half_edges: usize,
+    scheme: subdivision::Scheme,
+    steps: usize,
+) -> Option<usize> {
+    let (mut faces, mut half_edges) = (faces, half_edges);
+    for _ in 0..steps {
+        faces = match scheme {
+            subdivision::Scheme::Loop => faces.checked_mul(4)?,
+            subdivision::Scheme::CatmullClark => half_edges,
+            subdivision::Scheme::Midpoint => faces.checked_add(half_edges)?,
+        };
+        half_edges = half_edges.checked_mul(4)?;
+    }
+    Some(faces)
+}
+
+/// Output of `generate`
+enum Shape {
+    /// Closed surface
+    Permutation(Vec<usize>),
+    /// Surface with boundary, which a permutation cannot hold
+    Faces(Vec<Vec<usize>>),
+}
+
+fn generate(args: &Args) -> Result<Shape, CliError> {
+    let shape = match &args.input[..] {
+        [shape] => shape.as_str(),
+        _ => return Err(CliError::Usage("generate takes exactly one shape".into())),
+    };
+    if shape == "grid" {
+        let faces = shapes::grid_faces(parameter(args, "--nx", 4)?, parameter(args, "--ny", 4)?)
+            .map_err(option_error)?;
+        return Ok(Shape::Faces(
+            faces.iter().map(|face| face.to_vec()).collect(),
+        ));
+    }
+    let permutation = match shape {
+        "tetrahedron" => shapes::tetrahedron(),
+        "cube" => shapes::cube(),
+        "icosahedron" => shapes::icosahedron(),
+        "icosphere" => shapes::icosphere(parameter(args, "--subdiv", 1)?).map_err(option_error)?,
+        "torus" => shapes::torus(
+            parameter(args, "--major", 8)?,
+            parameter(args, "--minor", 4)?,
+        )
+        .map_err(option_error)?,
+        "random-triangulation" => shapes::random_triangulation(
+            parameter(args, "--faces", 20)?,
+            parameter(args, "--seed", 0)?,
+        )
+        .map_err(option_error)?,
+        _ => {
+            return Err(CliError::Usage(format!(
+                "Unknown shape '{}', expected one of {}",
+                shape,
+                SHAPES.join(", ")
+            )))
+        }
+    };
+    Ok(Shape::Permutation(permutation))
+}
+
+/// Checks given by `--allow`
+fn allowed_checks(args: &Args) -> Result<Vec<&str>, CliError> {
+    let mut allowed = Vec::new();
+    for (_, value) in args.parameters.iter().filter(|(key, _)| key == "--allow") {
+        for name in value.split(',') {
+            if !CHECKS.contains(&name) {
+                return Err(CliError::Usage(format!(
+                    "Unknown check '{}' for --allow, expected one of {}",
+                    name,
+                    CHECKS.join(", ")
+                )));
+            }
+            allowed.push(name);
+        }
+    }
+    Ok(allowed)
+}
+
+/// JSON object with the schema version followed by the given members
+fn json_document(members: &str) -> String {
+    format!(r#"{{"schema_version":{},{}}}"#, SCHEMA_VERSION, members)
+}
+
+fn json_array<T, F: Fn(&T) -> String>(items: &[T], f: F) -> String {
+    let items: Vec<String> = items.iter().map(f).collect();
+    format!("[{}]", items.join(","))
+}
+
+/// Report the checks, and returns whether all checks pass except the allowed ones
+fn check(
+    permutation: &[usize],
+    allowed: &[&str],
+    format: Format,
+    mut out: Box<dyn Write>,
+) -> Result<bool, CliError> {
+    let mut checks = Vec::new();
+    if let Err(e) = permutation::validate(permutation) {
+        checks.push(Check {
+            name: "permutation",
+            problems: vec![e.to_string()],
+        });
+    } else {
+        checks.push(Check {
+            name: "permutation",
+            problems: Vec::new(),
+        });
+        checks.extend(Mesh::from_permutation(permutation).check());
+    }
+    let is_allowed = |check: &Check| allowed.contains(&check.name);
+    let passed = checks
+        .iter()
+        .all(|check| check.passed() || is_allowed(check));
+
+    if format == Format::Json {
+        let checks = json_array(&checks, |check| {
+            format!(
+                r#"{{"name":"{}","passed":{},"allowed":{},"problems":{}}}"#,
+                check.name,
+                check.passed(),
+                is_allowed(check),
+                json_array(&check.problems, |problem| quote_json(problem))
+            )
+        });
+        writeln!(
+            out,
+            "{}",
+            json_document(&format!(r#""passed":{},"checks":{}"#, passed, checks))
+        )?;
+        return Ok(passed);
+    }
+
+    for check in &checks {
+        if check.passed() {
+            writeln!(out, "{}: ok", check.name)?;
+            continue;
+        }
+        if check.name == "permutation" {
+            writeln!(out, "permutation: failed ({})", check.problems[0])?;
+            continue;
+        }
+        writeln!(
+            out,
+            "{}: failed{}",
+            check.name,
+            if is_allowed(check) { " (allowed)" } else { "" }
+        )?;
+        for problem in check.problems.iter().take(MAX_PROBLEMS) {
+            writeln!(out, "    {}", problem)?;
+        }
+        if check.problems.len() > MAX_PROBLEMS {
+            writeln!(
+                out,
+                "    ... and {} more",
+                check.problems.len() - MAX_PROBLEMS
+            )?;
+        }
+    }
+    Ok(passed)
+}
+
+/// List the boundary loops of the faces, and draw them with `--export`
+fn boundary(args: &Args) -> Result<(), CliError> {
+    let (source, reader) = face_reader(args)?;
+    let faces: Vec<Vec<usize>> = reader
+        .collect::<Result<_, _>>()
+        .map_err(|e| input_error(&source, e))?;
+    let mesh = build_mesh(&faces).map_err(|e| CliError::Data {
+        message: format!("{}: {}", source, e),
+        hint: Some("umesh repair can remove degenerate faces and fix the orientation"),
+    })?;
+    let loops = mesh.boundary_loops();
+    if let Some(path) = last_value(args, "--export") {
+        let file = File::create(path).map_err(|e| CliError::Io {
+            message: format!("Cannot create '{}': {}", path, e),
+            hint: None,
+        })?;
+        let positions = mesh.tutte_embedding();
+        let style = SvgStyle {
+            positions: positions.as_deref(),
+            vertex_labels: true,
+            ..Default::default()
+        };
+        write_loops_svg(&loops, io::BufWriter::new(file), &style)?;
+    }
+    let (format, mut out) = (args.format, output(args)?);
+    if format == Format::Json {
+        let loops = json_array(&loops, |vertices| {
+            format!(
+                r#"{{"edges":{},"vertices":{}}}"#,
+                vertices.len(),
+                json_array(vertices, usize::to_string)
+            )
+        });
+        writeln!(out, "{}", json_document(&format!(r#""loops":{}"#, loops)))?;
+    } else if loops.is_empty() {
+        writeln!(out, "closed surface, no boundary")?;
+    } else {
+        for (i, vertices) in loops.iter().enumerate() {
+            let ids: Vec<String> = vertices.iter().map(|v| v.to_string()).collect();
+            writeln!(
+                out,
+                "loop {}: {} edges: {}",
+                i,
+                vertices.len(),
+                ids.join(" ")
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Mesh of the faces keeping their vertex ids unless it is closed, see [MeshBuilder::build]
+fn build_mesh(faces: &[Vec<usize>]) -> Result<Mesh, BuildError> {
+    let num_vertices = faces.iter().flatten().map(|&v| v + 1).max().unwrap_or(0);
+    let mut builder = MeshBuilder::new(num_vertices);
+    for _ in 0..num_vertices {
+        builder.add_vertex();
+    }
+    for face in faces {
+        builder.add_face(face)?;
+    }
+    builder.build()
+}
+
+fn components(
+    permutation: &[usize],
+    split_prefix: Option<&str>,
+    format: Format,
+    mut out: Box<dyn Write>,
+) -> Result<(), CliError> {
+    let mut reports = Vec::new();
+    for (i, component) in permutation::split_components(permutation)
+        .iter()
+        .enumerate()
+    {
+        let file = match split_prefix {
+            Some(prefix) => {
+                let path = format!("{}_{:03}.txt", prefix, i);
+                let file = File::create(&path).map_err(|e| CliError::Io {
+                    message: format!("Cannot create '{}': {}", path, e),
+                    hint: None,
+                })?;
+                write_permutation(component, file, &format!("component {}", i))?;
+                Some(path)
+            }
+            None => None,
+        };
+        reports.push((Mesh::from_permutation(component), file));
+    }
+
+    if format == Format::Json {
+        let components = json_array(&reports, |(mesh, file)| {
+            format!(
+                r#"{{"vertices":{},"edges":{},"faces":{},"genus":{},"file":{}}}"#,
+                mesh.num_vertices(),
+                mesh.num_edges(),
+                mesh.num_faces(),
+                mesh.genus().map_or("null".to_string(), |g| g.to_string()),
+                file.as_deref().map_or("null".to_string(), quote_json)
+            )
+        });
+        writeln!(
+            out,
+            "{}",
+            json_document(&format!(r#""components":{}"#, components))
+        )?;
+        return Ok(());
+    }
+
+    writeln!(out, "components: {}", reports.len())?;
+    for (i, (mesh, _)) in reports.iter().enumerate() {
+        let genus = mesh
+            .genus()
+            .map_or_else(|| "undefined".to_string(), |g| g.to_string());
+        writeln!(out, "component {}: {}, genus {}", i, counts(mesh), genus)?;
+    }
+    Ok(())
+}
+
+fn counts(mesh: &Mesh) -> String {
+    format!(
+        "{} vertices, {} edges, {} faces",
+        mesh.num_vertices(),
+        mesh.num_edges(),
+        mesh.num_faces()
+    )
+}
+
+/// Open the single input of a command reading faces
+fn face_reader(args: &Args) -> Result<(String, FaceReader<Box<dyn BufRead>>), CliError> {
+    let (source, reader): (String, Box<dyn BufRead>) = match &args.input[..] {
+        [arg] if arg == "-" => ("<stdin>".to_string(), Box::new(BufReader::new(io::stdin()))),
+        [arg] => {
+            let file = File::open(arg).map_err(|e| CliError::Io {
+                message: format!("Cannot open '{}': {}", arg, e),
+                hint: None,
+            })?;
+            (arg.clone(), Box::new(BufReader::new(file)))
+        }
+        _ => {
+            return Err(CliError::Usage(
+                "Faces must be given as exactly one file".into(),
+            ))
+        }
+    };
+    Ok((source, FaceReader::new(reader)))
+}
+
+/// Stream the faces into a permutation, stopping at the first unreadable line
+fn convert(args: &Args) -> Result<i32, CliError> {
+    let (source, mut reader) = face_reader(args)?;
+    let mut read_error = None;
+    let faces = std::iter::from_fn(|| match reader.next()? {
+        Ok(face) => Some(face),
+        Err(e) => {
+            read_error = Some(e);
+            None
+        }
+    });
+    let result = permutation::from_face_iter(faces);
+    if let Some(e) = read_error {
+        return Err(input_error(&source, e));
+    }
+    let permutation = result.map_err(|e| CliError::Data {
+        message: format!("{}: {}", source, e),
+        hint: Some("umesh repair can fix the orientation and fill holes"),
+    })?;
+    write_permutation(
+        &permutation,
+        output(args)?,
+        &format!("converted from {}", source),
+    )?;
+    Ok(0)
+}
+
+/// Print the report of each stage and the checks to stderr, and write the result
+///
+/// The result is written as a permutation if it is a closed surface,
+/// and otherwise as the repaired faces. Nothing is written with `--strict`
+/// unless the result passes the checks.
+fn repair_faces(args: &Args) -> Result<bool, CliError> {
+    let (source, reader) = face_reader(args)?;
+    let faces: Vec<Vec<usize>> = reader
+        .collect::<Result<_, _>>()
+        .map_err(|e| input_error(&source, e))?;
+    let options = RepairOptions {
+        remove_degenerate: args.remove_degenerate,
+        fix_orientation: args.fix_orientation,
+        fill_holes: match last_value(args, "--fill-holes") {
+            Some(_) => Some(parameter(args, "--fill-holes", 0)?),
+            None => None,
+        },
+    };
+    let (faces, reports) = repair(&faces, &options);
+    for report in &reports {
+        eprintln!("{}", report);
+    }
+    match permutation::from_faces(&faces) {
+        Ok(permutation) => {
+            let passed = check(&permutation, &[], Format::Text, Box::new(io::stderr()))?;
+            if passed || !args.strict {
+                write_permutation(&permutation, output(args)?, "repaired")?;
+            }
+            Ok(passed)
+        }
+        Err(e) => {
+            eprintln!("permutation: failed ({})", e);
+            if !args.strict {
+                write_faces(&faces, output(args)?, "partially repaired")?;
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Exit status of whether the input passes the checks
+fn status(passed: bool) -> i32 {
+    if passed {
+        0
+    } else {
+        2
+    }
+}
+
+/// Print whether two meshes are identical, isomorphic, or different,
+/// and the lines of their stats which differ
+fn diff(args: &Args) -> Result<i32, CliError> {
+    let (a, b) = match &args.input[..] {
+        [a, b] => (a, b),
+        _ => return Err(CliError::Usage("diff takes exactly two inputs".into())),
+    };
+    let mut permutations = Vec::new();
+    for input in &[a, b] {
+        let permutation = read_input(&[input.to_string()])?;
+        permutation::validate(&permutation).map_err(|e| CliError::Data {
+            message: format!("{}: Invalid permutation: {}", input, e),
+            hint: Some(PERMUTATION_HINT),
+        })?;
+        permutations.push(permutation);
+    }
+    let (a, b) = (&permutations[0], &permutations[1]);
+    let mut out = output(args)?;
+    if a == b {
+        writeln!(out, "identical")?;
+        return Ok(0);
+    }
+    let code = if permutation::is_isomorphic(a, b) {
+        writeln!(out, "isomorphic")?;
+        1
+    } else {
+        writeln!(out, "different")?;
+        let stats_a = Mesh::from_permutation(a).stats().to_string();
+        let stats_b = Mesh::from_permutation(b).stats().to_string();
+        for (line_a, line_b) in stats_a.lines().zip(stats_b.lines()) {
+            if line_a != line_b {
+                writeln!(out, "- {}\n+ {}", line_a, line_b)?;
+            }
+        }
+        2
+    };
+    if a.len() == b.len() {
+        let mismatched = a.iter().zip(b).filter(|(x, y)| x != y).count();
+        writeln!(out, "mismatched half-edges: {} of {}", mismatched, a.len())?;
+    }
+    Ok(code)
+}
+
+/// Run the command, and returns the exit status
+fn run(args: &Args) -> Result<i32, CliError> {
+    if args.command == Command::Generate {
+        match generate(args)? {
+            Shape::Permutation(permutation) => {
+                write_permutation(&permutation, output(args)?, &args.input[0])?
+            }
+            Shape::Faces(faces) => write_faces(&faces, output(args)?, &args.input[0])?,
+        }
+        return Ok(0);
+    }
+    if args.command == Command::Repair {
+        return repair_faces(args).map(status);
+    }
+    if args.command == Command::Diff {
+        return diff(args);
+    }
+    if args.command == Command::Convert {
+        return convert(args);
+    }
+    if args.command == Command::Boundary {
+        return boundary(args).map(|_| 0);
+    }
+
+    let permutation = {
+        let _timer = Timer::start("parse");
+        read_input(&args.input)?
+    };
+    let mut out = output(args)?;
+
+    if args.command == Command::Check {
+        return check(&permutation, &allowed_checks(args)?, args.format, out).map(status);
+    }
+
+    permutation::validate(&permutation).map_err(|e| CliError::Data {
+        message: format!("Invalid permutation: {}", e),
+        hint: Some(PERMUTATION_HINT),
+    })?;
+    let mesh = Mesh::from_permutation(&permutation);
+    match args.command {
+        Command::Dot => {
+            let node_label = match last_value(args, "--labels").unwrap_or("ids") {
+                "orbits" => NodeLabel::Orbit,
+                "ids" => NodeLabel::Id,
+                "none" => NodeLabel::None,
+                labels => return Err(labels_error(labels, "orbits, ids, none")),
+            };
+            let style = DotStyle {
+                node_label,
+                ..Default::default()
+            };
+            write_dot(&mesh, &mut out, &style)?
+        }
+        Command::Stats => {
+            let stats = mesh.stats();
+            if args.format == Format::Json {
+                // the members of MeshStats follow the schema version
+                writeln!(out, "{}", json_document(&stats.to_json_members()))?;
+            } else {
+                write!(out, "{}", stats)?;
+            }
+        }
+        Command::RenderSvg => {
+            let colors: Option<Vec<usize>> = if args.color {
+                let colors = mesh
+                    .face_coloring(PALETTE.len())
+                    .map_err(|e| CliError::Data {
+                        message: e.to_string(),
+                        hint: Some("Drop --color to draw faces in a single color"),
+                    })?;
+                Some(colors.into_iter().map(usize::from).collect())
+            } else {
+                None
+            };
+            let vertex_labels = match last_value(args, "--labels").unwrap_or("none") {
+                "ids" => true,
+                "none" => false,
+                labels => return Err(labels_error(labels, "ids, none")),
+            };
+            let style = SvgStyle {
+                face_colors: colors.as_deref(),
+                vertex_labels,
+                ..Default::default()
+            };
+            write_svg(&mesh, &mut out, &style)?
+        }
+        Command::Dual => {
+            let dual = permutation::dual(&permutation);
+            let mut text = Vec::new();
+            write_permutation(&dual, &mut text, "dual")?;
+            // check the output as written, so that the text format is covered too.
+            // The output is not relabeled, so the dual of the dual must be the input exactly.
+            if args.check
+                && !read_permutation(&text[..])
+                    .is_ok_and(|dual| permutation::dual(&dual) == permutation)
+            {
+                return Err(CliError::Data {
+                    message: "The dual of the output is not the input".to_string(),
+                    hint: None,
+                });
+            }
+            out.write_all(&text)?
+        }
+        Command::Subdivide => {
+            let scheme = match last_value(args, "--scheme").unwrap_or("loop") {
+                "loop" => subdivision::Scheme::Loop,
+                "catmull-clark" => subdivision::Scheme::CatmullClark,
+                "midpoint" => subdivision::Scheme::Midpoint,
+                scheme => {
+                    return Err(CliError::Usage(format!(
+                        "Unknown scheme '{}', expected one of loop, catmull-clark, midpoint",
+                        scheme
+                    )))
+                }
+            };
+            let steps = parameter(args, "--steps", 1)?;
+            let faces = subdivided_faces(mesh.num_faces(), permutation.len(), scheme, steps);
+            if faces.is_none_or(|faces| faces > MAX_SUBDIVIDED_FACES) {
+                return Err(CliError::Usage(format!(
+                    "--steps {} would give more than {} faces",
+                    steps, MAX_SUBDIVIDED_FACES
+                )));
+            }
+            let mut subdivided = permutation.clone();
+            for _ in 0..steps {
+                subdivided =
+                    subdivision::subdivide(&subdivided, scheme).map_err(|e| CliError::Data {
+                        message: e.to_string(),
+                        hint: None,
+                    })?;
+            }
+            eprintln!(
+                "subdivide: {} -> {}",
+                counts(&mesh),
+                counts(&Mesh::from_permutation(&subdivided))
+            );
+            write_permutation(&subdivided, &mut out, "subdivided")?
+        }
+        Command::Components => components(
+            &permutation,
+            last_value(args, "--split-prefix"),
+            args.format,
+            out,
+        )?,
+        Command::Query => query(
+            &mesh,
+            last_value(args, "--select").unwrap_or_default(),
+            args.operation.as_deref().unwrap_or_default(),
+            args.format,
+            out,
+        )?,
+        Command::Curvature => {
+            match last_value(args, "--kind").unwrap_or("gaussian") {
+                "gaussian" => {}
+                "mean" | "principal" => {
+                    return Err(CliError::Data {
+                        message: "Mean and principal curvatures require vertex positions"
+                            .to_string(),
+                        hint: Some("The input has only connectivity, use --kind gaussian"),
+                    })
+                }
+                kind => {
+                    return Err(CliError::Usage(format!(
+                        "Unknown kind '{}', expected one of gaussian, mean, principal",
+                        kind
+                    )))
+                }
+            }
+            let curvature = mesh.gaussian_curvature();
+            let total: f64 = curvature.iter().sum();
+            let chi = mesh.euler_characteristic();
+            eprintln!(
+                "total curvature: {:.6}, 2πχ = {:.6} (χ = {})",
+                total,
+                2.0 * std::f64::consts::PI * chi as f64,
+                chi
+            );
+            write_graphml(
+                &mesh,
+                &mut out,
+                &[umesh::io::Attribute::vertex(
+                    "gaussian_curvature",
+                    &curvature,
+                )],
+            )?
+        }
+        Command::Check
+        | Command::Generate
+        | Command::Repair
+        | Command::Diff
+        | Command::Convert
+        | Command::Boundary => unreachable!(),
+    }
+    Ok(0)
+}
+
+fn query(
+    mesh: &Mesh,
+    selection: &str,
+    operation: &str,
+    format: Format,
+    mut out: Box<dyn Write>,
+) -> Result<(), CliError> {
+    let selection = parse_selection(mesh, selection)
+        .map_err(|e| CliError::Usage(format!("Invalid selection: {}", e)))?;
+    let result = match operation {
+        "star" => selection.star(),
+        "closure" => selection.closure(),
+        "link" => selection.link(),
+        "boundary" => {
+            if selection.is_pure_complex().is_none() {
+                return Err(CliError::Data {
+                    message: "Boundary is defined only for a pure subcomplex".to_string(),
+                    hint: Some("Select only faces or only edges, and take the closure first"),
+                });
+            }
+            selection.boundary()
+        }
+        operation => {
+            return Err(CliError::Usage(format!(
+                "Unknown operation '{}', expected one of star, closure, link, boundary",
+                operation
+            )))
+        }
+    };
+    if format == Format::Json {
+        let ids = |ids: &SimplexSet| {
+            let ids: Vec<usize> = ids.iter().collect();
+            json_array(&ids, usize::to_string)
+        };
+        let members = format!(
+            r#""vertices":{},"edges":{},"faces":{}"#,
+            ids(result.vertices()),
+            ids(result.edges()),
+            ids(result.faces())
+        );
+        writeln!(out, "{}", json_document(&members))?;
+    } else {
+        writeln!(out, "{}", format_selection(&result))?;
+    }
+    Ok(())
+}
+
+/// Aligned table of the stages, the total wall time, and the peak topology memory,
+/// followed by the buffers of the largest mesh
+fn write_timings(report: &timing::Report, total: Duration, out: &mut dyn Write) -> io::Result<()> {
+    let milliseconds = |d: Duration| format!("{:.3} ms", d.as_secs_f64() * 1e3);
+    let mut rows: Vec<(String, String)> = report
+        .stages
+        .iter()
+        .map(|stage| {
+            let name = format!("{}{}", "  ".repeat(stage.depth), stage.name);
+            (name, milliseconds(stage.elapsed))
+        })
+        .collect();
+    rows.push(("total".to_string(), milliseconds(total)));
+    rows.push((
+        "peak topology memory".to_string(),
+        format!("{} bytes", report.peak_memory),
+    ));
+    let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let value_width = rows.iter().map(|(_, value)| value.len()).max().unwrap_or(0);
+    for (name, value) in &rows {
+        writeln!(
+            out,
+            "{:<nw$}  {:>vw$}",
+            name,
+            value,
+            nw = name_width,
+            vw = value_width
+        )?;
+    }
+    if !report.memory.buffers.is_empty() {
+        write!(out, "\n{}", report.memory)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            exit(if args.first().is_some_and(|command| command == "diff") {
+                DIFF_ERROR
+            } else {
+                1
+            });
+        }
+    };
+    if args.timings {
+        timing::enable();
+    }
+    let start = Instant::now();
+    let result = run(&args);
+    if args.timings {
+        // ignore the failure of stderr as eprintln does
+        let _ = write_timings(&timing::take(), start.elapsed(), &mut io::stderr());
+    }
+    match result {
+        Ok(0) => {}
+        Ok(code) => exit(code),
+        Err(e) => {
+            eprintln!("error: {}", e);
+            if let CliError::Usage(_) = e {
+                eprintln!("\n{}", USAGE);
+            }
+            if let Some(hint) = e.hint() {
+                eprintln!("hint: {}", hint);
+            }
+            exit(if args.command == Command::Diff {
+                DIFF_ERROR
+            } else {
+                e.exit_code()
+            });
+        }
+    }
+}
@@ -0,0 +1,433 @@
+/*!
+Incremental construction of a mesh face by face
+
+```
+use umesh::builder::MeshBuilder;
+
+// square pyramid without its bottom
+let mut builder = MeshBuilder::new(5);
+let apex = builder.add_vertex();
+let base: Vec<usize> = (0..4).map(|_| builder.add_vertex()).collect();
+for i in 0..4 {
+    builder.add_face(&[apex, base[i], base[(i + 1) % 4]]).unwrap();
+}
+let mesh = builder.build().unwrap();
+assert_eq!(mesh.num_boundary_loops(), 1);
+assert_eq!(mesh.vertex_edges(apex).len(), 4);
+```
+*/
+
+use crate::connection_matrix::Connection;
+use crate::flat::FlatError;
+use crate::half_edge::Mesh;
+use crate::permutation::{gather_faces, gather_vertices};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use core::fmt;
+
+/// Half-edge of each directed edge
+#[cfg(feature = "std")]
+type EdgeMap =
+    std::collections::HashMap<(usize, usize), usize, core::hash::BuildHasherDefault<EdgeHasher>>;
+/// Half-edge of each directed edge
+#[cfg(not(feature = "std"))]
+type EdgeMap = alloc::collections::BTreeMap<(usize, usize), usize>;
+
+/// Marker of a half-edge whose face is not added yet
+const UNSET: usize = usize::MAX;
+
+/// Multiplicative hash of vertex ids, much faster than the default SipHash for the edge map
+///
+/// It is not resistant to collisions crafted on purpose, which only slows down the builder.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+struct EdgeHasher(u64);
+
+#[cfg(feature = "std")]
+impl core::hash::Hasher for EdgeHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(u64::from(byte));
+        }
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.0 = (self.0.rotate_left(5) ^ i).wrapping_mul(0x517c_c1b7_2722_0a95);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Reason why a face cannot be added, or the mesh cannot be built
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    /// The face has less than two vertices
+    DegenerateFace { face: usize },
+    /// The face refers to a vertex which is not added
+    UnknownVertex { face: usize, vertex: usize },
+    /// The directed edge `from -> to` of the face already belongs to another face
+    DuplicatedEdge { face: usize, from: usize, to: usize },
+    /// The vertex belongs to no face
+    IsolatedVertex(usize),
+    /// The half-edge tables given to [Mesh::from_flat] are inconsistent
+    InvalidFlat(FlatError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::DegenerateFace { face } => {
+                write!(f, "Face {} has less than two vertices", face)
+            }
+            BuildError::UnknownVertex { face, vertex } => {
+                write!(f, "Face {} refers to vertex {} which is not added", face, vertex)
+            }
+            BuildError::DuplicatedEdge { face, from, to } => write!(
+                f,
+                "Directed edge {} -> {} of face {} belongs to another face, the surface would not be oriented or not a manifold",
+                from, to, face
+            ),
+            BuildError::IsolatedVertex(vertex) => {
+                write!(f, "Vertex {} belongs to no face", vertex)
+            }
+            BuildError::InvalidFlat(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl core::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            BuildError::InvalidFlat(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<FlatError> for BuildError {
+    fn from(e: FlatError) -> Self {
+        BuildError::InvalidFlat(e)
+    }
+}
+
+/// Mesh ids of the vertices and faces added to a [MeshBuilder]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Renumbering {
+    /// Mesh vertex id of each vertex of the builder
+    pub vertices: Vec<usize>,
+    /// Mesh face id of each face of the builder
+    pub faces: Vec<usize>,
+}
+
+/// Builder pairing the twin half-edges as faces are added
+///
+/// Edges are numbered in the order of first appearance as in [crate::from_faces].
+#[derive(Debug, Clone, Default)]
+pub struct MeshBuilder {
+    num_vertices: usize,
+    half_edges: EdgeMap,
+    /// Next half-edge in the face, or [UNSET]
+    next: Vec<usize>,
+    /// First half-edge of each face
+    face_starts: Vec<usize>,
+    /// Buffer of the half-edges of the face being added
+    scratch: Vec<usize>,
+}
+
+impl MeshBuilder {
+    /// Builder expecting about `num_vertices` vertices
+    pub fn new(num_vertices: usize) -> Self {
+        // Euler's formula of triangle meshes gives E ≈ 3V
+        let mut builder = MeshBuilder::default();
+        builder.reserve(6 * num_vertices);
+        builder
+    }
+
+    /// Reserve the space for `half_edges` more half-edges
+    pub(crate) fn reserve(&mut self, half_edges: usize) {
+        #[cfg(feature = "std")]
+        self.half_edges.reserve(half_edges);
+        self.next.reserve(half_edges);
+    }
+
+    pub fn add_vertex(&mut self) -> usize {
+        self.num_vertices += 1;
+        self.num_vertices - 1
+    }
+
+    /// Add a face with vertices in the counter-clockwise order, and returns its id
+    ///
+    /// The builder is unchanged if an error is returned.
+    pub fn add_face(&mut self, vertices: &[usize]) -> Result<usize, BuildError> {
+        let face = self.face_starts.len();
+        if let Some(&vertex) = vertices.iter().find(|&&v| v >= self.num_vertices) {
+            return Err(BuildError::UnknownVertex { face, vertex });
+        }
+        self.insert_face(vertices).map_err(|error| match error {
+            FaceError::Degenerate => BuildError::DegenerateFace { face },
+            FaceError::Duplicated { from, to } => BuildError::DuplicatedEdge { face, from, to },
+        })
+    }
+
+    /// Add a face without checking the vertex ids
+    pub(crate) fn insert_face(&mut self, vertices: &[usize]) -> Result<usize, FaceError> {
+        let n = vertices.len();
+        if n < 2 {
+            return Err(FaceError::Degenerate);
+        }
+        let directed = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+        // check all edges first to keep the builder unchanged on error
+        if let Some((from, to)) = (0..n)
+            .map(directed)
+            .find(|e| self.half_edges.contains_key(e))
+        {
+            return Err(FaceError::Duplicated { from, to });
+        }
+        if let Some((from, to)) = repeated_edge(vertices) {
+            return Err(FaceError::Duplicated { from, to });
+        }
+        let mut face_half_edges = core::mem::take(&mut self.scratch);
+        face_half_edges.clear();
+        for (from, to) in (0..n).map(directed) {
+            let h = match self.half_edges.get(&(to, from)) {
+                Some(&opposite) => opposite ^ 1,
+                None => {
+                    self.next.extend_from_slice(&[UNSET; 2]);
+                    self.next.len() - 2
+                }
+            };
+            self.half_edges.insert((from, to), h);
+            face_half_edges.push(h);
+        }
+        for i in 0..n {
+            self.next[face_half_edges[i]] = face_half_edges[(i + 1) % n];
+        }
+        self.face_starts.push(face_half_edges[0]);
+        self.scratch = face_half_edges;
+        Ok(self.face_starts.len() - 1)
+    }
+
+    /// Directed edge of a half-edge without face, if any
+    pub(crate) fn unpaired_edge(&self) -> Option<(usize, usize)> {
+        let h = self.next.iter().position(|&next| next == UNSET)?;
+        self.half_edges
+            .iter()
+            .find(|(_, &first)| first == h ^ 1)
+            .map(|(&(from, to), _)| (from, to))
+    }
+
+    /// Permutation on half-edges, valid only if [MeshBuilder::unpaired_edge] is `None`
+    pub(crate) fn into_permutation(self) -> Vec<usize> {
+        self.next
+    }
+
+    /// Build the mesh
+    ///
+    /// If every edge has two faces, the mesh is created by [Mesh::from_permutation],
+    /// which renumbers vertices and faces by [crate::gather_vertices] and [crate::gather_faces],
+    /// so the ids returned by [MeshBuilder::add_vertex] and [MeshBuilder::add_face] are not valid
+    /// in the mesh. Use [MeshBuilder::build_with_ids] to translate them.
+    /// Otherwise edges with one face become the boundary,
+    /// and the vertices and faces keep the ids given by the builder.
+    pub fn build(self) -> Result<Mesh, BuildError> {
+        let mut vertex_edge = Vec::with_capacity(self.next.len());
+        for (&(from, to), &h) in &self.half_edges {
+            if h % 2 == 0 {
+                vertex_edge.push((from, h / 2));
+                vertex_edge.push((to, h / 2));
+            }
+        }
+        let mut used = vec![false; self.num_vertices];
+        for &(v, _) in &vertex_edge {
+            used[v] = true;
+        }
+        if let Some(v) = used.iter().position(|&used| !used) {
+            return Err(BuildError::IsolatedVertex(v));
+        }
+        if self.unpaired_edge().is_none() {
+            return Ok(Mesh::from_permutation(&self.next));
+        }
+
+        let mut edge_face = Vec::with_capacity(self.next.len());
+        for (f, &start) in self.face_starts.iter().enumerate() {
+            let mut h = start;
+            loop {
+                edge_face.push((h / 2, f));
+                h = self.next[h];
+                if h == start {
+                    break;
+                }
+            }
+        }
+        Ok(Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        ))
+    }
+
+    /// [MeshBuilder::build] with the mesh ids of the vertices and faces of the builder
+    ///
+    /// The ids are unchanged unless the mesh is closed.
+    pub fn build_with_ids(self) -> Result<(Mesh, Renumbering), BuildError> {
+        let renumbering = if self.unpaired_edge().is_none() {
+            self.renumbering()
+        } else {
+            Renumbering {
+                vertices: (0..self.num_vertices).collect(),
+                faces: (0..self.face_starts.len()).collect(),
+            }
+        };
+        Ok((self.build()?, renumbering))
+    }
+
+    /// Ids given by [Mesh::from_permutation] to a closed mesh
+    fn renumbering(&self) -> Renumbering {
+        let mut origin = vec![0; self.next.len()];
+        for (&(from, _), &h) in &self.half_edges {
+            origin[h] = from;
+        }
+        // isolated vertices are left as is, and rejected by build
+        let mut vertices: Vec<usize> = (0..self.num_vertices).collect();
+        for (v, orbit) in gather_vertices(&self.next).iter().enumerate() {
+            vertices[origin[orbit.first()]] = v;
+        }
+        let mut face = vec![0; self.next.len()];
+        for (f, orbit) in gather_faces(&self.next).iter().enumerate() {
+            for h in orbit.indices() {
+                face[h] = f;
+            }
+        }
+        let faces = self.face_starts.iter().map(|&h| face[h]).collect();
+        Renumbering { vertices, faces }
+    }
+}
+
+/// Smallest directed edge appearing twice along the face
+fn repeated_edge(vertices: &[usize]) -> Option<(usize, usize)> {
+    let n = vertices.len();
+    let directed = |i: usize| (vertices[i], vertices[(i + 1) % n]);
+    if n <= 16 {
+        // faces are small, and comparing all pairs needs no allocation
+        return (0..n)
+            .filter(|&i| (0..i).any(|j| directed(j) == directed(i)))
+            .map(directed)
+            .min();
+    }
+    let mut sorted: Vec<(usize, usize)> = (0..n).map(directed).collect();
+    sorted.sort_unstable();
+    sorted
+        .windows(2)
+        .find(|pair| pair[0] == pair[1])
+        .map(|pair| pair[0])
+}
+
+/// Error of [MeshBuilder::insert_face] before the face id is known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum FaceError {
+    Degenerate,
+    Duplicated { from: usize, to: usize },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection_matrix::widen;
+    use crate::permutation::from_faces;
+
+    fn cube_faces() -> Vec<Vec<usize>> {
+        vec![
+            vec![0, 3, 2, 1],
+            vec![4, 5, 6, 7],
+            vec![0, 1, 5, 4],
+            vec![1, 2, 6, 5],
+            vec![2, 3, 7, 6],
+            vec![3, 0, 4, 7],
+        ]
+    }
+
+    #[test]
+    fn cube() {
+        let mut builder = MeshBuilder::new(8);
+        for v in 0..8 {
+            assert_eq!(builder.add_vertex(), v);
+        }
+        for (f, face) in cube_faces().iter().enumerate() {
+            assert_eq!(builder.add_face(face), Ok(f));
+        }
+        let mesh = builder.clone().build().unwrap();
+        let expected = from_faces(&cube_faces()).unwrap();
+        assert_eq!(widen(mesh.permutation().unwrap()), expected);
+        assert_eq!(mesh.genus(), Some(0));
+
+        let (renumbered, ids) = builder.build_with_ids().unwrap();
+        assert_eq!(renumbered.permutation(), mesh.permutation());
+        let sorted = |mut vertices: Vec<usize>| {
+            vertices.sort_unstable();
+            vertices
+        };
+        for (f, face) in cube_faces().iter().enumerate() {
+            let vertices = face.iter().map(|&v| ids.vertices[v]).collect();
+            assert_eq!(
+                sorted(mesh.face_vertices(ids.faces[f])),
+                sorted(vertices),
+                "face {}",
+                f
+            );
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let mut builder = MeshBuilder::new(8);
+        (0..8).for_each(|_| {
+            builder.add_vertex();
+        });
+        builder.add_face(&cube_faces()[0]).unwrap();
+        assert_eq!(
+            builder.add_face(&[1, 0, 3, 2]),
+            Err(BuildError::DuplicatedEdge {
+                face: 1,
+                from: 1,
+                to: 0
+            })
+        );
+        assert_eq!(
+            builder.add_face(&[0, 1, 8]),
+            Err(BuildError::UnknownVertex { face: 1, vertex: 8 })
+        );
+        assert_eq!(
+            builder.add_face(&[4]),
+            Err(BuildError::DegenerateFace { face: 1 })
+        );
+        assert!(builder.add_face(&[4, 5, 4, 5]).is_err());
+        assert_eq!(builder.add_face(&cube_faces()[1]), Ok(1));
+        assert_eq!(builder.add_vertex(), 8);
+        assert_eq!(builder.build().unwrap_err(), BuildError::IsolatedVertex(8));
+    }
+
+    #[test]
+    fn boundary() {
+        // cube without the face 0, keeping the ids of the builder
+        let mut builder = MeshBuilder::new(8);
+        (0..8).for_each(|_| {
+            builder.add_vertex();
+        });
+        for face in &cube_faces()[1..] {
+            builder.add_face(face).unwrap();
+        }
+        let (mesh, ids) = builder.build_with_ids().unwrap();
+        assert_eq!(ids.vertices, (0..8).collect::<Vec<_>>());
+        assert_eq!(ids.faces, (0..5).collect::<Vec<_>>());
+        assert_eq!(mesh.permutation(), None);
+        assert_eq!(mesh.num_faces(), 5);
+        assert_eq!(mesh.boundary_loops().len(), 1);
+        assert_eq!(mesh.face_vertices(0).len(), 4);
+        assert_eq!(mesh.genus(), Some(0));
+    }
+}
@@ -0,0 +1,155 @@
+/*!
+Validation of a mesh as a list of named checks
+*/
+
+use crate::connection_matrix::widen;
+use crate::half_edge::Mesh;
+use crate::permutation::gather_faces;
+use crate::timing::Timer;
+
+/// Names of the checks in the order of [Mesh::check]
+pub const CHECKS: [&str; 5] = ["invariants", "manifold", "boundary", "degenerate", "euler"];
+
+/// Result of a check with the descriptions of found problems
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub problems: Vec<String>,
+}
+
+impl Check {
+    pub fn passed(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Mesh {
+    /// Run all checks listed in [CHECKS]
+    ///
+    /// - `invariants`: see [Mesh::check_invariants]
+    /// - `manifold`: edges with more than two faces, and vertices with several fans of faces
+    /// - `boundary`: edges with only one face
+    /// - `degenerate`: self-loops, faces with less than three edges,
+    ///   and edges with the same face on both sides
+    /// - `euler`: the Euler characteristic is consistent with the components and boundary loops
+    pub fn check(&self) -> Vec<Check> {
+        let _timer = Timer::start("check");
+        let invariants = self.check_invariants().err().unwrap_or_default();
+
+        let mut manifold = Vec::new();
+        for e in 0..self.num_edges() {
+            let n = self.edge_faces(e).len();
+            if n == 0 || n > 2 {
+                manifold.push(format!("edge {} has {} faces", e, n));
+            }
+        }
+        for v in 0..self.num_vertices() {
+            let n = self.num_fans(v);
+            if n > 1 {
+                manifold.push(format!("vertex {} joins {} fans of faces", v, n));
+            }
+        }
+
+        let boundary = self
+            .boundary_edges()
+            .into_iter()
+            .map(|e| format!("edge {} is on the boundary", e))
+            .collect();
+
+        let mut degenerate = Vec::new();
+        for e in 0..self.num_edges() {
+            if let [v] = self.edge_vertices(e) {
+                degenerate.push(format!("edge {} is a self-loop at vertex {}", e, v));
+            }
+        }
+        for f in 0..self.num_faces() {
+            let n = self.face_edges(f).len();
+            if n < 3 {
+                degenerate.push(format!("face {} has {} edges", f, n));
+            }
+        }
+        if let Some(permutation) = self.permutation() {
+            let mut face = vec![0; permutation.len()];
+            for (f, orbit) in gather_faces(&widen(permutation)).iter().enumerate() {
+                for h in orbit.indices() {
+                    face[h] = f;
+                }
+            }
+            for e in 0..permutation.len() / 2 {
+                if face[2 * e] == face[2 * e + 1] {
+                    degenerate.push(format!("edge {} has face {} on both sides", e, face[2 * e]));
+                }
+            }
+        }
+
+        let mut euler = Vec::new();
+        if manifold.is_empty() && self.genus().is_none() {
+            euler.push(format!(
+                "χ = {} is inconsistent with {} components and {} boundary loops",
+                self.euler_characteristic(),
+                self.num_components(),
+                self.num_boundary_loops()
+            ));
+        }
+
+        CHECKS
+            .iter()
+            .zip(vec![invariants, manifold, boundary, degenerate, euler])
+            .map(|(&name, problems)| Check { name, problems })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection_matrix::Connection;
+    use crate::fixtures;
+
+    fn problems(mesh: &Mesh, name: &str) -> Vec<String> {
+        let checks = mesh.check();
+        assert_eq!(checks.len(), CHECKS.len());
+        checks
+            .into_iter()
+            .find(|check| check.name == name)
+            .unwrap()
+            .problems
+    }
+
+    #[test]
+    fn cube() {
+        let mesh = fixtures::CUBE.mesh();
+        assert!(mesh.check().iter().all(Check::passed));
+    }
+
+    #[test]
+    fn degenerate() {
+        // a single face bounded by both sides of one edge
+        let mesh = Mesh::from_permutation(&[1, 0]);
+        assert_eq!(
+            problems(&mesh, "degenerate"),
+            vec!["face 0 has 1 edges", "edge 0 has face 0 on both sides"]
+        );
+        assert!(problems(&mesh, "manifold").is_empty());
+    }
+
+    #[test]
+    fn non_manifold() {
+        // three triangles sharing the edge 0 between vertices 0 and 1
+        let mut vertex_edge = vec![(0, 0), (1, 0)];
+        let mut edge_face = vec![(0, 0), (0, 1), (0, 2)];
+        for f in 0..3 {
+            let apex = 2 + f;
+            let (e1, e2) = (1 + 2 * f, 2 + 2 * f);
+            vertex_edge.extend(vec![(0, e1), (apex, e1), (1, e2), (apex, e2)]);
+            edge_face.extend(vec![(e1, f), (e2, f)]);
+        }
+        let mesh = Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        );
+        assert_eq!(problems(&mesh, "manifold"), vec!["edge 0 has 3 faces"]);
+        assert_eq!(problems(&mesh, "boundary").len(), 6);
+        assert!(problems(&mesh, "invariants").is_empty());
+    }
+}
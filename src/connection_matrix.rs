@@ -1,10 +1,119 @@
-use std::collections::BTreeSet;
+use crate::error::Error;
+use crate::memory::MemoryReport;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use alloc::borrow::Cow;
+use alloc::collections::BTreeSet;
+use core::fmt;
+
+#[cfg(not(feature = "u32-index"))]
+type Repr = usize;
+#[cfg(feature = "u32-index")]
+type Repr = u32;
+
+/// Index stored in the connection matrices and the permutation of [crate::Mesh]
+///
+/// It holds a `u32` with the `u32-index` feature to halve the memory, and a `usize` otherwise.
+/// The integer is not exposed, so code using it compiles the same with and without the feature;
+/// convert it with [Index::get] or [to_usize], and whole rows with [widen].
+#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
+pub struct Index(Repr);
+
+impl Index {
+    /// The index as `usize`
+    #[inline]
+    #[allow(clippy::unnecessary_cast)]
+    pub fn get(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<Index> for usize {
+    #[inline]
+    fn from(i: Index) -> usize {
+        i.get()
+    }
+}
+
+impl fmt::Debug for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for Index {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// [Error::OutOfRange] if `len` entries of `kind` do not fit in [Index]
+#[cfg(not(feature = "u32-index"))]
+pub(crate) fn check_len(_kind: &'static str, _len: usize) -> Result<(), Error> {
+    Ok(())
+}
+
+/// [Error::OutOfRange] if `len` entries of `kind` do not fit in [Index]
+#[cfg(feature = "u32-index")]
+pub(crate) fn check_len(kind: &'static str, len: usize) -> Result<(), Error> {
+    let max = u32::MAX as usize;
+    if len > max {
+        return Err(Error::OutOfRange {
+            kind,
+            index: len,
+            len: max,
+        });
+    }
+    Ok(())
+}
+
+/// Convert into [Index], panics if it does not fit
+#[cfg(not(feature = "u32-index"))]
+#[inline]
+pub fn to_index(i: usize) -> Index {
+    Index(i)
+}
+
+/// Convert into [Index], panics if it does not fit
+#[cfg(feature = "u32-index")]
+#[inline]
+pub fn to_index(i: usize) -> Index {
+    match core::convert::TryFrom::try_from(i) {
+        Ok(i) => Index(i),
+        Err(_) => panic!(
+            "Index {} exceeds u32::MAX, disable the u32-index feature",
+            i
+        ),
+    }
+}
+
+/// Convert from [Index]
+#[inline]
+pub fn to_usize(i: Index) -> usize {
+    i.get()
+}
+
+/// Stored indices as `usize`, borrowed without the `u32-index` feature
+#[cfg(not(feature = "u32-index"))]
+pub fn widen(indices: &[Index]) -> Cow<'_, [usize]> {
+    // Safety: `Index` is a `repr(transparent)` wrapper of `usize` without the feature
+    Cow::Borrowed(unsafe {
+        core::slice::from_raw_parts(indices.as_ptr() as *const usize, indices.len())
+    })
+}
+
+/// Stored indices as `usize`, borrowed without the `u32-index` feature
+#[cfg(feature = "u32-index")]
+pub fn widen(indices: &[Index]) -> Cow<'_, [usize]> {
+    Cow::Owned(indices.iter().map(|&i| to_usize(i)).collect())
+}
 
 /// Sorted indices of a connection (equal to CRS format in sparce matrices without elements)
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Connection {
-    fr: Vec<usize>,
-    to: Vec<usize>,
+    fr: Vec<Index>,
+    to: Vec<Index>,
     to_max: usize,
 }
 
@@ -20,41 +129,118 @@ impl Connection {
     }
 
     /// Create connection matrix from a series of pair of indices.
-    /// The indices will be sorted.
-    pub fn from_vec(mut indices: Vec<(usize, usize)>) -> Self {
-        indices.sort_unstable();
-        unsafe { Self::from_sorted_vec(indices) }
+    /// The indices will be sorted, and duplicated pairs are merged.
+    ///
+    /// The pairs are bucketed by row with a counting sort, and only each row is sorted,
+    /// which is linear for the rows of bounded length in meshes.
+    ///
+    /// Panics if an index does not fit in [Index], see [Connection::try_from_vec].
+    pub fn from_vec(indices: Vec<(usize, usize)>) -> Self {
+        let rows = indices.iter().map(|&(f, _)| f + 1).max().unwrap_or(0);
+        // start of each row, and then the position to fill next
+        let mut next = vec![0; rows + 1];
+        for &(f, _) in &indices {
+            next[f + 1] += 1;
+        }
+        for f in 0..rows {
+            next[f + 1] += next[f];
+        }
+        let starts = next.clone();
+        let mut to = vec![to_index(0); indices.len()];
+        let mut to_max = 0;
+        for (f, t) in indices {
+            to[next[f]] = to_index(t);
+            next[f] += 1;
+            to_max = core::cmp::max(to_max, t);
+        }
+
+        let mut fr = Vec::with_capacity(rows + 1);
+        fr.push(to_index(0));
+        let mut len = 0;
+        for f in 0..rows {
+            to[starts[f]..starts[f + 1]].sort_unstable();
+            for i in starts[f]..starts[f + 1] {
+                if i == starts[f] || to[i] != to[i - 1] {
+                    to[len] = to[i];
+                    len += 1;
+                }
+            }
+            fr.push(to_index(len));
+        }
+        if len < to.len() {
+            to.truncate(len);
+            to.shrink_to_fit();
+        }
+        if to.is_empty() {
+            fr.truncate(1);
+        }
+        Connection { fr, to, to_max }
+    }
+
+    /// [Connection::from_vec] returning [Error::OutOfRange] if the pairs or an index do not fit in [Index]
+    pub fn try_from_vec(indices: Vec<(usize, usize)>) -> Result<Self, Error> {
+        check_len("pair", indices.len())?;
+        let max = indices
+            .iter()
+            .map(|&(f, t)| core::cmp::max(f, t))
+            .max()
+            .unwrap_or(0);
+        check_len("index", max)?;
+        Ok(Self::from_vec(indices))
     }
 
     /// Create connection matrix from a series of pair of indices without sorting.
+    /// Duplicated pairs are merged as in [Connection::from_vec].
     ///
     /// Safety
     /// ------
     /// - unsafe if the input indices are not sorted
     pub unsafe fn from_sorted_vec(indices: Vec<(usize, usize)>) -> Self {
+        let rows = indices.last().map_or(0, |&(f, _)| f + 1);
         let mut to = Vec::with_capacity(indices.len());
-        let mut fr = vec![0];
+        let mut fr = Vec::with_capacity(rows + 1);
+        fr.push(to_index(0));
         let mut current_fr = 0;
         let mut to_max = 0;
+        let mut previous = (0, 0);
         for (n, (f, t)) in indices.into_iter().enumerate() {
             while f != current_fr {
-                fr.push(n);
+                fr.push(to_index(to.len()));
                 current_fr += 1;
             }
-            to_max = std::cmp::max(to_max, t);
-            to.push(t);
+            if n > 0 && previous == (f, t) {
+                continue;
+            }
+            previous = (f, t);
+            to_max = core::cmp::max(to_max, t);
+            to.push(to_index(t));
+        }
+        if !to.is_empty() {
+            fr.push(to_index(to.len()));
         }
-        fr.push(to.len());
         Connection { fr, to, to_max }
     }
 
     /// Get connected indices
-    pub fn get_connected(&self, from_index: usize) -> &[usize] {
-        let first = self.fr[from_index];
-        let last = self.fr[from_index + 1];
+    pub fn get_connected(&self, from_index: usize) -> &[Index] {
+        let first = to_usize(self.fr[from_index]);
+        let last = to_usize(self.fr[from_index + 1]);
         &self.to[first..last]
     }
 
+    /// [Connection::get_connected] returning [Error::OutOfRange] for a row which does not exist
+    pub fn try_get_connected(&self, from_index: usize) -> Result<&[Index], Error> {
+        let rows = self.fr.len() - 1;
+        if from_index >= rows {
+            return Err(Error::OutOfRange {
+                kind: "row",
+                index: from_index,
+                len: rows,
+            });
+        }
+        Ok(self.get_connected(from_index))
+    }
+
     /// Get connected indices of input indices
     pub fn gather_connected<Iter: Iterator<Item = usize>>(
         &self,
@@ -62,18 +248,34 @@ impl Connection {
     ) -> BTreeSet<usize> {
         from_indices
             .flat_map(|from_index| self.get_connected(from_index).iter())
-            .cloned()
+            .map(|&i| to_usize(i))
             .collect()
     }
 
+    /// Heap memory of the row offsets `fr` and the indices `to`
+    pub fn memory_usage(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        report.push("fr", &self.fr);
+        report.push("to", &self.to);
+        report
+    }
+
+    /// Release the capacity beyond the stored indices
+    pub fn shrink_to_fit(&mut self) {
+        self.fr.shrink_to_fit();
+        self.to.shrink_to_fit();
+    }
+
     pub fn shape(&self) -> (usize, usize) {
+        if self.to.is_empty() {
+            return (0, 0);
+        }
         (self.fr.len() - 1, self.to_max + 1)
     }
 
-    pub fn indices(&self) -> IndexIter {
+    pub fn indices(&self) -> IndexIter<'_> {
         IndexIter {
             f_index: 0,
-            f_count: 0,
             t_index: 0,
             fr: &self.fr,
             to: &self.to,
@@ -83,14 +285,24 @@ impl Connection {
     pub fn transpose(&self) -> Self {
         Self::from_iter(self.indices().map(|(f, t)| (t, f)))
     }
+
+    /// Composition `(i, k)` for all `(i, j)` in `self` and `(j, k)` in `other`,
+    /// i.e. non-zero pattern of the matrix product
+    pub fn compose(&self, other: &Connection) -> Self {
+        Self::from_iter(self.indices().flat_map(|(i, j)| {
+            other
+                .get_connected(j)
+                .iter()
+                .map(move |&k| (i, to_usize(k)))
+        }))
+    }
 }
 
 pub struct IndexIter<'mat> {
     f_index: usize,
-    f_count: usize,
     t_index: usize,
-    fr: &'mat [usize],
-    to: &'mat [usize],
+    fr: &'mat [Index],
+    to: &'mat [Index],
 }
 
 impl<'mat> Iterator for IndexIter<'mat> {
@@ -99,22 +311,43 @@ impl<'mat> Iterator for IndexIter<'mat> {
         if self.t_index >= self.to.len() {
             return None;
         }
-        // Decompress from index
-        let f = self.f_index;
-        self.f_count += 1;
-        if self.f_count >= self.fr[self.f_index] {
-            self.f_count = 0;
+        // Decompress from index, skipping empty rows
+        while to_usize(self.fr[self.f_index + 1]) <= self.t_index {
             self.f_index += 1;
         }
-        let t = self.to[self.t_index];
+        let t = to_usize(self.to[self.t_index]);
         self.t_index += 1;
-        Some((f, t))
+        Some((self.f_index, t))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn try_from_vec() {
+        let mat = Connection::try_from_vec(vec![(1, 0), (0, 1)]).unwrap();
+        assert_eq!(mat, Connection::from_vec(vec![(0, 1), (1, 0)]));
+        assert_eq!(widen(mat.get_connected(0)), vec![1]);
+    }
+
+    #[cfg(feature = "u32-index")]
+    #[test]
+    fn too_large_for_u32_index() {
+        assert!(check_len("half-edge", u32::MAX as usize).is_ok());
+        let e = check_len("half-edge", u32::MAX as usize + 1).unwrap_err();
+        assert!(matches!(
+            e,
+            Error::OutOfRange {
+                kind: "half-edge",
+                index: 4294967296,
+                len: 4294967295
+            }
+        ));
+        let e = Connection::try_from_vec(vec![(0, u32::MAX as usize + 1)]).unwrap_err();
+        assert!(matches!(e, Error::OutOfRange { kind: "index", .. }));
+    }
     #[test]
     fn square() {
         // 1 0 1 0
@@ -132,8 +365,8 @@ mod tests {
             (3, 3),
         ]);
         dbg!(&mat);
-        assert_eq!(mat.fr, vec![0, 2, 4, 6, 8]);
-        assert_eq!(mat.to, vec![0, 2, 0, 1, 1, 3, 0, 3]);
+        assert_eq!(widen(&mat.fr), vec![0, 2, 4, 6, 8]);
+        assert_eq!(widen(&mat.to), vec![0, 2, 0, 1, 1, 3, 0, 3]);
         assert_eq!(mat.shape(), (4, 4));
     }
 
@@ -144,8 +377,8 @@ mod tests {
         // 1 0 0 1
         let mat = Connection::from_vec(vec![(0, 0), (0, 2), (1, 1), (1, 3), (2, 0), (2, 3)]);
         dbg!(&mat);
-        assert_eq!(mat.fr, vec![0, 2, 4, 6]);
-        assert_eq!(mat.to, vec![0, 2, 1, 3, 0, 3]);
+        assert_eq!(widen(&mat.fr), vec![0, 2, 4, 6]);
+        assert_eq!(widen(&mat.to), vec![0, 2, 1, 3, 0, 3]);
         assert_eq!(mat.shape(), (3, 4));
     }
 
@@ -157,11 +390,19 @@ mod tests {
         // 1 0 0 1
         let mat = Connection::from_vec(vec![(0, 0), (0, 2), (2, 1), (2, 3), (3, 0), (3, 3)]);
         dbg!(&mat);
-        assert_eq!(mat.fr, vec![0, 2, 2, 4, 6]);
-        assert_eq!(mat.to, vec![0, 2, 1, 3, 0, 3]);
+        assert_eq!(widen(&mat.fr), vec![0, 2, 2, 4, 6]);
+        assert_eq!(widen(&mat.to), vec![0, 2, 1, 3, 0, 3]);
         assert_eq!(mat.shape(), (4, 4));
     }
 
+    #[test]
+    fn empty() {
+        let mat = Connection::from_vec(Vec::new());
+        assert_eq!(mat.shape(), (0, 0));
+        assert_eq!(mat.indices().count(), 0);
+        assert_eq!(mat.transpose(), mat);
+    }
+
     #[test]
     fn indices_iter() {
         // 1 0 0 0
@@ -172,4 +413,78 @@ mod tests {
         let mat2 = Connection::from_iter(mat.indices());
         assert_eq!(mat, mat2);
     }
+
+    #[test]
+    fn indices_iter_empty_row() {
+        // 1 0 1 0
+        // 0 0 0 0
+        // 0 1 0 1
+        let mat = Connection::from_vec(vec![(0, 0), (0, 2), (2, 1), (2, 3)]);
+        let indices: Vec<_> = mat.indices().collect();
+        assert_eq!(indices, vec![(0, 0), (0, 2), (2, 1), (2, 3)]);
+    }
+
+    #[test]
+    fn duplicated() {
+        let mat = Connection::from_vec(vec![(0, 1), (1, 0), (0, 1)]);
+        assert_eq!(widen(&mat.to), vec![1, 0]);
+        let sorted = unsafe { Connection::from_sorted_vec(vec![(0, 1), (0, 1), (1, 0)]) };
+        assert_eq!(sorted, mat);
+    }
+
+    #[test]
+    fn same_as_sorted() {
+        // pseudo-random pairs with duplicates and empty rows
+        let mut state = 12345_usize;
+        let mut random = |n: usize| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % n
+        };
+        for len in [1, 2, 10, 100, 1000] {
+            let indices: Vec<_> = (0..len)
+                .map(|_| (random(len / 3 + 1), random(len / 2 + 1)))
+                .collect();
+            let mut sorted = indices.clone();
+            sorted.sort_unstable();
+            let expected = unsafe { Connection::from_sorted_vec(sorted) };
+            assert_eq!(Connection::from_vec(indices), expected);
+        }
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mut mat = Connection::from_vec(vec![(0, 0), (0, 2), (2, 1)]);
+        let size = core::mem::size_of::<Index>();
+        let report = mat.memory_usage();
+        assert_eq!(report.used(), (4 + 3) * size);
+        assert_eq!(report.allocated(), report.used());
+
+        mat.to.reserve(100);
+        assert!(mat.memory_usage().allocated() >= (4 + 103) * size);
+        mat.shrink_to_fit();
+        assert_eq!(mat.memory_usage().allocated(), (4 + 3) * size);
+    }
+
+    #[test]
+    fn transpose() {
+        // 1 1 0
+        // 0 0 0
+        // 0 1 1
+        let mat = Connection::from_vec(vec![(0, 0), (0, 1), (2, 1), (2, 2)]);
+        let t = Connection::from_vec(vec![(0, 0), (1, 0), (1, 2), (2, 2)]);
+        assert_eq!(mat.transpose(), t);
+        assert_eq!(mat.transpose().transpose(), mat);
+    }
+
+    #[test]
+    fn compose() {
+        // path graph 0 - 1 - 2 as vertex-edge incidence
+        let ve = Connection::from_vec(vec![(0, 0), (1, 0), (1, 1), (2, 1)]);
+        let adj = ve.compose(&ve.transpose());
+        let expected =
+            Connection::from_vec(vec![(0, 0), (0, 1), (1, 0), (1, 1), (1, 2), (2, 1), (2, 2)]);
+        assert_eq!(adj, expected);
+    }
 }
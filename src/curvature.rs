@@ -0,0 +1,87 @@
+/*!
+Combinatorial curvature of a mesh without positions
+
+Every face is regarded as a regular polygon with unit edges, so that
+a corner of a face with `k` edges has the angle `(k - 2)π / k`.
+The Gaussian curvature of a vertex is its angle defect,
+`2π` minus the sum of the corner angles at an interior vertex
+and `π` minus the sum at a boundary vertex,
+and the total satisfies the Gauss-Bonnet theorem `Σ K = 2πχ` exactly.
+*/
+
+use crate::half_edge::Mesh;
+use std::f64::consts::PI;
+
+impl Mesh {
+    /// Angle defect of each vertex with regular faces
+    pub fn gaussian_curvature(&self) -> Vec<f64> {
+        let mut curvature = vec![2.0 * PI; self.num_vertices()];
+        for e in self.boundary_edges() {
+            let (a, b) = self.edge_ends(e);
+            curvature[a] = PI;
+            curvature[b] = PI;
+        }
+        for f in 0..self.num_faces() {
+            let vertices = self.face_vertices(f);
+            let k = vertices.len() as f64;
+            for v in vertices {
+                curvature[v] -= (k - 2.0) * PI / k;
+            }
+        }
+        curvature
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connection_matrix::Connection, shapes};
+
+    fn assert_gauss_bonnet(mesh: &Mesh) {
+        let total: f64 = mesh.gaussian_curvature().iter().sum();
+        let expected = 2.0 * PI * mesh.euler_characteristic() as f64;
+        assert!((total - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn regular() {
+        let mesh = Mesh::from_permutation(&shapes::cube());
+        for k in mesh.gaussian_curvature() {
+            assert!((k - PI / 2.0).abs() < 1e-12);
+        }
+        let mesh = Mesh::from_permutation(&shapes::torus(6, 4).unwrap());
+        assert!(mesh.gaussian_curvature().iter().all(|k| k.abs() < 1e-12));
+    }
+
+    #[test]
+    fn gauss_bonnet() {
+        assert_gauss_bonnet(&Mesh::from_permutation(&shapes::icosphere(2).unwrap()));
+        assert_gauss_bonnet(&Mesh::from_permutation(
+            &shapes::random_triangulation(40, 3).unwrap(),
+        ));
+    }
+
+    #[test]
+    fn boundary() {
+        // single square, whose corners turn by π/2 along the boundary
+        let vertex_edge = vec![
+            (0, 0),
+            (1, 0),
+            (1, 1),
+            (2, 1),
+            (2, 2),
+            (3, 2),
+            (3, 3),
+            (0, 3),
+        ];
+        let edge_face = vec![(0, 0), (1, 0), (2, 0), (3, 0)];
+        let mesh = Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        );
+        for k in mesh.gaussian_curvature() {
+            assert!((k - PI / 2.0).abs() < 1e-12);
+        }
+        assert_gauss_bonnet(&mesh);
+    }
+}
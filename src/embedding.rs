@@ -0,0 +1,167 @@
+/*!
+Planar embedding of a mesh with the topology of a disk or a sphere
+*/
+
+use crate::connection_matrix::to_usize;
+use crate::half_edge::Mesh;
+use crate::timing::Timer;
+use std::f64::consts::PI;
+
+const MAX_ITERATIONS: usize = 10_000;
+const TOLERANCE: f64 = 1e-10;
+
+impl Mesh {
+    /// Tutte embedding into the unit disk
+    ///
+    /// The vertices of the boundary loop are placed on the unit circle at equal intervals,
+    /// and each interior vertex is placed at the average of its neighbors.
+    /// A closed mesh is embedded by taking face 0 as the outer boundary.
+    ///
+    /// Returns `None` unless the mesh is a connected manifold of genus zero
+    /// with at most one boundary loop.
+    pub fn tutte_embedding(&self) -> Option<Vec<[f64; 2]>> {
+        let _timer = Timer::start("tutte_embedding");
+        if self.num_components() != 1 || self.genus() != Some(0) {
+            return None;
+        }
+        let outer = match self.num_boundary_loops() {
+            0 if self.num_faces() > 0 => self.face_vertices(0),
+            1 => self.boundary_loops().swap_remove(0),
+            _ => return None,
+        };
+
+        let n = self.num_vertices();
+        let mut positions = vec![[0.0; 2]; n];
+        let mut fixed = vec![false; n];
+        for (i, &v) in outer.iter().enumerate() {
+            let angle = 2.0 * PI * i as f64 / outer.len() as f64;
+            positions[v] = [angle.cos(), angle.sin()];
+            fixed[v] = true;
+        }
+
+        // Gauss-Seidel iteration of the discrete Laplace equation
+        let adjacency = self.vertex_adjacency();
+        for _ in 0..MAX_ITERATIONS {
+            let mut delta: f64 = 0.0;
+            for v in (0..n).filter(|&v| !fixed[v]) {
+                let neighbors = adjacency.get_connected(v);
+                let mut average = [0.0; 2];
+                for &w in neighbors {
+                    let w = to_usize(w);
+                    average[0] += positions[w][0] / neighbors.len() as f64;
+                    average[1] += positions[w][1] / neighbors.len() as f64;
+                }
+                delta = delta
+                    .max((average[0] - positions[v][0]).abs())
+                    .max((average[1] - positions[v][1]).abs());
+                positions[v] = average;
+            }
+            if delta < TOLERANCE {
+                break;
+            }
+        }
+        Some(positions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection_matrix::Connection;
+    use crate::fixtures;
+    use crate::permutation::{gather_faces, gather_vertices};
+    use crate::predicates::orient2d;
+
+    fn assert_close(a: [f64; 2], b: [f64; 2]) {
+        assert!((a[0] - b[0]).abs() < 1e-8 && (a[1] - b[1]).abs() < 1e-8);
+    }
+
+    #[test]
+    fn tetrahedron() {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        let positions = mesh.tutte_embedding().unwrap();
+        let outer = mesh.face_vertices(0);
+        assert_eq!(outer.len(), 3);
+        let inner = (0..4).find(|v| !outer.contains(v)).unwrap();
+        assert_close(positions[inner], [0.0, 0.0]);
+        for v in outer {
+            let [x, y] = positions[v];
+            assert!((x * x + y * y - 1.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn no_flipped_faces() {
+        // Tutte's theorem: the inner faces are convex, and none is flipped against the others
+        let permutation = fixtures::ICOSAHEDRON.permutation().unwrap();
+        let mesh = Mesh::from_permutation(&permutation);
+        let positions = mesh.tutte_embedding().unwrap();
+        let mut vertex = vec![0; permutation.len()];
+        for (v, orbit) in gather_vertices(&permutation).iter().enumerate() {
+            for h in orbit.indices() {
+                vertex[h] = v;
+            }
+        }
+        // the cyclic order of the half-edges is the orientation of the face
+        let orientation: Vec<f64> = gather_faces(&permutation)
+            .iter()
+            .map(|orbit| {
+                let h = orbit.first();
+                let [a, b, c] = [h, permutation[h], permutation[permutation[h]]];
+                orient2d(
+                    positions[vertex[a]],
+                    positions[vertex[b]],
+                    positions[vertex[c]],
+                )
+            })
+            .collect();
+        assert_ne!(orientation[0], 0.0);
+        assert!(orientation[1..]
+            .iter()
+            .all(|&o| o.signum() == -orientation[0].signum()));
+    }
+
+    #[test]
+    fn grid() {
+        // 3x3 vertices, 2x2 quads
+        let mut vertex_edge = Vec::new();
+        for (e, &(a, b)) in [
+            (0, 1),
+            (1, 2),
+            (3, 4),
+            (4, 5),
+            (6, 7),
+            (7, 8),
+            (0, 3),
+            (1, 4),
+            (2, 5),
+            (3, 6),
+            (4, 7),
+            (5, 8),
+        ]
+        .iter()
+        .enumerate()
+        {
+            vertex_edge.push((a, e));
+            vertex_edge.push((b, e));
+        }
+        let mut edge_face = Vec::new();
+        for (f, edges) in [[0, 2, 6, 7], [1, 3, 7, 8], [2, 4, 9, 10], [3, 5, 10, 11]]
+            .iter()
+            .enumerate()
+        {
+            edge_face.extend(edges.iter().map(|&e| (e, f)));
+        }
+        let mesh = Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        );
+        let positions = mesh.tutte_embedding().unwrap();
+        assert_close(positions[4], [0.0, 0.0]);
+    }
+
+    #[test]
+    fn not_embeddable() {
+        assert!(fixtures::TWO_TETRAHEDRA.mesh().tutte_embedding().is_none());
+    }
+}
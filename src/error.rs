@@ -0,0 +1,297 @@
+/*!
+Error type of the whole crate
+
+Each module reports its own error type, e.g. [PermutationError] or [BuildError],
+and [Error] collects them for callers using several modules through `?`.
+The `try_` variants of the constructors and queries, e.g. [Mesh::try_from_permutation],
+return [Error] where the plain ones panic.
+
+[Mesh::try_from_permutation]: crate::Mesh::try_from_permutation
+*/
+
+use crate::builder::BuildError;
+use crate::permutation::{FaceListError, PermutationError};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use core::fmt;
+
+#[derive(Debug)]
+pub enum Error {
+    /// Input is not a permutation on half-edges
+    InvalidPermutation(PermutationError),
+    /// Faces do not form a closed oriented surface
+    InvalidFaces(FaceListError),
+    /// Incremental construction by [crate::builder::MeshBuilder] failed
+    Build(BuildError),
+    /// Sizes which must agree differ
+    ShapeMismatch {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// Id of a vertex, edge, face, or row is not less than the number of them
+    OutOfRange {
+        kind: &'static str,
+        index: usize,
+        len: usize,
+    },
+    /// The operation requires every edge to be shared by two faces
+    NotClosed {
+        /// Number of edges not shared by two faces
+        open_edges: usize,
+    },
+    /// An edge is not incident to one or two faces, or the faces around a vertex
+    /// are not connected through its edges, see [crate::Mesh::check_manifold]
+    NonManifold { kind: &'static str, index: usize },
+    /// The operation requires a pure subcomplex, see [crate::Simplices::is_pure_complex]
+    NotPureComplex,
+    /// The faces cannot be oriented consistently into a manifold, see [crate::Mesh::to_flat]
+    NotOrientableManifold,
+    /// A dense matrix would have more elements than the limit, see [crate::operators]
+    TooLarge {
+        what: &'static str,
+        elements: usize,
+        limit: usize,
+    },
+    /// Reading or writing failed
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+    /// Malformed text input, with the message of the format
+    Parse(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidPermutation(e) => write!(f, "{}", e),
+            Error::InvalidFaces(e) => write!(f, "{}", e),
+            Error::Build(e) => write!(f, "{}", e),
+            Error::ShapeMismatch {
+                what,
+                expected,
+                actual,
+            } => write!(f, "Expected {} {}, but got {}", expected, what, actual),
+            Error::OutOfRange { kind, index, len } => write!(
+                f,
+                "{} {} is out of range, ids must be less than {}",
+                kind, index, len
+            ),
+            Error::NotClosed { open_edges } => write!(
+                f,
+                "Mesh is not closed, {} edges are not shared by two faces",
+                open_edges
+            ),
+            Error::NonManifold { kind, index } => {
+                write!(f, "Mesh is not a manifold around {} {}", kind, index)
+            }
+            Error::NotPureComplex => write!(f, "Simplices are not a pure subcomplex"),
+            Error::NotOrientableManifold => {
+                write!(
+                    f,
+                    "Mesh is not a manifold whose faces can be oriented consistently"
+                )
+            }
+            Error::TooLarge {
+                what,
+                elements,
+                limit,
+            } => write!(
+                f,
+                "{} has {} elements, more than the limit {}",
+                what, elements, limit
+            ),
+            #[cfg(feature = "std")]
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Parse(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::InvalidPermutation(e) => Some(e),
+            Error::InvalidFaces(e) => Some(e),
+            Error::Build(e) => Some(e),
+            #[cfg(feature = "std")]
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<PermutationError> for Error {
+    fn from(e: PermutationError) -> Self {
+        Error::InvalidPermutation(e)
+    }
+}
+
+impl From<FaceListError> for Error {
+    fn from(e: FaceListError) -> Self {
+        Error::InvalidFaces(e)
+    }
+}
+
+impl From<BuildError> for Error {
+    fn from(e: BuildError) -> Self {
+        Error::Build(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<crate::io::permutation::ReadError> for Error {
+    fn from(e: crate::io::permutation::ReadError) -> Self {
+        match e {
+            crate::io::permutation::ReadError::Io(e) => Error::Io(e),
+            e => Error::Parse(e.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<crate::io::graph6::Graph6Error> for Error {
+    fn from(e: crate::io::graph6::Graph6Error) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<crate::io::selection::SelectionError> for Error {
+    fn from(e: crate::io::selection::SelectionError) -> Self {
+        match e {
+            crate::io::selection::SelectionError::OutOfRange { kind, id, count } => {
+                Error::OutOfRange {
+                    kind,
+                    index: id,
+                    len: count,
+                }
+            }
+            e => Error::Parse(e.to_string()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{builder::MeshBuilder, io, shapes, Connection, Mesh};
+
+    #[test]
+    fn variants() {
+        let e = Mesh::try_from_permutation(&[1, 0, 0, 2]).unwrap_err();
+        assert!(matches!(e, Error::InvalidPermutation(_)));
+        assert_eq!(
+            e.to_string(),
+            "Not a bijection: permutation[1] = permutation[2] = 0"
+        );
+
+        let e = Error::from(crate::from_faces(&[vec![0, 1, 2]]).unwrap_err());
+        assert!(matches!(e, Error::InvalidFaces(_)));
+
+        let mut builder = MeshBuilder::new(1);
+        let e = Error::from(builder.add_face(&[0, 1]).unwrap_err());
+        assert!(matches!(e, Error::Build(_)));
+
+        let vertex_edge = Connection::from_vec(vec![(0, 0), (1, 0), (1, 1), (0, 1)]);
+        let edge_face = Connection::from_vec(vec![(0, 0)]);
+        let e = Mesh::try_from_connections(vertex_edge, edge_face).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Expected 2 rows in the edge-face matrix, but got 1"
+        );
+
+        let tetrahedron = Mesh::from_permutation(&shapes::tetrahedron());
+        let e = tetrahedron.try_simplicies(&[0], &[6], &[]).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "edge 6 is out of range, ids must be less than 6"
+        );
+
+        let e = edge_face_of(&tetrahedron).try_get_connected(6).unwrap_err();
+        assert!(matches!(
+            e,
+            Error::OutOfRange {
+                kind: "row",
+                index: 6,
+                len: 6
+            }
+        ));
+
+        let e = tetrahedron.try_face_vertices(4).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "face 4 is out of range, ids must be less than 4"
+        );
+
+        // three faces on an edge
+        let book = Mesh::try_from_connections(
+            Connection::from_vec(vec![(0, 0), (1, 0)]),
+            Connection::from_vec(vec![(0, 0), (0, 1), (0, 2)]),
+        )
+        .unwrap();
+        let e = book.check_manifold().unwrap_err();
+        assert!(matches!(
+            e,
+            Error::NonManifold {
+                kind: "edge",
+                index: 0
+            }
+        ));
+        assert_eq!(e.to_string(), "Mesh is not a manifold around edge 0");
+
+        let open = Mesh::try_from_connections(
+            Connection::from_vec(vec![(0, 0), (1, 0), (1, 1), (2, 1), (2, 2), (0, 2)]),
+            Connection::from_vec(vec![(0, 0), (1, 0), (2, 0)]),
+        )
+        .unwrap();
+        let e = open.try_dual().unwrap_err();
+        assert!(matches!(e, Error::NotClosed { open_edges: 3 }));
+
+        let e = tetrahedron
+            .simplicies(&[0], &[0], &[])
+            .try_boundary()
+            .unwrap_err();
+        assert!(matches!(e, Error::NotPureComplex));
+
+        let e = tetrahedron.d1_dense_with_limit(23).unwrap_err();
+        assert_eq!(e.to_string(), "d1 has 24 elements, more than the limit 23");
+
+        let e = Error::from(io::permutation::read_permutation(FailingReader).unwrap_err());
+        assert!(matches!(e, Error::Io(_)));
+
+        let e = Error::from(io::permutation::parse_permutation("0 x").unwrap_err());
+        assert!(matches!(e, Error::Parse(_)));
+    }
+
+    fn edge_face_of(mesh: &Mesh) -> Connection {
+        Connection::from_iter((0..mesh.num_edges()).flat_map(|e| {
+            mesh.edge_faces(e)
+                .iter()
+                .map(move |&f| (e, crate::connection_matrix::to_usize(f)))
+        }))
+    }
+
+    /// Reader failing on every read
+    struct FailingReader;
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("broken pipe"))
+        }
+    }
+
+    impl std::io::BufRead for FailingReader {
+        fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+            Err(std::io::Error::other("broken pipe"))
+        }
+
+        fn consume(&mut self, _amt: usize) {}
+    }
+}
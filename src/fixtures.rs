@@ -0,0 +1,243 @@
+/*!
+Meshes embedded in the crate with their known properties, for tests
+
+The files are in the `fixtures` directory of the repository, either as permutations
+(see [crate::io::permutation]) or as face lists (see [crate::io::faces]) for meshes
+with boundary or without manifold structure.
+Tests assert against the properties of a [Fixture] instead of repeating the numbers.
+
+```
+use umesh::fixtures::{self, ANNULUS};
+
+let mesh = ANNULUS.mesh();
+assert_eq!(mesh.num_boundary_loops(), ANNULUS.num_boundary_loops);
+assert_eq!(fixtures::load("annulus").unwrap().num_faces(), ANNULUS.num_faces);
+```
+
+The module is compiled for the tests of this crate, and with the `fixtures` feature.
+*/
+
+use crate::builder::MeshBuilder;
+use crate::io::{faces::read_faces, permutation::parse_permutation};
+use crate::Mesh;
+
+/// Embedded mesh with its counts and topological invariants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fixture {
+    pub name: &'static str,
+    pub num_vertices: usize,
+    pub num_edges: usize,
+    pub num_faces: usize,
+    pub euler_characteristic: isize,
+    /// [Mesh::genus], `None` for a mesh which is not a manifold
+    pub genus: Option<usize>,
+    pub num_boundary_loops: usize,
+    data: Data,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Data {
+    Permutation(&'static str),
+    Faces(&'static str),
+}
+
+pub const TETRAHEDRON: Fixture = Fixture {
+    name: "tetrahedron",
+    num_vertices: 4,
+    num_edges: 6,
+    num_faces: 4,
+    euler_characteristic: 2,
+    genus: Some(0),
+    num_boundary_loops: 0,
+    data: Data::Permutation(include_str!("../fixtures/tetrahedron.txt")),
+};
+
+pub const CUBE: Fixture = Fixture {
+    name: "cube",
+    num_vertices: 8,
+    num_edges: 12,
+    num_faces: 6,
+    euler_characteristic: 2,
+    genus: Some(0),
+    num_boundary_loops: 0,
+    data: Data::Permutation(include_str!("../fixtures/cube.txt")),
+};
+
+pub const ICOSAHEDRON: Fixture = Fixture {
+    name: "icosahedron",
+    num_vertices: 12,
+    num_edges: 30,
+    num_faces: 20,
+    euler_characteristic: 2,
+    genus: Some(0),
+    num_boundary_loops: 0,
+    data: Data::Permutation(include_str!("../fixtures/icosahedron.txt")),
+};
+
+/// Two disjoint copies of [TETRAHEDRON]
+pub const TWO_TETRAHEDRA: Fixture = Fixture {
+    name: "two_tetrahedra",
+    num_vertices: 8,
+    num_edges: 12,
+    num_faces: 8,
+    euler_characteristic: 4,
+    genus: Some(0),
+    num_boundary_loops: 0,
+    data: Data::Permutation(include_str!("../fixtures/two_tetrahedra.txt")),
+};
+
+/// Octagon glued into a closed surface of genus 2, with a single vertex
+pub const GENUS2: Fixture = Fixture {
+    name: "genus2",
+    num_vertices: 1,
+    num_edges: 4,
+    num_faces: 1,
+    euler_characteristic: -2,
+    genus: Some(2),
+    num_boundary_loops: 0,
+    data: Data::Permutation(include_str!("../fixtures/genus2.txt")),
+};
+
+/// Disk of 2 x 2 quads
+pub const OPEN_PATCH: Fixture = Fixture {
+    name: "open_patch",
+    num_vertices: 9,
+    num_edges: 12,
+    num_faces: 4,
+    euler_characteristic: 1,
+    genus: Some(0),
+    num_boundary_loops: 1,
+    data: Data::Faces(include_str!("../fixtures/open_patch.txt")),
+};
+
+/// Ring of 4 quads
+pub const ANNULUS: Fixture = Fixture {
+    name: "annulus",
+    num_vertices: 8,
+    num_edges: 12,
+    num_faces: 4,
+    euler_characteristic: 0,
+    genus: Some(0),
+    num_boundary_loops: 2,
+    data: Data::Faces(include_str!("../fixtures/annulus.txt")),
+};
+
+/// Two triangles sharing a vertex
+pub const BOWTIE: Fixture = Fixture {
+    name: "bowtie",
+    num_vertices: 5,
+    num_edges: 6,
+    num_faces: 2,
+    euler_characteristic: 1,
+    genus: None,
+    num_boundary_loops: 1,
+    data: Data::Faces(include_str!("../fixtures/bowtie.txt")),
+};
+
+pub const ALL: [Fixture; 8] = [
+    TETRAHEDRON,
+    CUBE,
+    ICOSAHEDRON,
+    TWO_TETRAHEDRA,
+    GENUS2,
+    OPEN_PATCH,
+    ANNULUS,
+    BOWTIE,
+];
+
+impl Fixture {
+    /// Permutation on half-edges, `None` for a fixture given by faces
+    pub fn permutation(&self) -> Option<Vec<usize>> {
+        match self.data {
+            Data::Permutation(text) => Some(
+                parse_permutation(text)
+                    .unwrap_or_else(|e| panic!("Broken fixture {}: {}", self.name, e)),
+            ),
+            Data::Faces(_) => None,
+        }
+    }
+
+    /// Faces as lists of vertex ids, `None` for a fixture given by a permutation
+    pub fn faces(&self) -> Option<Vec<Vec<usize>>> {
+        match self.data {
+            Data::Faces(text) => Some(
+                read_faces(text.as_bytes())
+                    .unwrap_or_else(|e| panic!("Broken fixture {}: {}", self.name, e)),
+            ),
+            Data::Permutation(_) => None,
+        }
+    }
+
+    /// Mesh by [Mesh::from_permutation], or by [MeshBuilder] keeping the vertex ids of the faces
+    pub fn mesh(&self) -> Mesh {
+        if let Some(permutation) = self.permutation() {
+            return Mesh::from_permutation(&permutation);
+        }
+        let faces = self.faces().unwrap_or_default();
+        let num_vertices = faces.iter().flatten().map(|&v| v + 1).max().unwrap_or(0);
+        let mut builder = MeshBuilder::new(num_vertices);
+        for _ in 0..num_vertices {
+            builder.add_vertex();
+        }
+        for face in &faces {
+            builder
+                .add_face(face)
+                .unwrap_or_else(|e| panic!("Broken fixture {}: {}", self.name, e));
+        }
+        builder
+            .build()
+            .unwrap_or_else(|e| panic!("Broken fixture {}: {}", self.name, e))
+    }
+}
+
+/// Mesh of the fixture named `name`, see [ALL]
+pub fn load(name: &str) -> Option<Mesh> {
+    ALL.iter()
+        .find(|fixture| fixture.name == name)
+        .map(Fixture::mesh)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn properties() {
+        for fixture in &ALL {
+            let mesh = fixture.mesh();
+            let counts = (mesh.num_vertices(), mesh.num_edges(), mesh.num_faces());
+            let expected = (fixture.num_vertices, fixture.num_edges, fixture.num_faces);
+            assert_eq!(counts, expected, "{}", fixture.name);
+            assert_eq!(
+                mesh.euler_characteristic(),
+                fixture.euler_characteristic,
+                "{}",
+                fixture.name
+            );
+            assert_eq!(mesh.genus(), fixture.genus, "{}", fixture.name);
+            assert_eq!(
+                mesh.num_boundary_loops(),
+                fixture.num_boundary_loops,
+                "{}",
+                fixture.name
+            );
+            assert_eq!(mesh.check_invariants(), Ok(()), "{}", fixture.name);
+        }
+        assert!(load("annulus").is_some());
+        assert!(load("bunny").is_none());
+    }
+
+    #[test]
+    fn shapes() {
+        assert_eq!(
+            TETRAHEDRON.permutation(),
+            Some(crate::shapes::tetrahedron())
+        );
+        assert_eq!(CUBE.permutation(), Some(crate::shapes::cube()));
+        assert_eq!(
+            ICOSAHEDRON.permutation(),
+            Some(crate::shapes::icosahedron())
+        );
+        assert_eq!(ANNULUS.permutation(), None);
+    }
+}
@@ -0,0 +1,654 @@
+/*!
+Half-edge tables as flat arrays, for renderers, GPU buffers, and FFI
+
+[Mesh::to_flat] exports the half-edge structure as contiguous `u32` arrays,
+and [Mesh::from_flat] imports them after checking every cross-reference.
+In an exported mesh the half-edges `2k` and `2k + 1` are the twins of edge `k`,
+and the vertices and faces keep their ids.
+
+```
+# #[cfg(feature = "std")] {
+use umesh::{flat::NO_FACE, shapes, Mesh};
+
+let mesh = Mesh::from_permutation(&shapes::cube());
+let flat = mesh.to_flat().unwrap();
+assert_eq!(flat.next.len(), 24);
+assert_eq!(flat.twin[5], 4);
+assert!(flat.face.iter().all(|&f| f != NO_FACE));
+let h = flat.vertex_halfedge[3] as usize;
+assert_eq!(flat.vertex[h], 3);
+
+let copy = Mesh::from_flat(&flat).unwrap();
+assert_eq!(copy.to_flat().unwrap(), flat);
+# }
+```
+*/
+
+use crate::builder::BuildError;
+use crate::connection_matrix::{to_usize, widen, Connection};
+use crate::error::Error;
+use crate::half_edge::Mesh;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::timing::Timer;
+use alloc::collections::VecDeque;
+use core::fmt;
+
+/// [FlatHalfEdgeMesh::face] of a half-edge on the boundary
+pub const NO_FACE: u32 = u32::MAX;
+
+/// Half-edge structure as arrays indexed by half-edge, face, and vertex
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FlatHalfEdgeMesh {
+    /// Next half-edge in the face, or along the boundary loop
+    pub next: Vec<u32>,
+    /// Half-edge of the same edge in the opposite direction
+    pub twin: Vec<u32>,
+    /// Origin vertex of each half-edge
+    pub vertex: Vec<u32>,
+    /// Face of each half-edge, or [NO_FACE] on the boundary
+    pub face: Vec<u32>,
+    /// Smallest half-edge of each face
+    pub face_halfedge: Vec<u32>,
+    /// Smallest half-edge leaving each vertex
+    pub vertex_halfedge: Vec<u32>,
+}
+
+/// Inconsistent cross-reference in a [FlatHalfEdgeMesh], reported as [BuildError::InvalidFlat]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FlatError {
+    /// An array indexed by half-edge is not as long as `next`
+    LengthMismatch {
+        array: &'static str,
+        len: usize,
+        expected: usize,
+    },
+    /// `array[index] = value` is not a half-edge, vertex, or face
+    OutOfRange {
+        array: &'static str,
+        index: usize,
+        value: usize,
+    },
+    /// The twin of `twin` is not `half_edge`, or the half-edge is its own twin
+    Twin { half_edge: usize, twin: usize },
+    /// Both `next[first]` and `next[second]` are `value`
+    NotBijective {
+        first: usize,
+        second: usize,
+        value: usize,
+    },
+    /// The next half-edge does not start where the half-edge ends, i.e. at the origin of its twin
+    Vertex { half_edge: usize },
+    /// The next half-edge belongs to another face
+    Face { half_edge: usize },
+    /// Neither side of the edge belongs to a face
+    IsolatedEdge { half_edge: usize },
+    /// `array[index]` is a half-edge of another vertex or face
+    Seed { array: &'static str, index: usize },
+}
+
+impl fmt::Display for FlatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FlatError::LengthMismatch {
+                array,
+                len,
+                expected,
+            } => write!(
+                f,
+                "{} has {} half-edges, but next has {}",
+                array, len, expected
+            ),
+            FlatError::OutOfRange {
+                array,
+                index,
+                value,
+            } => write!(f, "{}[{}] = {} is out of range", array, index, value),
+            FlatError::Twin { half_edge, twin } => write!(
+                f,
+                "Half-edge {} has twin {}, whose twin is not half-edge {}",
+                half_edge, twin, half_edge
+            ),
+            FlatError::NotBijective {
+                first,
+                second,
+                value,
+            } => write!(
+                f,
+                "Not a bijection: next[{}] = next[{}] = {}",
+                first, second, value
+            ),
+            FlatError::Vertex { half_edge } => write!(
+                f,
+                "Half-edge {} does not end at the origin of its next half-edge",
+                half_edge
+            ),
+            FlatError::Face { half_edge } => write!(
+                f,
+                "Half-edge {} and its next half-edge belong to different faces",
+                half_edge
+            ),
+            FlatError::IsolatedEdge { half_edge } => write!(
+                f,
+                "Neither half-edge {} nor its twin belongs to a face",
+                half_edge
+            ),
+            FlatError::Seed { array, index } => write!(
+                f,
+                "{}[{}] is a half-edge of another {}",
+                array,
+                index,
+                if *array == "face_halfedge" {
+                    "face"
+                } else {
+                    "vertex"
+                }
+            ),
+        }
+    }
+}
+
+impl core::error::Error for FlatError {}
+
+/// [Error::OutOfRange] unless the ids `0..len` fit in `u32` and differ from [NO_FACE]
+fn check_u32(kind: &'static str, len: usize) -> Result<(), Error> {
+    let max = NO_FACE as usize;
+    if len > max {
+        return Err(Error::OutOfRange {
+            kind,
+            index: len - 1,
+            len: max,
+        });
+    }
+    Ok(())
+}
+
+impl Mesh {
+    /// Half-edge tables of the mesh
+    ///
+    /// A mesh created from a permutation is exported as is. A mesh created from connection
+    /// matrices is oriented consistently along its faces, starting from the smallest face
+    /// of each component, and the boundary half-edges are linked along the boundary loops.
+    /// Returns [Error::NotOrientableManifold] for such a mesh if it cannot be oriented,
+    /// e.g. for an edge shared by three faces, a face visiting a vertex twice,
+    /// two boundary loops meeting at a vertex, or a vertex without edges,
+    /// and [Error::OutOfRange] if the half-edges, vertices, or faces do not fit in `u32`.
+    pub fn to_flat(&self) -> Result<FlatHalfEdgeMesh, Error> {
+        let _timer = Timer::start("to_flat");
+        let n = 2 * self.num_edges();
+        check_u32("half-edge", n)?;
+        check_u32("vertex", self.num_vertices())?;
+        check_u32("face", self.num_faces())?;
+        let (next, face, vertex) = match self.permutation() {
+            Some(permutation) => {
+                let permutation = widen(permutation).into_owned();
+                let mut face = vec![0; n];
+                for (f, orbit) in crate::permutation::gather_faces(&permutation)
+                    .iter()
+                    .enumerate()
+                {
+                    for h in orbit.indices() {
+                        face[h] = f;
+                    }
+                }
+                let mut vertex = vec![0; n];
+                for (v, orbit) in crate::permutation::gather_vertices(&permutation)
+                    .iter()
+                    .enumerate()
+                {
+                    for h in orbit.indices() {
+                        vertex[h] = v;
+                    }
+                }
+                (permutation, face, vertex)
+            }
+            None => self.orient().ok_or(Error::NotOrientableManifold)?,
+        };
+
+        let mut face_halfedge = vec![None; self.num_faces()];
+        let mut vertex_halfedge = vec![None; self.num_vertices()];
+        for h in (0..n).rev() {
+            if face[h] != usize::MAX {
+                face_halfedge[face[h]] = Some(h);
+            }
+            vertex_halfedge[vertex[h]] = Some(h);
+        }
+        // every id fits in u32 by the checks above
+        let seeds = |seeds: Vec<Option<usize>>| -> Option<Vec<u32>> {
+            seeds.into_iter().map(|h| h.map(|h| h as u32)).collect()
+        };
+        let face_halfedge = seeds(face_halfedge).ok_or(Error::NotOrientableManifold)?;
+        let vertex_halfedge = seeds(vertex_halfedge).ok_or(Error::NotOrientableManifold)?;
+        Ok(FlatHalfEdgeMesh {
+            next: next.into_iter().map(|h| h as u32).collect(),
+            twin: (0..n).map(|h| (h ^ 1) as u32).collect(),
+            vertex: vertex.into_iter().map(|v| v as u32).collect(),
+            face: face
+                .into_iter()
+                .map(|f| if f == usize::MAX { NO_FACE } else { f as u32 })
+                .collect(),
+            face_halfedge,
+            vertex_halfedge,
+        })
+    }
+
+    /// Mesh of half-edge tables, after checking their cross-references
+    ///
+    /// Edges are numbered by the smaller half-edge of each pair of twins.
+    /// Without boundary the mesh is created by [Mesh::from_permutation] on the half-edges
+    /// renumbered so that twins are `2k` and `2k + 1`, and the vertices and faces are numbered
+    /// as described in [crate::permutation]. Otherwise the vertices and faces keep their ids,
+    /// but the mesh has no permutation.
+    /// The tables of [Mesh::to_flat] give the same tables again in either case.
+    pub fn from_flat(flat: &FlatHalfEdgeMesh) -> Result<Mesh, BuildError> {
+        let _timer = Timer::start("from_flat");
+        validate(flat)?;
+        let n = flat.next.len();
+        let at = |array: &[u32], i: usize| array[i] as usize;
+
+        // pairs of twins as edges in the order of the smaller half-edge
+        let mut renumber = vec![0; n];
+        let mut edges = 0;
+        for h in 0..n {
+            let twin = at(&flat.twin, h);
+            if h < twin {
+                renumber[h] = 2 * edges;
+                renumber[twin] = 2 * edges + 1;
+                edges += 1;
+            }
+        }
+
+        if flat.face.iter().all(|&f| f != NO_FACE) {
+            let mut permutation = vec![0; n];
+            for h in 0..n {
+                permutation[renumber[h]] = renumber[at(&flat.next, h)];
+            }
+            return Ok(Mesh::from_permutation(&permutation));
+        }
+        let vertex_edge = (0..n)
+            .map(|h| (at(&flat.vertex, h), renumber[h] / 2))
+            .collect();
+        let edge_face = (0..n)
+            .filter(|&h| flat.face[h] != NO_FACE)
+            .map(|h| (renumber[h] / 2, at(&flat.face, h)))
+            .collect();
+        Ok(Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        ))
+    }
+
+    /// Next half-edge, face or `usize::MAX`, and origin vertex of each half-edge,
+    /// where the half-edge `2e` runs from the smaller vertex of edge `e` to the larger one
+    fn orient(&self) -> Option<(Vec<usize>, Vec<usize>, Vec<usize>)> {
+        let n = 2 * self.num_edges();
+        let mut vertex = vec![0; n];
+        for e in 0..self.num_edges() {
+            match *self.edge_vertices(e) {
+                [a, b] => {
+                    vertex[2 * e] = to_usize(a);
+                    vertex[2 * e + 1] = to_usize(b);
+                }
+                // self-loop
+                _ => return None,
+            }
+        }
+        let target = |h: usize| vertex[h ^ 1];
+
+        // half-edges of each face in one of its two orientations
+        let mut cycles = Vec::with_capacity(self.num_faces());
+        for f in 0..self.num_faces() {
+            let edges = widen(self.face_edges(f)).into_owned();
+            let mut used = vec![false; edges.len()];
+            let mut cycle = Vec::with_capacity(edges.len());
+            let mut h = 2 * *edges.first()?;
+            used[0] = true;
+            cycle.push(h);
+            while cycle.len() < edges.len() {
+                let to = target(h);
+                let i = (0..edges.len()).find(|&i| {
+                    !used[i] && (vertex[2 * edges[i]] == to || vertex[2 * edges[i] + 1] == to)
+                })?;
+                used[i] = true;
+                h = if vertex[2 * edges[i]] == to {
+                    2 * edges[i]
+                } else {
+                    2 * edges[i] + 1
+                };
+                cycle.push(h);
+            }
+            let mut origins: Vec<usize> = cycle.iter().map(|&h| vertex[h]).collect();
+            origins.sort_unstable();
+            origins.dedup();
+            if target(h) != vertex[cycle[0]] || origins.len() != cycle.len() {
+                return None;
+            }
+            cycles.push(cycle);
+        }
+
+        // orient each component from its smallest face by the twins across the edges
+        let mut face = vec![usize::MAX; n];
+        let mut oriented = vec![false; self.num_faces()];
+        for root in 0..self.num_faces() {
+            if oriented[root] {
+                continue;
+            }
+            oriented[root] = true;
+            claim(&cycles[root], root, &mut face)?;
+            let mut queue = VecDeque::from(vec![root]);
+            while let Some(f) = queue.pop_front() {
+                for i in 0..cycles[f].len() {
+                    let h = cycles[f][i];
+                    let neighbors = self.edge_faces(h / 2);
+                    if neighbors.len() > 2 {
+                        return None;
+                    }
+                    for g in neighbors.iter().map(|&g| to_usize(g)) {
+                        if oriented[g] {
+                            continue;
+                        }
+                        // g runs along the twin of h
+                        oriented[g] = true;
+                        if cycles[g].contains(&h) {
+                            let cycle = &mut cycles[g];
+                            cycle.reverse();
+                            cycle.iter_mut().for_each(|h| *h ^= 1);
+                        }
+                        claim(&cycles[g], g, &mut face)?;
+                        queue.push_back(g);
+                    }
+                }
+            }
+        }
+
+        let mut next = vec![usize::MAX; n];
+        for cycle in &cycles {
+            for (i, &h) in cycle.iter().enumerate() {
+                next[h] = cycle[(i + 1) % cycle.len()];
+            }
+        }
+        // boundary loops, where a single boundary half-edge leaves each vertex
+        let mut leaving = vec![usize::MAX; self.num_vertices()];
+        for h in (0..n).filter(|&h| face[h] == usize::MAX) {
+            if face[h ^ 1] == usize::MAX || leaving[vertex[h]] != usize::MAX {
+                return None;
+            }
+            leaving[vertex[h]] = h;
+        }
+        for h in (0..n).filter(|&h| face[h] == usize::MAX) {
+            next[h] = leaving[target(h)];
+            if next[h] == usize::MAX {
+                return None;
+            }
+        }
+        Some((next, face, vertex))
+    }
+}
+
+/// Mark the half-edges of the face, failing if one already belongs to another face
+fn claim(cycle: &[usize], f: usize, face: &mut [usize]) -> Option<()> {
+    for &h in cycle {
+        if face[h] != usize::MAX {
+            return None;
+        }
+        face[h] = f;
+    }
+    Some(())
+}
+
+/// Check the lengths, ranges, and cross-references of the tables
+fn validate(flat: &FlatHalfEdgeMesh) -> Result<(), FlatError> {
+    let n = flat.next.len();
+    let (num_faces, num_vertices) = (flat.face_halfedge.len(), flat.vertex_halfedge.len());
+    for &(array, values) in &[
+        ("twin", &flat.twin),
+        ("vertex", &flat.vertex),
+        ("face", &flat.face),
+    ] {
+        if values.len() != n {
+            return Err(FlatError::LengthMismatch {
+                array,
+                len: values.len(),
+                expected: n,
+            });
+        }
+    }
+    let ranges: [(&'static str, &Vec<u32>, usize); 6] = [
+        ("next", &flat.next, n),
+        ("twin", &flat.twin, n),
+        ("vertex", &flat.vertex, num_vertices),
+        ("face", &flat.face, num_faces),
+        ("face_halfedge", &flat.face_halfedge, n),
+        ("vertex_halfedge", &flat.vertex_halfedge, n),
+    ];
+    for &(array, values, len) in &ranges {
+        let out = values
+            .iter()
+            .position(|&i| i as usize >= len && !(array == "face" && i == NO_FACE));
+        if let Some(index) = out {
+            return Err(FlatError::OutOfRange {
+                array,
+                index,
+                value: values[index] as usize,
+            });
+        }
+    }
+
+    let at = |array: &[u32], i: usize| array[i] as usize;
+    let mut previous = vec![usize::MAX; n];
+    for h in 0..n {
+        let twin = at(&flat.twin, h);
+        if twin == h || at(&flat.twin, twin) != h {
+            return Err(FlatError::Twin { half_edge: h, twin });
+        }
+        let next = at(&flat.next, h);
+        if previous[next] != usize::MAX {
+            return Err(FlatError::NotBijective {
+                first: previous[next],
+                second: h,
+                value: next,
+            });
+        }
+        previous[next] = h;
+    }
+    for h in 0..n {
+        let next = at(&flat.next, h);
+        if flat.vertex[next] != flat.vertex[at(&flat.twin, h)] {
+            return Err(FlatError::Vertex { half_edge: h });
+        }
+        if flat.face[next] != flat.face[h] {
+            return Err(FlatError::Face { half_edge: h });
+        }
+        if flat.face[h] == NO_FACE && flat.face[at(&flat.twin, h)] == NO_FACE {
+            return Err(FlatError::IsolatedEdge { half_edge: h });
+        }
+    }
+    let seeds = [
+        ("face_halfedge", &flat.face_halfedge, &flat.face),
+        ("vertex_halfedge", &flat.vertex_halfedge, &flat.vertex),
+    ];
+    for &(array, seeds, owner) in &seeds {
+        if let Some(index) = (0..seeds.len()).find(|&i| owner[at(seeds, i)] as usize != i) {
+            return Err(FlatError::Seed { array, index });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use std::collections::BTreeMap;
+
+    /// Mesh of triangles by connection matrices, without orientation
+    fn unoriented(triangles: &[[usize; 3]]) -> Mesh {
+        let mut edges = BTreeMap::new();
+        let mut edge_face = Vec::new();
+        for (f, t) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (t[i], t[(i + 1) % 3]);
+                let key = (a.min(b), a.max(b));
+                let next = edges.len();
+                edge_face.push((*edges.entry(key).or_insert(next), f));
+            }
+        }
+        let vertex_edge = edges
+            .iter()
+            .flat_map(|(&(a, b), &e)| vec![(a, e), (b, e)])
+            .collect();
+        Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        )
+    }
+
+    #[test]
+    fn round_trip() {
+        for fixture in &fixtures::ALL {
+            let mesh = fixture.mesh();
+            let flat = match mesh.to_flat() {
+                Ok(flat) => flat,
+                Err(e) => {
+                    // the bowtie is not a manifold
+                    assert!(matches!(e, Error::NotOrientableManifold));
+                    assert_eq!(fixture.genus, None, "{}", fixture.name);
+                    continue;
+                }
+            };
+            assert_eq!(validate(&flat), Ok(()), "{}", fixture.name);
+            assert_eq!(flat.next.len(), 2 * fixture.num_edges);
+            assert_eq!(flat.face_halfedge.len(), fixture.num_faces);
+            assert_eq!(flat.vertex_halfedge.len(), fixture.num_vertices);
+            let boundary = flat.face.iter().filter(|&&f| f == NO_FACE).count();
+            assert_eq!(boundary, mesh.boundary_edges().len(), "{}", fixture.name);
+            if let Some(permutation) = fixture.permutation() {
+                let next: Vec<usize> = flat.next.iter().map(|&h| h as usize).collect();
+                assert_eq!(next, permutation);
+            }
+
+            let copy = Mesh::from_flat(&flat).unwrap();
+            assert_eq!(copy.to_flat().unwrap(), flat, "{}", fixture.name);
+            assert_eq!(copy.boundary_edges(), mesh.boundary_edges());
+            assert_eq!(copy.num_boundary_loops(), fixture.num_boundary_loops);
+            for f in 0..mesh.num_faces() {
+                assert_eq!(copy.face_edges(f), mesh.face_edges(f), "{}", fixture.name);
+            }
+        }
+    }
+
+    #[test]
+    fn orientation() {
+        // faces given in inconsistent orders are oriented along the first one
+        let disk = unoriented(&[[0, 1, 2], [0, 3, 2], [0, 3, 4]]);
+        let flat = disk.to_flat().unwrap();
+        for h in 0..flat.next.len() {
+            let next = flat.next[h] as usize;
+            assert_eq!(flat.vertex[next], flat.vertex[h ^ 1]);
+        }
+        assert_eq!(flat.face.iter().filter(|&&f| f == NO_FACE).count(), 5);
+
+        // Möbius strip of five triangles
+        let mobius = unoriented(&[[0, 1, 2], [1, 2, 3], [2, 3, 4], [3, 4, 0], [4, 0, 1]]);
+        assert_eq!(mobius.num_boundary_loops(), 1);
+        assert!(matches!(
+            mobius.to_flat(),
+            Err(Error::NotOrientableManifold)
+        ));
+        // three triangles on an edge
+        let book = unoriented(&[[0, 1, 2], [0, 1, 3], [0, 1, 4]]);
+        assert!(matches!(book.to_flat(), Err(Error::NotOrientableManifold)));
+    }
+
+    #[test]
+    fn too_many_for_u32() {
+        assert!(check_u32("half-edge", NO_FACE as usize).is_ok());
+        let e = check_u32("half-edge", NO_FACE as usize + 1).unwrap_err();
+        assert!(matches!(
+            e,
+            Error::OutOfRange {
+                kind: "half-edge",
+                index: 4294967295,
+                len: 4294967295
+            }
+        ));
+    }
+
+    #[test]
+    fn invalid() {
+        let flat = fixtures::TETRAHEDRON.mesh().to_flat().unwrap();
+        let corrupt = |edit: &dyn Fn(&mut FlatHalfEdgeMesh)| {
+            let mut flat = flat.clone();
+            edit(&mut flat);
+            match Mesh::from_flat(&flat) {
+                Err(BuildError::InvalidFlat(e)) => Some(e),
+                Err(e) => panic!("{}", e),
+                Ok(_) => None,
+            }
+        };
+
+        let error = corrupt(&|flat| flat.twin[3] = 5).unwrap();
+        assert_eq!(
+            error,
+            FlatError::Twin {
+                half_edge: 2,
+                twin: 3
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "Half-edge 2 has twin 3, whose twin is not half-edge 2"
+        );
+        assert_eq!(
+            corrupt(&|flat| flat.twin[7] = 7),
+            Some(FlatError::Twin {
+                half_edge: 6,
+                twin: 7
+            })
+        );
+        assert_eq!(
+            corrupt(&|flat| flat.twin.pop().map(drop).unwrap()),
+            Some(FlatError::LengthMismatch {
+                array: "twin",
+                len: 11,
+                expected: 12
+            })
+        );
+        assert_eq!(
+            corrupt(&|flat| flat.vertex[4] = 4),
+            Some(FlatError::OutOfRange {
+                array: "vertex",
+                index: 4,
+                value: 4
+            })
+        );
+        assert_eq!(
+            corrupt(&|flat| flat.next[1] = flat.next[0]),
+            Some(FlatError::NotBijective {
+                first: 0,
+                second: 1,
+                value: flat.next[0] as usize
+            })
+        );
+        assert!(matches!(
+            corrupt(&|flat| flat.vertex.swap(0, 1)),
+            Some(FlatError::Vertex { .. })
+        ));
+        assert!(matches!(
+            corrupt(&|flat| flat.face[0] = (flat.face[0] + 1) % 4),
+            Some(FlatError::Face { .. })
+        ));
+        assert_eq!(
+            corrupt(&|flat| flat.face_halfedge[0] = flat.face_halfedge[1]),
+            Some(FlatError::Seed {
+                array: "face_halfedge",
+                index: 0
+            })
+        );
+        assert_eq!(
+            corrupt(&|flat| flat.face = vec![NO_FACE; 12]),
+            Some(FlatError::IsolatedEdge { half_edge: 0 })
+        );
+        assert!(Mesh::from_flat(&FlatHalfEdgeMesh::default()).is_ok());
+    }
+}
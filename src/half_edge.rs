@@ -7,9 +7,21 @@ based on [Keenan Crane][DDG].
 
 */
 
-use crate::{connection_matrix::*, permutation::*};
-use std::collections::BTreeSet;
-use std::iter::FromIterator;
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::{
+    connection_matrix::*, error::Error, memory::MemoryReport, permutation::*,
+    simplex_set::SimplexSet, timing::Timer,
+};
+use alloc::collections::{BTreeMap, BTreeSet, VecDeque};
+use core::fmt;
+
+/// Order of graph traversal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Traversal {
+    BreadthFirst,
+    DepthFirst,
+}
 
 #[derive(Debug, Clone)]
 pub struct Mesh {
@@ -21,115 +33,1047 @@ pub struct Mesh {
     edge_face: Connection,
     /// Transpose of A1
     face_edge: Connection,
+    /// Permutation on half-edges if the mesh is created from it
+    permutation: Option<Vec<Index>>,
+    cache: DerivedCache,
+}
+
+/// Cell computing its value on the first access
+///
+/// With `std` this is [std::sync::OnceLock], so `&Mesh` can be shared between threads.
+#[cfg(feature = "std")]
+type Lazy<T> = std::sync::OnceLock<T>;
+/// Cell computing its value on the first access
+///
+/// Without `std` this is [core::cell::OnceCell], and [Mesh] is not `Sync`.
+#[cfg(not(feature = "std"))]
+type Lazy<T> = core::cell::OnceCell<T>;
+
+/// Structures derived from the connection matrices, computed on first use
+///
+/// Each field must be reset by [Mesh::clear_cache] when the topology changes.
+#[derive(Debug, Clone, Default)]
+struct DerivedCache {
+    vertex_face: Lazy<Connection>,
+    vertex_adjacency: Lazy<Connection>,
+    face_adjacency: Lazy<Connection>,
+    prev: Lazy<Option<Vec<Index>>>,
 }
 
 impl Mesh {
     /// Create mesh from two connection matrices `A0` and `A1` in DDG
     ///
     /// Vertices, edges, and faces are initialized by `Default` trait.
+    ///
+    /// Panics if the numbers of edges disagree, see [Mesh::try_from_connections].
     pub fn from_connections(vertex_edge: Connection, edge_face: Connection) -> Self {
+        Self::try_from_connections(vertex_edge, edge_face).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::from_connections] returning [Error::ShapeMismatch]
+    /// unless the columns of `A0` and the rows of `A1` agree
+    pub fn try_from_connections(
+        vertex_edge: Connection,
+        edge_face: Connection,
+    ) -> Result<Self, Error> {
+        let _timer = Timer::start("from_connections");
         let (_, e1) = vertex_edge.shape();
         let (e2, _) = edge_face.shape();
-        assert_eq!(e1, e2); // Vertex-Edge matrix and Edge-Face matrix are compatible
+        if e1 != e2 {
+            return Err(Error::ShapeMismatch {
+                what: "rows in the edge-face matrix",
+                expected: e1,
+                actual: e2,
+            });
+        }
 
         let edge_vertex = vertex_edge.transpose();
         let face_edge = edge_face.transpose();
-        Mesh {
+        let mesh = Mesh {
             vertex_edge,
             edge_vertex,
             edge_face,
             face_edge,
-        }
+            permutation: None,
+            cache: DerivedCache::default(),
+        };
+        crate::timing::record_memory(&mesh.memory_usage());
+        Ok(mesh)
     }
 
     /// Create from permutation (see DDG §2.5 for detail)
     ///
+    /// The edge `k` consists of the twin half-edges `2k` and `2k + 1`.
+    ///
     /// Vertices, edges, and faces are initialized by `Default` trait.
+    /// With the `parallel` feature, the orbits of a large permutation are gathered on several threads,
+    /// see the `parallel` module.
     pub fn from_permutation(permutation: &[usize]) -> Self {
+        Self::from_permutation_with(permutation, ORBIT_THRESHOLD)
+    }
+
+    /// [Mesh::from_permutation] gathering the orbits on several threads from `threshold` half-edges,
+    /// `usize::MAX` keeps it sequential
+    #[cfg(feature = "parallel")]
+    pub fn from_permutation_with_threshold(permutation: &[usize], threshold: usize) -> Self {
+        Self::from_permutation_with(permutation, threshold)
+    }
+
+    fn from_permutation_with(permutation: &[usize], threshold: usize) -> Self {
+        let _timer = Timer::start("from_permutation");
+        let (vertices, faces) = gather_orbits(permutation, threshold);
         let vertex_edge = Connection::from_iter(
-            gather_vertices(permutation)
+            vertices
                 .iter()
                 .enumerate()
-                .flat_map(move |(v, orbit)| orbit.indices().iter().map(move |&e| (v, e))),
+                .flat_map(move |(v, orbit)| orbit.indices().map(move |h| (v, h / 2))),
         );
 
         let edge_face = Connection::from_iter(
-            gather_faces(permutation)
+            faces
                 .iter()
                 .enumerate()
-                .flat_map(move |(f, orbit)| orbit.indices().iter().map(move |&e| (e, f))),
+                .flat_map(move |(f, orbit)| orbit.indices().map(move |h| (h / 2, f))),
         );
-        Self::from_connections(vertex_edge, edge_face)
+        let mesh = Mesh {
+            permutation: Some(permutation.iter().map(|&h| to_index(h)).collect()),
+            ..Self::from_connections(vertex_edge, edge_face)
+        };
+        crate::timing::record_memory(&mesh.memory_usage());
+        mesh
+    }
+
+    /// [Mesh::from_permutation] after checking the input by [validate],
+    /// and [Error::OutOfRange] if the half-edges do not fit in [Index]
+    pub fn try_from_permutation(permutation: &[usize]) -> Result<Self, Error> {
+        check_len("half-edge", permutation.len())?;
+        validate(permutation)?;
+        Ok(Self::from_permutation(permutation))
+    }
+
+    /// Heap memory of the connection matrices, the permutation, and the cached structures computed so far
+    pub fn memory_usage(&self) -> MemoryReport {
+        let mut report = MemoryReport::default();
+        report.extend("vertex_edge", self.vertex_edge.memory_usage());
+        report.extend("edge_vertex", self.edge_vertex.memory_usage());
+        report.extend("edge_face", self.edge_face.memory_usage());
+        report.extend("face_edge", self.face_edge.memory_usage());
+        if let Some(permutation) = &self.permutation {
+            report.push("permutation", permutation);
+        }
+        let cache = &self.cache;
+        let cached = [
+            ("vertex_face", cache.vertex_face.get()),
+            ("vertex_adjacency", cache.vertex_adjacency.get()),
+            ("face_adjacency", cache.face_adjacency.get()),
+        ];
+        for (name, connection) in cached.iter() {
+            if let Some(connection) = connection {
+                report.extend(name, connection.memory_usage());
+            }
+        }
+        if let Some(Some(prev)) = cache.prev.get() {
+            report.push("prev", prev);
+        }
+        report
+    }
+
+    /// Release the capacity beyond the stored indices, including the cached structures
+    pub fn shrink_to_fit(&mut self) {
+        self.vertex_edge.shrink_to_fit();
+        self.edge_vertex.shrink_to_fit();
+        self.edge_face.shrink_to_fit();
+        self.face_edge.shrink_to_fit();
+        if let Some(permutation) = &mut self.permutation {
+            permutation.shrink_to_fit();
+        }
+        let cache = &mut self.cache;
+        let cached = [
+            cache.vertex_face.get_mut(),
+            cache.vertex_adjacency.get_mut(),
+            cache.face_adjacency.get_mut(),
+        ];
+        for connection in IntoIterator::into_iter(cached).flatten() {
+            connection.shrink_to_fit();
+        }
+        if let Some(Some(prev)) = cache.prev.get_mut() {
+            prev.shrink_to_fit();
+        }
+    }
+
+    /// Permutation on half-edges, available only for the mesh created by [Mesh::from_permutation]
+    pub fn permutation(&self) -> Option<&[Index]> {
+        self.permutation.as_deref()
+    }
+
+    /// Inverse of [Mesh::permutation], i.e. the previous half-edge in the face
+    ///
+    /// Computed on the first call and cached like [Mesh::vertex_adjacency].
+    pub fn prev_map(&self) -> Option<&[Index]> {
+        self.cache
+            .prev
+            .get_or_init(|| {
+                let permutation = self.permutation.as_ref()?;
+                let mut prev = vec![to_index(0); permutation.len()];
+                for (h, &next) in permutation.iter().enumerate() {
+                    prev[to_usize(next)] = to_index(h);
+                }
+                Some(prev)
+            })
+            .as_deref()
+    }
+
+    /// Drop the cached structures, see [Mesh::vertex_adjacency]
+    ///
+    /// They are recomputed on the next access.
+    pub fn clear_cache(&mut self) {
+        self.cache = DerivedCache::default();
+    }
+
+    /// Number of vertices
+    pub fn num_vertices(&self) -> usize {
+        self.vertex_edge.shape().0
+    }
+
+    /// Number of edges
+    pub fn num_edges(&self) -> usize {
+        self.edge_vertex.shape().0
+    }
+
+    /// Number of faces
+    pub fn num_faces(&self) -> usize {
+        self.face_edge.shape().0
+    }
+
+    /// Euler characteristic `V - E + F`
+    pub fn euler_characteristic(&self) -> isize {
+        self.num_vertices() as isize - self.num_edges() as isize + self.num_faces() as isize
+    }
+
+    /// Vertices of the edge (a single vertex for a self-loop)
+    ///
+    /// Panics if the id does not exist, see [Mesh::try_edge_vertices].
+    pub fn edge_vertices(&self, edge: usize) -> &[Index] {
+        self.try_edge_vertices(edge)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::edge_vertices] returning [Error::OutOfRange] for an edge which does not exist
+    pub fn try_edge_vertices(&self, edge: usize) -> Result<&[Index], Error> {
+        row(&self.edge_vertex, "edge", edge, self.num_edges())
+    }
+
+    /// Edges incident to the vertex
+    ///
+    /// Panics if the id does not exist, see [Mesh::try_vertex_edges].
+    pub fn vertex_edges(&self, vertex: usize) -> &[Index] {
+        self.try_vertex_edges(vertex)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::vertex_edges] returning [Error::OutOfRange] for a vertex which does not exist
+    pub fn try_vertex_edges(&self, vertex: usize) -> Result<&[Index], Error> {
+        row(&self.vertex_edge, "vertex", vertex, self.num_vertices())
+    }
+
+    /// Faces incident to the edge
+    ///
+    /// Panics if the id does not exist, see [Mesh::try_edge_faces].
+    pub fn edge_faces(&self, edge: usize) -> &[Index] {
+        self.try_edge_faces(edge)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::edge_faces] returning [Error::OutOfRange] for an edge which does not exist
+    pub fn try_edge_faces(&self, edge: usize) -> Result<&[Index], Error> {
+        row(&self.edge_face, "edge", edge, self.num_edges())
+    }
+
+    /// Edges of the face
+    ///
+    /// Panics if the id does not exist, see [Mesh::try_face_edges].
+    pub fn face_edges(&self, face: usize) -> &[Index] {
+        self.try_face_edges(face)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::face_edges] returning [Error::OutOfRange] for a face which does not exist
+    pub fn try_face_edges(&self, face: usize) -> Result<&[Index], Error> {
+        row(&self.face_edge, "face", face, self.num_faces())
+    }
+
+    /// Edges incident to exactly one face
+    ///
+    /// A mesh created from a permutation has no boundary,
+    /// even if both sides of an edge belong to the same face.
+    pub fn boundary_edges(&self) -> Vec<usize> {
+        if self.permutation.is_some() {
+            return Vec::new();
+        }
+        (0..self.num_edges())
+            .filter(|&e| self.edge_faces(e).len() == 1)
+            .collect()
+    }
+
+    /// Every edge is shared by two faces
+    pub fn is_closed(&self) -> bool {
+        self.permutation.is_some() || (0..self.num_edges()).all(|e| self.edge_faces(e).len() == 2)
+    }
+
+    /// Every edge is incident to one or two faces,
+    /// and the faces around each vertex are connected through the edges incident to the vertex.
+    ///
+    /// A mesh created from a permutation is always a manifold.
+    pub fn is_manifold(&self) -> bool {
+        self.check_manifold().is_ok()
+    }
+
+    /// [Mesh::is_manifold] returning [Error::NonManifold] for the first edge
+    /// not incident to one or two faces, or else the first vertex with several fans
+    pub fn check_manifold(&self) -> Result<(), Error> {
+        if self.permutation.is_some() {
+            return Ok(());
+        }
+        if let Some(edge) =
+            (0..self.num_edges()).find(|&e| !matches!(self.edge_faces(e).len(), 1 | 2))
+        {
+            return Err(Error::NonManifold {
+                kind: "edge",
+                index: edge,
+            });
+        }
+        match (0..self.num_vertices()).find(|&v| self.num_fans(v) > 1) {
+            Some(vertex) => Err(Error::NonManifold {
+                kind: "vertex",
+                index: vertex,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of groups of faces around the vertex connected through its incident edges
+    pub(crate) fn num_fans(&self, vertex: usize) -> usize {
+        let edges = self.vertex_edges(vertex);
+        let faces: Vec<usize> = self
+            .edge_face
+            .gather_connected(edges.iter().map(|&e| to_usize(e)))
+            .into_iter()
+            .collect();
+        let mut fan = UnionFind::new(faces.len());
+        for &e in edges {
+            let incident: Vec<_> = self
+                .edge_faces(to_usize(e))
+                .iter()
+                .map(|&f| faces.binary_search(&to_usize(f)).unwrap())
+                .collect();
+            for pair in incident.windows(2) {
+                fan.union(pair[0], pair[1]);
+            }
+        }
+        (0..faces.len()).filter(|&i| fan.find(i) == i).count()
+    }
+
+    /// Number of connected components, where an isolated vertex is a component
+    pub fn num_components(&self) -> usize {
+        let mut components = UnionFind::new(self.num_vertices());
+        for e in 0..self.num_edges() {
+            let (a, b) = self.edge_ends(e);
+            components.union(a, b);
+        }
+        (0..self.num_vertices())
+            .filter(|&v| components.find(v) == v)
+            .count()
+    }
+
+    /// Number of boundary loops, i.e. connected components of the boundary edges
+    pub fn num_boundary_loops(&self) -> usize {
+        let mut loops = UnionFind::new(self.num_vertices());
+        let mut on_boundary = vec![false; self.num_vertices()];
+        for e in self.boundary_edges() {
+            let (a, b) = self.edge_ends(e);
+            on_boundary[a] = true;
+            on_boundary[b] = true;
+            loops.union(a, b);
+        }
+        (0..self.num_vertices())
+            .filter(|&v| on_boundary[v] && loops.find(v) == v)
+            .count()
+    }
+
+    /// Boundary loops as vertices in cyclic order, sorted by their smallest edge
+    ///
+    /// The number of edges of a loop equals the number of its vertices.
+    pub fn boundary_loops(&self) -> Vec<Vec<usize>> {
+        let _timer = Timer::start("boundary_loops");
+        let boundary = self.boundary_edges();
+        let mut loops = UnionFind::new(self.num_vertices());
+        for &e in &boundary {
+            let (a, b) = self.edge_ends(e);
+            loops.union(a, b);
+        }
+        // the smallest edge of each loop, in the order of the edges
+        let mut found = vec![false; self.num_vertices()];
+        let mut starts = Vec::new();
+        let mut unused = vec![false; self.num_edges()];
+        for e in boundary {
+            let root = loops.find(self.edge_ends(e).0);
+            if !found[root] {
+                found[root] = true;
+                starts.push(e);
+            }
+            unused[e] = true;
+        }
+        starts
+            .into_iter()
+            .map(|start| self.walk_boundary(start, &mut unused))
+            .collect()
+    }
+
+    /// Vertices of the boundary loop from the edge `start`, marking its edges as used
+    ///
+    /// Each step takes the smallest unused edge at the vertex,
+    /// so the walk costs the degrees of the vertices on the loop.
+    fn walk_boundary(&self, start: usize, unused: &mut [bool]) -> Vec<usize> {
+        let mut current = self.edge_ends(start).0;
+        let mut vertices = vec![current];
+        while let Some(e) = self
+            .vertex_edges(current)
+            .iter()
+            .map(|&e| to_usize(e))
+            .find(|&e| unused[e])
+        {
+            unused[e] = false;
+            let (a, b) = self.edge_ends(e);
+            current = if a == current { b } else { a };
+            vertices.push(current);
+        }
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        vertices
+    }
+
+    /// Sum of genera of the components `(2c - χ - b) / 2`,
+    /// where `c` is the number of components and `b` is the number of boundary loops.
+    ///
+    /// Returns `None` if the mesh is not a manifold.
+    pub fn genus(&self) -> Option<usize> {
+        if !self.is_manifold() {
+            return None;
+        }
+        let twice = 2 * self.num_components() as isize
+            - self.euler_characteristic()
+            - self.num_boundary_loops() as isize;
+        if twice < 0 || twice % 2 != 0 {
+            return None;
+        }
+        Some(twice as usize / 2)
+    }
+
+    /// Check the consistency of the connection matrices
+    ///
+    /// Returns the descriptions of all violations:
+    /// each edge has one or two vertices, each face has an edge,
+    /// the stored transposes agree, and a permutation has two half-edges per edge.
+    pub fn check_invariants(&self) -> Result<(), Vec<String>> {
+        let mut violations = Vec::new();
+        for e in 0..self.num_edges() {
+            let n = self.edge_vertices(e).len();
+            if !(1..=2).contains(&n) {
+                violations.push(format!("edge {} has {} vertices", e, n));
+            }
+        }
+        for f in 0..self.num_faces() {
+            if self.face_edges(f).is_empty() {
+                violations.push(format!("face {} has no edges", f));
+            }
+        }
+        if self.edge_vertex != self.vertex_edge.transpose() {
+            violations.push("edge-vertex is not the transpose of vertex-edge".to_string());
+        }
+        if self.face_edge != self.edge_face.transpose() {
+            violations.push("face-edge is not the transpose of edge-face".to_string());
+        }
+        if let Some(permutation) = &self.permutation {
+            if permutation.len() != 2 * self.num_edges() {
+                violations.push(format!(
+                    "{} half-edges for {} edges",
+                    permutation.len(),
+                    self.num_edges()
+                ));
+            }
+        }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(violations)
+        }
+    }
+
+    /// Vertices of the face in cyclic order along its boundary
+    ///
+    /// The orientation is not recorded in the connection matrices,
+    /// and the order is only well-defined when the boundary of the face visits each vertex once.
+    /// Panics if the face does not exist, see [Mesh::try_face_vertices].
+    pub fn face_vertices(&self, face: usize) -> Vec<usize> {
+        self.try_face_vertices(face)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::face_vertices] returning [Error::OutOfRange] for a face which does not exist
+    pub fn try_face_vertices(&self, face: usize) -> Result<Vec<usize>, Error> {
+        Ok(self.chain_vertices(&widen(self.try_face_edges(face)?)))
+    }
+
+    /// Both vertices of the edge, equal for a self-loop
+    pub(crate) fn edge_ends(&self, edge: usize) -> (usize, usize) {
+        let vertices = self.edge_vertices(edge);
+        (to_usize(vertices[0]), to_usize(*vertices.last().unwrap()))
+    }
+
+    /// Vertices visited by walking along the edges which form a closed chain
+    pub(crate) fn chain_vertices(&self, edges: &[usize]) -> Vec<usize> {
+        let mut used = vec![false; edges.len()];
+        let mut current = self.edge_ends(edges[0]).0;
+        let mut vertices = vec![current];
+        while let Some(i) = (0..edges.len()).find(|&i| {
+            let (a, b) = self.edge_ends(edges[i]);
+            !used[i] && (a == current || b == current)
+        }) {
+            used[i] = true;
+            let (a, b) = self.edge_ends(edges[i]);
+            current = if a == current { b } else { a };
+            vertices.push(current);
+        }
+        if vertices.len() > 1 && vertices.first() == vertices.last() {
+            vertices.pop();
+        }
+        vertices
+    }
+
+    /// Vertex-face incidence, i.e. faces having the vertex on their boundary
+    ///
+    /// Computed on the first call and cached like [Mesh::vertex_adjacency].
+    pub fn vertex_face(&self) -> &Connection {
+        self.cache
+            .vertex_face
+            .get_or_init(|| self.vertex_edge.compose(&self.edge_face))
+    }
+
+    /// Vertex-vertex adjacency, i.e. vertices sharing an edge
+    ///
+    /// The result is square, symmetric, and has no diagonal entries.
+    /// Self-loop edges do not make a vertex adjacent to itself, see [Mesh::vertex_adjacency_with].
+    ///
+    /// The matrix is computed on the first call and kept in the mesh,
+    /// so later calls only borrow it until [Mesh::clear_cache].
+    pub fn vertex_adjacency(&self) -> &Connection {
+        self.cache.vertex_adjacency.get_or_init(|| {
+            let adjacency = self.vertex_edge.compose(&self.edge_vertex);
+            Connection::from_iter(adjacency.indices().filter(|(i, j)| i != j))
+        })
+    }
+
+    /// [Mesh::vertex_adjacency] which also makes a vertex adjacent to itself
+    /// through its self-loop edges if `self_loops` is true
+    ///
+    /// The result is not cached.
+    pub fn vertex_adjacency_with(&self, self_loops: bool) -> Connection {
+        let adjacency = self.vertex_adjacency();
+        if !self_loops {
+            return adjacency.clone();
+        }
+        let loops = (0..self.num_edges()).filter_map(|e| match self.edge_vertices(e) {
+            [v] => Some((to_usize(*v), to_usize(*v))),
+            _ => None,
+        });
+        Connection::from_iter(adjacency.indices().chain(loops))
+    }
+
+    /// Face-face adjacency, i.e. faces sharing an edge
+    ///
+    /// The result is square, symmetric, and has no diagonal entries.
+    /// Computed on the first call and cached like [Mesh::vertex_adjacency].
+    pub fn face_adjacency(&self) -> &Connection {
+        self.cache.face_adjacency.get_or_init(|| {
+            let adjacency = self.face_edge.compose(&self.edge_face);
+            Connection::from_iter(adjacency.indices().filter(|(i, j)| i != j))
+        })
+    }
+
+    /// BFS spanning tree of the vertex graph, see [Mesh::spanning_tree_by]
+    pub fn spanning_tree(&self, root: usize) -> Simplices<'_> {
+        self.spanning_tree_by(root, Traversal::BreadthFirst)
+    }
+
+    /// Spanning tree of each connected component of the vertex graph
+    ///
+    /// The component containing `root` is traversed from `root`,
+    /// and other components from their smallest vertex.
+    /// The result contains all vertices and the edges of the trees.
+    pub fn spanning_tree_by(&self, root: usize, traversal: Traversal) -> Simplices<'_> {
+        self.try_spanning_tree_by(root, traversal)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::spanning_tree_by] returning [Error::OutOfRange] for an invalid root
+    pub fn try_spanning_tree_by(
+        &self,
+        root: usize,
+        traversal: Traversal,
+    ) -> Result<Simplices<'_>, Error> {
+        let n = self.num_vertices();
+        if root >= n {
+            return Err(Error::OutOfRange {
+                kind: "vertex",
+                index: root,
+                len: n,
+            });
+        }
+        let mut visited = vec![false; n];
+        let mut edges = BTreeSet::new();
+        // (vertex, edge reaching the vertex)
+        let mut queue = VecDeque::new();
+        for start in core::iter::once(root).chain(0..n) {
+            if visited[start] {
+                continue;
+            }
+            queue.push_back((start, None));
+            while let Some((v, via)) = match traversal {
+                Traversal::BreadthFirst => queue.pop_front(),
+                Traversal::DepthFirst => queue.pop_back(),
+            } {
+                if visited[v] {
+                    continue;
+                }
+                visited[v] = true;
+                if let Some(e) = via {
+                    edges.insert(e);
+                }
+                let incident = self.vertex_edge.get_connected(v);
+                let next = incident.iter().filter_map(|&e| {
+                    let e = to_usize(e);
+                    let (a, b) = self.edge_ends(e);
+                    let w = if a == v { b } else { a };
+                    if w == v {
+                        return None;
+                    }
+                    Some((w, Some(e)))
+                });
+                // Reverse to visit neighbors in the edge order for DFS
+                match traversal {
+                    Traversal::BreadthFirst => queue.extend(next),
+                    Traversal::DepthFirst => queue.extend(next.rev()),
+                }
+            }
+        }
+        Ok(Simplices {
+            mesh: self,
+            vertices: SimplexSet::from_ids((0..n).collect(), n),
+            edges: SimplexSet::from_ids(edges.into_iter().collect(), self.num_edges()),
+            faces: SimplexSet::default(),
+        })
+    }
+
+    /// Minimum spanning forest of the vertex graph weighted by `weights` per edge
+    ///
+    /// Computed by Kruskal's algorithm, where ties in weight are broken by smaller edge id.
+    /// Returns the selected edges with all vertices, and the total weight.
+    pub fn minimum_spanning_tree(&self, weights: &[f64]) -> (Simplices<'_>, f64) {
+        self.try_minimum_spanning_tree(weights)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::minimum_spanning_tree] returning [Error::ShapeMismatch]
+    /// unless there is a weight per edge
+    pub fn try_minimum_spanning_tree(
+        &self,
+        weights: &[f64],
+    ) -> Result<(Simplices<'_>, f64), Error> {
+        if weights.len() != self.num_edges() {
+            return Err(Error::ShapeMismatch {
+                what: "edge weights",
+                expected: self.num_edges(),
+                actual: weights.len(),
+            });
+        }
+        let mut order: Vec<usize> = (0..self.num_edges()).collect();
+        order.sort_by(|&a, &b| weights[a].total_cmp(&weights[b]).then(a.cmp(&b)));
+
+        let mut forest = UnionFind::new(self.num_vertices());
+        let mut edges = BTreeSet::new();
+        let mut total = 0.0;
+        for e in order {
+            let (a, b) = self.edge_ends(e);
+            if forest.union(a, b) {
+                edges.insert(e);
+                total += weights[e];
+            }
+        }
+        let tree = Simplices {
+            mesh: self,
+            vertices: SimplexSet::from_ids((0..self.num_vertices()).collect(), self.num_vertices()),
+            edges: SimplexSet::from_ids(edges.into_iter().collect(), self.num_edges()),
+            faces: SimplexSet::default(),
+        };
+        Ok((tree, total))
+    }
+
+    /// Color faces so that faces sharing an edge have different colors
+    ///
+    /// Faces are colored greedily in the order of decreasing number of adjacent faces
+    /// (ties broken by smaller face id) with the smallest color not used by the adjacent faces.
+    /// Returns an error if more than `max_colors` colors are required in this order.
+    /// Since colors are `u8`, a `max_colors` above 256 is the same as 256.
+    pub fn face_coloring(&self, max_colors: usize) -> Result<Vec<u8>, ColoringError> {
+        let _timer = Timer::start("face_coloring");
+        greedy_coloring(self.face_adjacency(), self.num_faces(), max_colors)
+    }
+
+    /// Color vertices so that vertices sharing an edge have different colors,
+    /// in the same manner as [Mesh::face_coloring]
+    pub fn vertex_coloring(&self, max_colors: usize) -> Result<Vec<u8>, ColoringError> {
+        greedy_coloring(self.vertex_adjacency(), self.num_vertices(), max_colors)
+    }
+
+    /// Dual mesh, whose vertices are the faces and whose faces are the vertices
+    ///
+    /// Vertex `i` of the dual corresponds to face `i` of this mesh, and face `j` to vertex `j`.
+    /// Edges keep their ids.
+    ///
+    /// Panics if the mesh is not closed, see [Mesh::try_dual].
+    pub fn dual(&self) -> Mesh {
+        self.try_dual().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Mesh::dual] returning [Error::NotClosed] for a mesh which is not closed
+    pub fn try_dual(&self) -> Result<Mesh, Error> {
+        let _timer = Timer::start("dual");
+        if !self.is_closed() {
+            let open_edges = (0..self.num_edges())
+                .filter(|&e| self.edge_faces(e).len() != 2)
+                .count();
+            return Err(Error::NotClosed { open_edges });
+        }
+        match &self.permutation {
+            Some(permutation) => Ok(Mesh::from_permutation(&crate::permutation::dual(&widen(
+                permutation,
+            )))),
+            None => Mesh::try_from_connections(self.face_edge.clone(), self.edge_vertex.clone()),
+        }
     }
 
     /// Get simplicies
-    pub fn simplicies(&self, vertices: &[usize], edges: &[usize], faces: &[usize]) -> Simplices {
+    pub fn simplicies(
+        &self,
+        vertices: &[usize],
+        edges: &[usize],
+        faces: &[usize],
+    ) -> Simplices<'_> {
         Simplices {
             mesh: self,
-            vertices: BTreeSet::from_iter(vertices.iter().cloned()),
-            edges: BTreeSet::from_iter(edges.iter().cloned()),
-            faces: BTreeSet::from_iter(faces.iter().cloned()),
+            vertices: SimplexSet::from_ids(vertices.to_vec(), self.num_vertices()),
+            edges: SimplexSet::from_ids(edges.to_vec(), self.num_edges()),
+            faces: SimplexSet::from_ids(faces.to_vec(), self.num_faces()),
         }
     }
+
+    /// All vertices, edges, and faces of the mesh
+    pub fn all_simplices(&self) -> Simplices<'_> {
+        let all = |n: usize| SimplexSet::from_ids((0..n).collect(), n);
+        Simplices {
+            mesh: self,
+            vertices: all(self.num_vertices()),
+            edges: all(self.num_edges()),
+            faces: all(self.num_faces()),
+        }
+    }
+
+    /// [Mesh::simplicies] returning [Error::OutOfRange] for ids which do not exist
+    pub fn try_simplicies(
+        &self,
+        vertices: &[usize],
+        edges: &[usize],
+        faces: &[usize],
+    ) -> Result<Simplices<'_>, Error> {
+        let groups = [
+            ("vertex", vertices, self.num_vertices()),
+            ("edge", edges, self.num_edges()),
+            ("face", faces, self.num_faces()),
+        ];
+        for &(kind, ids, len) in &groups {
+            if let Some(&index) = ids.iter().find(|&&id| id >= len) {
+                return Err(Error::OutOfRange { kind, index, len });
+            }
+        }
+        Ok(self.simplicies(vertices, edges, faces))
+    }
+}
+
+/// Row of the connection for the `index`-th of `len` elements of `kind`,
+/// which is empty if the row is beyond the last non-empty row
+fn row<'a>(
+    connection: &'a Connection,
+    kind: &'static str,
+    index: usize,
+    len: usize,
+) -> Result<&'a [Index], Error> {
+    if index >= len {
+        return Err(Error::OutOfRange { kind, index, len });
+    }
+    if index < connection.shape().0 {
+        Ok(connection.get_connected(index))
+    } else {
+        Ok(&[])
+    }
+}
+
+/// Greedy coloring fails within the given number of colors
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ColoringError {
+    pub max_colors: usize,
+    /// Element which cannot be colored
+    pub index: usize,
+}
+
+impl fmt::Display for ColoringError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Element {} cannot be colored with {} colors",
+            self.index, self.max_colors
+        )
+    }
+}
+
+impl core::error::Error for ColoringError {}
+
+/// Number of colors representable in `u8`
+const MAX_COLORS: usize = 1 << 8;
+
+fn greedy_coloring(
+    adjacency: &Connection,
+    n: usize,
+    max_colors: usize,
+) -> Result<Vec<u8>, ColoringError> {
+    let max_colors = max_colors.min(MAX_COLORS);
+    let rows = adjacency.shape().0;
+    let neighbors = |i: usize| -> &[Index] {
+        if i < rows {
+            adjacency.get_connected(i)
+        } else {
+            &[]
+        }
+    };
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by_key(|&i| (core::cmp::Reverse(neighbors(i).len()), i));
+
+    let mut colors: Vec<Option<u8>> = vec![None; n];
+    let mut used = vec![false; max_colors];
+    for i in order {
+        used.iter_mut().for_each(|u| *u = false);
+        for &j in neighbors(i) {
+            if let Some(c) = colors[to_usize(j)] {
+                used[c as usize] = true;
+            }
+        }
+        let color = used.iter().position(|u| !u).ok_or(ColoringError {
+            max_colors,
+            index: i,
+        })?;
+        colors[i] = Some(color as u8);
+    }
+    Ok(colors.into_iter().map(Option::unwrap).collect())
+}
+
+/// Selection size from which [Simplices::star] and [Simplices::closure] gather in parallel
+#[cfg(feature = "parallel")]
+const DEFAULT_THRESHOLD: usize = crate::parallel::DEFAULT_THRESHOLD;
+#[cfg(not(feature = "parallel"))]
+const DEFAULT_THRESHOLD: usize = usize::MAX;
+
+/// Number of half-edges from which [Mesh::from_permutation] gathers the orbits in parallel
+#[cfg(feature = "parallel")]
+const ORBIT_THRESHOLD: usize = crate::parallel::DEFAULT_ORBIT_THRESHOLD;
+#[cfg(not(feature = "parallel"))]
+const ORBIT_THRESHOLD: usize = usize::MAX;
+
+/// Vertices and faces of the permutation, see [gather_vertices] and [gather_faces]
+///
+/// Runs on several threads from `threshold` half-edges with the `parallel` feature.
+fn gather_orbits(permutation: &[usize], threshold: usize) -> (Vec<Orbit>, Vec<Orbit>) {
+    #[cfg(feature = "parallel")]
+    if permutation.len() >= threshold && crate::parallel::num_threads() > 1 {
+        let threads = crate::parallel::num_threads();
+        return (
+            crate::parallel::gather_vertices(permutation, threads),
+            crate::parallel::gather_faces(permutation, threads),
+        );
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = threshold;
+    (gather_vertices(permutation), gather_faces(permutation))
+}
+
+/// Simplices connected to any of `ids` by the connection matrix
+///
+/// Runs on several threads from `threshold` ids with the `parallel` feature.
+fn gather(
+    connection: &Connection,
+    ids: &SimplexSet,
+    universe: usize,
+    threshold: usize,
+) -> SimplexSet {
+    #[cfg(feature = "parallel")]
+    if ids.len() >= threshold && crate::parallel::num_threads() > 1 {
+        let ids: Vec<usize> = ids.iter().collect();
+        let connected = crate::parallel::gather(connection, &ids, crate::parallel::num_threads());
+        return SimplexSet::from_ids(connected, universe);
+    }
+    #[cfg(not(feature = "parallel"))]
+    let _ = threshold;
+    let connected = ids
+        .iter()
+        .flat_map(|id| connection.get_connected(id).iter().map(|&i| to_usize(i)))
+        .collect();
+    SimplexSet::from_ids(connected, universe)
+}
+
+/// Disjoint sets of indices
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    /// Merge sets containing `a` and `b`, and returns false if they are already in the same set
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        self.parent[a] = b;
+        true
+    }
 }
 
 /// Simplices in the mesh
 ///
 /// - Simplex on the half-edge mesh must be one of vertex, edge, and face.
+/// - The ids of each dimension are a [SimplexSet], which is a bitset if it covers a large part of the mesh.
 #[derive(Debug, Clone)]
 pub struct Simplices<'mesh> {
     mesh: &'mesh Mesh,
-    vertices: BTreeSet<usize>,
-    edges: BTreeSet<usize>,
-    faces: BTreeSet<usize>,
+    vertices: SimplexSet,
+    edges: SimplexSet,
+    faces: SimplexSet,
 }
 
-impl<'mesh> std::ops::Sub for Simplices<'mesh> {
+impl<'mesh> core::ops::Sub for Simplices<'mesh> {
     type Output = Self;
     fn sub(self, other: Self) -> Self {
+        self.combine(&other, SimplexSet::difference)
+    }
+}
+
+impl<'mesh> core::ops::BitOr for Simplices<'mesh> {
+    type Output = Self;
+    fn bitor(self, other: Self) -> Self {
+        self.combine(&other, SimplexSet::union)
+    }
+}
+
+impl<'mesh> core::ops::BitAnd for Simplices<'mesh> {
+    type Output = Self;
+    fn bitand(self, other: Self) -> Self {
+        self.combine(&other, SimplexSet::intersection)
+    }
+}
+
+impl<'mesh> Simplices<'mesh> {
+    pub fn vertices(&self) -> &SimplexSet {
+        &self.vertices
+    }
+
+    pub fn edges(&self) -> &SimplexSet {
+        &self.edges
+    }
+
+    pub fn faces(&self) -> &SimplexSet {
+        &self.faces
+    }
+
+    /// The same simplices with every dimension stored as a bitset
+    ///
+    /// Results of the operations choose their representation by density again.
+    pub fn to_dense(&self) -> Self {
         Simplices {
             mesh: self.mesh,
-            vertices: self.vertices.difference(&other.vertices).cloned().collect(),
-            edges: self.edges.difference(&other.edges).cloned().collect(),
-            faces: self.faces.difference(&other.faces).cloned().collect(),
+            vertices: self.vertices.to_dense(self.mesh.num_vertices()),
+            edges: self.edges.to_dense(self.mesh.num_edges()),
+            faces: self.faces.to_dense(self.mesh.num_faces()),
         }
     }
-}
 
-impl<'mesh> Simplices<'mesh> {
     pub fn is_empty(&self) -> bool {
         self.vertices.is_empty() && self.edges.is_empty() && self.faces.is_empty()
     }
 
     pub fn is_complex(&self) -> bool {
-        let edges = self
-            .mesh
-            .face_edge
-            .gather_connected(self.faces.iter().cloned());
+        let edges = gather(
+            &self.mesh.face_edge,
+            &self.faces,
+            self.mesh.num_edges(),
+            DEFAULT_THRESHOLD,
+        );
         if !edges.is_subset(&self.edges) {
             return false;
         }
-        let vertices = self
-            .mesh
-            .edge_vertex
-            .gather_connected(edges.iter().cloned());
+        let vertices = gather(
+            &self.mesh.edge_vertex,
+            &edges,
+            self.mesh.num_vertices(),
+            DEFAULT_THRESHOLD,
+        );
         vertices.is_subset(&self.vertices)
     }
 
+    /// Dimension of the complex if every simplex is a face of a simplex of the top dimension
     pub fn is_pure_complex(&self) -> Option<usize> {
-        let edges = self
-            .mesh
-            .face_edge
-            .gather_connected(self.faces.iter().cloned());
-        if edges != self.edges {
-            return None;
+        if !self.faces.is_empty() {
+            let edges = gather(
+                &self.mesh.face_edge,
+                &self.faces,
+                self.mesh.num_edges(),
+                DEFAULT_THRESHOLD,
+            );
+            if edges != self.edges {
+                return None;
+            }
         }
-        let vertices = self
-            .mesh
-            .edge_vertex
-            .gather_connected(edges.iter().cloned());
-        if vertices != self.vertices {
-            return None;
+        if !self.edges.is_empty() {
+            let vertices = gather(
+                &self.mesh.edge_vertex,
+                &self.edges,
+                self.mesh.num_vertices(),
+                DEFAULT_THRESHOLD,
+            );
+            if vertices != self.vertices {
+                return None;
+            }
         }
         if !self.faces.is_empty() {
             return Some(2);
@@ -142,17 +1086,22 @@ impl<'mesh> Simplices<'mesh> {
 
     /// Star operation `St(S)` (not Hodge star)
     pub fn star(&self) -> Self {
-        let mut edges = self
-            .mesh
-            .vertex_edge
-            .gather_connected(self.vertices.iter().cloned());
-        for &edge in &self.edges {
-            edges.insert(edge);
-        }
-        let mut faces = self.mesh.edge_face.gather_connected(edges.iter().cloned());
-        for &face in &self.faces {
-            faces.insert(face);
-        }
+        self.star_with(DEFAULT_THRESHOLD)
+    }
+
+    /// [Simplices::star] gathering on several threads from `threshold` selected simplices,
+    /// `usize::MAX` keeps it sequential
+    #[cfg(feature = "parallel")]
+    pub fn star_with_threshold(&self, threshold: usize) -> Self {
+        self.star_with(threshold)
+    }
+
+    fn star_with(&self, threshold: usize) -> Self {
+        let (num_edges, num_faces) = (self.mesh.num_edges(), self.mesh.num_faces());
+        let edges = gather(&self.mesh.vertex_edge, &self.vertices, num_edges, threshold)
+            .union(&self.edges, num_edges);
+        let faces = gather(&self.mesh.edge_face, &edges, num_faces, threshold)
+            .union(&self.faces, num_faces);
         Self {
             mesh: self.mesh,
             vertices: self.vertices.clone(),
@@ -163,20 +1112,22 @@ impl<'mesh> Simplices<'mesh> {
 
     /// Closure operation `Cl(S)`
     pub fn closure(&self) -> Self {
-        let mut edges = self
-            .mesh
-            .face_edge
-            .gather_connected(self.faces.iter().cloned());
-        for &edge in &self.edges {
-            edges.insert(edge);
-        }
-        let mut vertices = self
-            .mesh
-            .edge_vertex
-            .gather_connected(edges.iter().cloned());
-        for &vertex in &self.vertices {
-            vertices.insert(vertex);
-        }
+        self.closure_with(DEFAULT_THRESHOLD)
+    }
+
+    /// [Simplices::closure] gathering on several threads from `threshold` selected simplices,
+    /// `usize::MAX` keeps it sequential
+    #[cfg(feature = "parallel")]
+    pub fn closure_with_threshold(&self, threshold: usize) -> Self {
+        self.closure_with(threshold)
+    }
+
+    fn closure_with(&self, threshold: usize) -> Self {
+        let (num_vertices, num_edges) = (self.mesh.num_vertices(), self.mesh.num_edges());
+        let edges = gather(&self.mesh.face_edge, &self.faces, num_edges, threshold)
+            .union(&self.edges, num_edges);
+        let vertices = gather(&self.mesh.edge_vertex, &edges, num_vertices, threshold)
+            .union(&self.vertices, num_vertices);
         Self {
             mesh: self.mesh,
             vertices,
@@ -191,7 +1142,813 @@ impl<'mesh> Simplices<'mesh> {
     }
 
     /// Boundary operation `bd(S)`
+    ///
+    /// The closure of the simplices which are proper faces of exactly one simplex of `S`.
+    /// Panics unless `S` is a pure subcomplex (see [Simplices::is_pure_complex]).
     pub fn boundary(&self) -> Self {
-        unimplemented!()
+        self.try_boundary().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// [Simplices::boundary] returning [Error::NotPureComplex] unless `S` is a pure subcomplex
+    pub fn try_boundary(&self) -> Result<Self, Error> {
+        let dim = self.is_pure_complex().ok_or(Error::NotPureComplex)?;
+        let (simplices, faces_of) = match dim {
+            2 => (&self.faces, &self.mesh.face_edge),
+            1 => (&self.edges, &self.mesh.edge_vertex),
+            _ => return Ok(self.mesh.simplicies(&[], &[], &[])),
+        };
+        let mut count = BTreeMap::new();
+        for s in simplices {
+            for &t in faces_of.get_connected(s) {
+                *count.entry(to_usize(t)).or_insert(0) += 1;
+            }
+        }
+        let once: Vec<usize> = count
+            .into_iter()
+            .filter(|&(_, n)| n == 1)
+            .map(|(t, _)| t)
+            .collect();
+        if dim == 2 {
+            Ok(self.mesh.simplicies(&[], &once, &[]).closure())
+        } else {
+            Ok(self.mesh.simplicies(&once, &[], &[]))
+        }
+    }
+
+    /// Split into the pieces connected through shared vertices
+    ///
+    /// Selected simplices sharing a vertex, selected or not, are in the same piece,
+    /// e.g. two faces sharing an edge or a corner. Unlike [Mesh::num_components],
+    /// a selection scattered over a connected mesh has several pieces.
+    /// An isolated selected vertex is a piece by itself, and the pieces are sorted by their smallest vertex.
+    pub fn connected_components(&self) -> Vec<Self> {
+        let mesh = self.mesh;
+        let mut pieces = UnionFind::new(mesh.num_vertices());
+        for e in &self.edges {
+            let (a, b) = mesh.edge_ends(e);
+            pieces.union(a, b);
+        }
+        for f in &self.faces {
+            for &e in mesh.face_edge.get_connected(f) {
+                let (a, b) = mesh.edge_ends(to_usize(e));
+                pieces.union(a, b);
+            }
+        }
+
+        // a vertex of each simplex
+        let face_vertex = |f: usize| {
+            mesh.edge_ends(to_usize(mesh.face_edge.get_connected(f)[0]))
+                .0
+        };
+        let mut used = vec![false; mesh.num_vertices()];
+        let vertices = self.vertices.iter();
+        let edges = self.edges.iter().map(|e| mesh.edge_ends(e).0);
+        for v in vertices
+            .chain(edges)
+            .chain(self.faces.iter().map(face_vertex))
+        {
+            let root = pieces.find(v);
+            used[root] = true;
+        }
+        let mut index = vec![usize::MAX; mesh.num_vertices()];
+        let mut ids: Vec<[Vec<usize>; 3]> = Vec::new();
+        for v in 0..mesh.num_vertices() {
+            let root = pieces.find(v);
+            if used[root] && index[root] == usize::MAX {
+                index[root] = ids.len();
+                ids.push(Default::default());
+            }
+        }
+
+        for v in &self.vertices {
+            ids[index[pieces.find(v)]][0].push(v);
+        }
+        for e in &self.edges {
+            ids[index[pieces.find(mesh.edge_ends(e).0)]][1].push(e);
+        }
+        for f in &self.faces {
+            ids[index[pieces.find(face_vertex(f))]][2].push(f);
+        }
+        ids.into_iter()
+            .map(|[vertices, edges, faces]| Simplices {
+                mesh,
+                vertices: SimplexSet::from_ids(vertices, mesh.num_vertices()),
+                edges: SimplexSet::from_ids(edges, mesh.num_edges()),
+                faces: SimplexSet::from_ids(faces, mesh.num_faces()),
+            })
+            .collect()
+    }
+
+    /// Apply a set operation to each dimension
+    fn combine(&self, other: &Self, op: fn(&SimplexSet, &SimplexSet, usize) -> SimplexSet) -> Self {
+        Simplices {
+            mesh: self.mesh,
+            vertices: op(&self.vertices, &other.vertices, self.mesh.num_vertices()),
+            edges: op(&self.edges, &other.edges, self.mesh.num_edges()),
+            faces: op(&self.faces, &other.faces, self.mesh.num_faces()),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    /// Check that the edges form a forest spanning each component
+    fn assert_spanning_forest(mesh: &Mesh, tree: &Simplices, components: usize) {
+        let n = mesh.num_vertices();
+        assert_eq!(tree.vertices().len(), n);
+        assert_eq!(tree.edges().len(), n - components);
+        assert!(tree.faces().is_empty());
+        assert!(tree.is_complex());
+
+        // each tree edge must join two different trees
+        let mut forest = UnionFind::new(n);
+        for e in tree.edges() {
+            let (a, b) = mesh.edge_ends(e);
+            assert!(forest.union(a, b), "Cycle found at edge {}", e);
+        }
+        let roots: BTreeSet<_> = (0..n).map(|v| forest.find(v)).collect();
+        assert_eq!(roots.len(), components);
+    }
+
+    #[test]
+    fn spanning_tree() {
+        let mesh = fixtures::CUBE.mesh();
+        for root in 0..mesh.num_vertices() {
+            for &traversal in &[Traversal::BreadthFirst, Traversal::DepthFirst] {
+                let tree = mesh.spanning_tree_by(root, traversal);
+                assert_spanning_forest(&mesh, &tree, 1);
+            }
+        }
+        // BFS tree of the cube contains all edges incident to the root
+        let tree = mesh.spanning_tree(0);
+        for &e in mesh.vertex_edge.get_connected(0) {
+            assert!(tree.edges().contains(to_usize(e)));
+        }
+    }
+
+    #[test]
+    fn spanning_tree_components() {
+        let mesh = fixtures::TWO_TETRAHEDRA.mesh();
+        assert_eq!(mesh.num_vertices(), 8);
+        let tree = mesh.spanning_tree(5);
+        assert_spanning_forest(&mesh, &tree, 2);
+        let tree = mesh.spanning_tree_by(5, Traversal::DepthFirst);
+        assert_spanning_forest(&mesh, &tree, 2);
+        // DFS tree of a tetrahedron is a path
+        let tree = mesh.spanning_tree_by(0, Traversal::DepthFirst);
+        for v in 0..4 {
+            let degree = mesh
+                .vertex_edge
+                .get_connected(v)
+                .iter()
+                .filter(|&&e| tree.edges().contains(to_usize(e)))
+                .count();
+            assert!(degree <= 2);
+        }
+    }
+
+    /// `nx` x `ny` vertices connected by quadrangles,
+    /// where horizontal edges come first
+    fn grid(nx: usize, ny: usize) -> Mesh {
+        grid_with_holes(nx, ny, &[])
+    }
+
+    /// Quad grid without the faces at the given `(i, j)`
+    fn grid_with_holes(nx: usize, ny: usize, holes: &[(usize, usize)]) -> Mesh {
+        let horizontal = (nx - 1) * ny;
+        let mut vertex_edge = Vec::new();
+        let mut edge_face = Vec::new();
+        for j in 0..ny {
+            for i in 0..nx - 1 {
+                let e = i + (nx - 1) * j;
+                vertex_edge.push((i + nx * j, e));
+                vertex_edge.push((i + 1 + nx * j, e));
+            }
+        }
+        for j in 0..ny - 1 {
+            for i in 0..nx {
+                let e = horizontal + i + nx * j;
+                vertex_edge.push((i + nx * j, e));
+                vertex_edge.push((i + nx * (j + 1), e));
+            }
+        }
+        let mut num_faces = 0;
+        for j in 0..ny - 1 {
+            for i in 0..nx - 1 {
+                if holes.contains(&(i, j)) {
+                    continue;
+                }
+                let f = num_faces;
+                num_faces += 1;
+                edge_face.push((i + (nx - 1) * j, f));
+                edge_face.push((i + (nx - 1) * (j + 1), f));
+                edge_face.push((horizontal + i + nx * j, f));
+                edge_face.push((horizontal + i + 1 + nx * j, f));
+            }
+        }
+        Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        )
+    }
+
+    #[test]
+    fn minimum_spanning_tree() {
+        let (nx, ny) = (5, 4);
+        let mesh = grid(nx, ny);
+        let horizontal = (nx - 1) * ny;
+        // vertical edges are twice as long
+        let weights: Vec<f64> = (0..mesh.num_edges())
+            .map(|e| if e < horizontal { 1.0 } else { 2.0 })
+            .collect();
+        let (tree, length) = mesh.minimum_spanning_tree(&weights);
+        assert_spanning_forest(&mesh, &tree, 1);
+        assert_eq!(length, (horizontal + 2 * (ny - 1)) as f64);
+        // ties are broken by edge id, i.e. the first column of vertical edges
+        let vertical: Vec<_> = tree.edges().iter().filter(|&e| e >= horizontal).collect();
+        assert_eq!(
+            vertical,
+            vec![horizontal, horizontal + nx, horizontal + 2 * nx]
+        );
+    }
+
+    #[test]
+    fn minimum_spanning_tree_deterministic() {
+        let mesh = fixtures::TWO_TETRAHEDRA.mesh();
+        let weights = vec![1.0; mesh.num_edges()];
+        let (tree, length) = mesh.minimum_spanning_tree(&weights);
+        assert_spanning_forest(&mesh, &tree, 2);
+        assert_eq!(length, 6.0);
+        // edges 2 and 8 close triangles with smaller edges
+        let edges: Vec<_> = tree.edges().iter().collect();
+        assert_eq!(edges, vec![0, 1, 3, 6, 7, 9]);
+    }
+
+    fn assert_proper_coloring(adjacency: &Connection, colors: &[u8]) {
+        for (i, j) in adjacency.indices() {
+            assert_ne!(colors[i], colors[j], "{} and {} have the same color", i, j);
+        }
+    }
+
+    #[test]
+    fn icosahedron() {
+        let mesh = fixtures::ICOSAHEDRON.mesh();
+        assert_eq!(mesh.num_vertices(), 12);
+        assert_eq!(mesh.num_edges(), 30);
+        assert_eq!(mesh.num_faces(), 20);
+        assert_eq!(mesh.euler_characteristic(), 2);
+    }
+
+    #[test]
+    fn euler_characteristic() {
+        assert_eq!(fixtures::TETRAHEDRON.mesh().euler_characteristic(), 2);
+        assert_eq!(fixtures::CUBE.mesh().euler_characteristic(), 2);
+        assert_eq!(fixtures::TWO_TETRAHEDRA.mesh().euler_characteristic(), 4);
+        assert_eq!(grid(3, 3).euler_characteristic(), 1);
+    }
+
+    #[test]
+    fn dual() {
+        let cube = fixtures::CUBE.mesh();
+        let octahedron = cube.dual();
+        assert_eq!(
+            (
+                octahedron.num_vertices(),
+                octahedron.num_edges(),
+                octahedron.num_faces()
+            ),
+            (6, 12, 8)
+        );
+        for f in 0..cube.num_faces() {
+            assert_eq!(octahedron.vertex_edges(f), cube.face_edges(f));
+        }
+        assert_eq!(octahedron.dual().permutation(), cube.permutation());
+
+        // same for the mesh without permutation
+        let vertex_edge = (0..cube.num_vertices())
+            .flat_map(|v| cube.vertex_edges(v).iter().map(move |&e| (v, to_usize(e))))
+            .collect();
+        let edge_face = (0..cube.num_edges())
+            .flat_map(|e| cube.edge_faces(e).iter().map(move |&f| (e, to_usize(f))))
+            .collect();
+        let cube = Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        );
+        let octahedron = cube.dual();
+        assert_eq!(octahedron.num_vertices(), 6);
+        assert_eq!(octahedron.num_faces(), 8);
+        assert!((0..8).all(|f| octahedron.face_edges(f).len() == 3));
+    }
+
+    #[test]
+    fn cube_face_coloring() {
+        let mesh = fixtures::CUBE.mesh();
+        let adjacency = mesh.face_adjacency();
+        let colors = mesh.face_coloring(3).unwrap();
+        assert_proper_coloring(adjacency, &colors);
+        // opposite faces share colors
+        for f in 0..6 {
+            for g in 0..6 {
+                if f != g && !adjacency.get_connected(f).contains(&to_index(g)) {
+                    assert_eq!(colors[f], colors[g]);
+                }
+            }
+        }
+        assert_eq!(
+            mesh.face_coloring(2),
+            Err(ColoringError {
+                max_colors: 2,
+                index: 2
+            })
+        );
+    }
+
+    #[test]
+    fn coloring() {
+        for fixture in &fixtures::ALL[..4] {
+            let mesh = fixture.mesh();
+            let colors = mesh.face_coloring(4).unwrap();
+            assert_proper_coloring(mesh.face_adjacency(), &colors);
+            let colors = mesh.vertex_coloring(5).unwrap();
+            assert_proper_coloring(mesh.vertex_adjacency(), &colors);
+        }
+        // the vertex graph of a tetrahedron is complete
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        assert!(mesh.vertex_coloring(3).is_err());
+        assert_eq!(mesh.vertex_coloring(usize::MAX), mesh.vertex_coloring(4));
+    }
+
+    #[test]
+    fn topology() {
+        for fixture in &[fixtures::TETRAHEDRON, fixtures::CUBE, fixtures::ICOSAHEDRON] {
+            let mesh = fixture.mesh();
+            assert!(mesh.is_closed());
+            assert!(mesh.is_manifold());
+            assert_eq!(mesh.num_components(), 1);
+            assert_eq!(mesh.num_boundary_loops(), 0);
+            assert_eq!(mesh.genus(), Some(0));
+        }
+
+        let mesh = fixtures::TWO_TETRAHEDRA.mesh();
+        assert_eq!(mesh.num_components(), 2);
+        assert_eq!(mesh.genus(), Some(0));
+
+        let mesh = grid(4, 3);
+        assert!(!mesh.is_closed());
+        assert!(mesh.is_manifold());
+        assert_eq!(mesh.boundary_edges().len(), 2 * 3 + 2 * 2);
+        assert_eq!(mesh.num_boundary_loops(), 1);
+        assert_eq!(mesh.genus(), Some(0));
+    }
+
+    #[test]
+    fn link() {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        let link = mesh.simplicies(&[0], &[], &[]).link();
+        assert_eq!((link.vertices().len(), link.edges().len()), (3, 3));
+        assert!(link.faces().is_empty() && !link.vertices().contains(0));
+
+        // hexagon around a vertex of the cube
+        let mesh = fixtures::CUBE.mesh();
+        let link = mesh.simplicies(&[0], &[], &[]).link();
+        assert_eq!((link.vertices().len(), link.edges().len()), (6, 6));
+        assert_eq!(link.is_pure_complex(), Some(1));
+    }
+
+    #[test]
+    fn connected_components() {
+        let mesh = fixtures::ICOSAHEDRON.mesh();
+        let adjacency = mesh.vertex_adjacency();
+        let neighbors = |v: usize| -> Vec<usize> {
+            adjacency
+                .get_connected(v)
+                .iter()
+                .map(|&w| to_usize(w))
+                .filter(|&w| w != v)
+                .collect()
+        };
+        // the vertex opposite to vertex 0, whose neighbors are not adjacent to 0
+        let near = mesh.simplicies(&[0], &[], &[]).star().closure();
+        let opposite = (0..12)
+            .find(|&v| (neighbors(v).iter().chain(&[v])).all(|&w| !near.vertices().contains(w)))
+            .unwrap();
+
+        // five faces around vertex 0, two adjacent faces at the opposite vertex, and a stray vertex
+        let cap: Vec<usize> = near.faces().iter().collect();
+        let star: Vec<usize> = mesh
+            .simplicies(&[opposite], &[], &[])
+            .star()
+            .faces()
+            .iter()
+            .collect();
+        let pair: Vec<usize> = (1..star.len())
+            .map(|i| vec![star[0], star[i]])
+            .find(|pair| {
+                mesh.face_adjacency()
+                    .get_connected(pair[0])
+                    .contains(&to_index(pair[1]))
+            })
+            .unwrap();
+        let pair_vertices = mesh.simplicies(&[], &[], &pair).closure();
+        let stray = neighbors(opposite)
+            .into_iter()
+            .find(|&v| !pair_vertices.vertices().contains(v))
+            .unwrap();
+        let faces: Vec<usize> = cap.iter().chain(&pair).cloned().collect();
+        let selection = mesh.simplicies(&[stray], &[], &faces);
+        assert_eq!(mesh.num_components(), 1);
+
+        let components = selection.connected_components();
+        assert_eq!(components.len(), 3);
+        let ids = |s: &Simplices| {
+            [
+                s.vertices().to_btree_set(),
+                s.edges().to_btree_set(),
+                s.faces().to_btree_set(),
+            ]
+        };
+        let expected = [
+            mesh.simplicies(&[], &[], &cap),
+            mesh.simplicies(&[], &[], &pair),
+            mesh.simplicies(&[stray], &[], &[]),
+        ];
+        for piece in &expected {
+            assert_eq!(
+                components.iter().filter(|c| ids(c) == ids(piece)).count(),
+                1
+            );
+        }
+        // sorted by the smallest vertex
+        let first = |s: &Simplices| s.closure().vertices().iter().next().unwrap();
+        assert!(components.windows(2).all(|w| first(&w[0]) < first(&w[1])));
+        assert_eq!(first(&components[0]), 0);
+
+        // a closed selection and the whole mesh are a single piece
+        assert_eq!(near.connected_components().len(), 1);
+        assert!(mesh
+            .simplicies(&[], &[], &[])
+            .connected_components()
+            .is_empty());
+        assert_eq!(mesh.all_simplices().connected_components().len(), 1);
+    }
+
+    #[test]
+    fn memory_usage() {
+        let mesh = fixtures::CUBE.mesh();
+        let size = core::mem::size_of::<Index>();
+        // row offsets and incidences of A0, A1, and their transposes, and the permutation
+        let indices = (9 + 24) + (13 + 24) + (13 + 24) + (7 + 24) + 24;
+        let report = mesh.memory_usage();
+        assert_eq!(report.buffers.len(), 9);
+        assert_eq!(
+            (report.used(), report.allocated()),
+            (indices * size, indices * size)
+        );
+
+        // 8 vertices with 3 neighbors each
+        mesh.vertex_adjacency();
+        assert_eq!(mesh.memory_usage().used(), (indices + 9 + 24) * size);
+        assert!(mesh
+            .memory_usage()
+            .buffers
+            .iter()
+            .any(|buffer| buffer.name == "vertex_adjacency.fr"));
+
+        // a mesh built with a large hint keeps no capacity of the builder
+        let mut builder = crate::builder::MeshBuilder::new(10_000);
+        for _ in 0..4 {
+            builder.add_vertex();
+        }
+        for face in &[[0, 1, 2], [0, 3, 1], [1, 3, 2], [2, 3, 0]] {
+            builder.add_face(face).unwrap();
+        }
+        let mut mesh = builder.build().unwrap();
+        let report = mesh.memory_usage();
+        assert_eq!(report.allocated(), report.used());
+
+        mesh.permutation.as_mut().unwrap().reserve(1000);
+        assert!(mesh.memory_usage().allocated() >= report.used() + 1000 * size);
+        mesh.shrink_to_fit();
+        assert_eq!(mesh.memory_usage(), report);
+    }
+
+    /// Same simplices with every dimension stored in a BTreeSet
+    fn sparse<'mesh>(s: &Simplices<'mesh>) -> Simplices<'mesh> {
+        Simplices {
+            mesh: s.mesh,
+            vertices: s.vertices.to_btree_set().into(),
+            edges: s.edges.to_btree_set().into(),
+            faces: s.faces.to_btree_set().into(),
+        }
+    }
+
+    #[test]
+    fn dense_simplices() {
+        for seed in 0..8 {
+            let mesh =
+                Mesh::from_permutation(&crate::shapes::random_triangulation(40, seed).unwrap());
+            let every = |n: usize, step: usize| -> Vec<usize> {
+                (seed as usize..n).step_by(step).collect()
+            };
+            let a = mesh.simplicies(
+                &every(mesh.num_vertices(), 3),
+                &every(mesh.num_edges(), 5),
+                &[],
+            );
+            let b = mesh.simplicies(
+                &[],
+                &every(mesh.num_edges(), 2),
+                &every(mesh.num_faces(), 7),
+            );
+            let ids = |s: &Simplices| {
+                [
+                    s.vertices().to_btree_set(),
+                    s.edges().to_btree_set(),
+                    s.faces().to_btree_set(),
+                ]
+            };
+            for x in [sparse(&a), a.to_dense()] {
+                for y in [sparse(&b), b.to_dense()] {
+                    assert_eq!(ids(&x.star()), ids(&a.star()));
+                    assert_eq!(ids(&x.closure()), ids(&a.closure()));
+                    assert_eq!(ids(&x.link()), ids(&a.link()));
+                    assert_eq!(x.is_complex(), a.is_complex());
+                    assert_eq!(x.closure().is_pure_complex(), a.closure().is_pure_complex());
+                    assert_eq!(ids(&(x.clone() - y.clone())), ids(&(a.clone() - b.clone())));
+                    assert_eq!(ids(&(x.clone() | y.clone())), ids(&(a.clone() | b.clone())));
+                    assert_eq!(ids(&(x.clone() & y.clone())), ids(&(a.clone() & b.clone())));
+                }
+            }
+            let union = a.clone() | b.clone();
+            for v in 0..mesh.num_vertices() {
+                assert_eq!(union.vertices().contains(v), a.vertices().contains(v));
+            }
+            assert!((a.clone() & b.clone()).vertices().is_empty());
+        }
+
+        let mesh = fixtures::CUBE.mesh();
+        let all = mesh.all_simplices();
+        assert!(all.is_complex() && all.faces().is_dense());
+        let rest = all - mesh.simplicies(&[0], &[], &[]).star();
+        assert_eq!(
+            (
+                rest.vertices().len(),
+                rest.edges().len(),
+                rest.faces().len()
+            ),
+            (7, 9, 3)
+        );
+    }
+
+    #[test]
+    fn simplices_boundary() {
+        let mesh = fixtures::CUBE.mesh();
+        let face = mesh.simplicies(&[], &[], &[0]).closure();
+        let boundary = face.boundary();
+        assert_eq!((boundary.vertices().len(), boundary.edges().len()), (4, 4));
+        assert!(boundary.boundary().is_empty());
+
+        let star = mesh.simplicies(&[0], &[], &[]).star().closure();
+        let boundary = star.boundary();
+        assert_eq!(
+            boundary.edges(),
+            mesh.simplicies(&[0], &[], &[]).link().edges()
+        );
+
+        let all: Vec<usize> = (0..mesh.num_faces()).collect();
+        assert!(mesh
+            .simplicies(&[], &[], &all)
+            .closure()
+            .boundary()
+            .is_empty());
+    }
+
+    #[test]
+    fn boundary_loops() {
+        assert!(fixtures::CUBE.mesh().boundary_loops().is_empty());
+
+        let loops = grid(4, 3).boundary_loops();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 10);
+        let mut vertices = loops[0].clone();
+        vertices.sort_unstable();
+        assert_eq!(vertices, vec![0, 1, 2, 3, 4, 7, 8, 9, 10, 11]);
+
+        // annulus
+        let mesh = grid_with_holes(4, 4, &[(1, 1)]);
+        assert_eq!(mesh.num_boundary_loops(), 2);
+        let loops = mesh.boundary_loops();
+        assert_eq!(loops.len(), 2);
+        assert_eq!(loops[0].len(), 12);
+        let mut inner = loops[1].clone();
+        inner.sort_unstable();
+        assert_eq!(inner, vec![5, 6, 9, 10]);
+        assert_eq!(mesh.genus(), Some(0));
+
+        // a long strip, whose loop is walked in linear time
+        let mesh = grid(20_000, 2);
+        let loops = mesh.boundary_loops();
+        assert_eq!(loops.len(), 1);
+        assert_eq!(loops[0].len(), 40_000);
+        let adjacency = mesh.vertex_adjacency();
+        for (i, &v) in loops[0].iter().enumerate() {
+            let next = loops[0][(i + 1) % loops[0].len()];
+            assert!(widen(adjacency.get_connected(v)).contains(&next));
+        }
+    }
+
+    #[test]
+    fn non_manifold() {
+        // three triangles sharing the edge 0 = (0, 1)
+        let vertex_edge = Connection::from_vec(vec![
+            (0, 0),
+            (1, 0),
+            (0, 1),
+            (2, 1),
+            (1, 2),
+            (2, 2),
+            (0, 3),
+            (3, 3),
+            (1, 4),
+            (3, 4),
+            (0, 5),
+            (4, 5),
+            (1, 6),
+            (4, 6),
+        ]);
+        let edge_face = Connection::from_vec(vec![
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (0, 1),
+            (3, 1),
+            (4, 1),
+            (0, 2),
+            (5, 2),
+            (6, 2),
+        ]);
+        let mesh = Mesh::from_connections(vertex_edge, edge_face);
+        assert!(!mesh.is_manifold());
+        assert_eq!(mesh.genus(), None);
+
+        // two triangles sharing only the vertex 0
+        let vertex_edge = Connection::from_vec(vec![
+            (0, 0),
+            (1, 0),
+            (1, 1),
+            (2, 1),
+            (0, 2),
+            (2, 2),
+            (0, 3),
+            (3, 3),
+            (3, 4),
+            (4, 4),
+            (0, 5),
+            (4, 5),
+        ]);
+        let edge_face = Connection::from_vec(vec![(0, 0), (1, 0), (2, 0), (3, 1), (4, 1), (5, 1)]);
+        let mesh = Mesh::from_connections(vertex_edge, edge_face);
+        assert!(!mesh.is_manifold());
+        assert!(matches!(
+            mesh.check_manifold(),
+            Err(Error::NonManifold {
+                kind: "vertex",
+                index: 0
+            })
+        ));
+        assert_eq!(mesh.num_components(), 1);
+    }
+
+    fn assert_symmetric_without_diagonal(adjacency: &Connection) {
+        assert_eq!(adjacency, &adjacency.transpose());
+        assert!(adjacency.indices().all(|(i, j)| i != j));
+    }
+
+    #[test]
+    fn tetrahedron_adjacency() {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        let adjacency = mesh.vertex_adjacency();
+        assert_eq!(adjacency.shape(), (4, 4));
+        assert_symmetric_without_diagonal(adjacency);
+        for v in 0..4 {
+            assert_eq!(adjacency.get_connected(v).len(), 3);
+        }
+    }
+
+    #[test]
+    fn cube_vertex_adjacency() {
+        let mesh = fixtures::CUBE.mesh();
+        let adjacency = mesh.vertex_adjacency();
+        assert_eq!(adjacency.shape(), (8, 8));
+        assert_symmetric_without_diagonal(adjacency);
+        for v in 0..8 {
+            assert_eq!(adjacency.get_connected(v).len(), 3);
+        }
+    }
+
+    #[test]
+    fn self_loop_adjacency() {
+        // edge 0 is a self-loop at vertex 0, and edge 1 joins the vertices 0 and 1
+        let vertex_edge = Connection::from_vec(vec![(0, 0), (0, 1), (1, 1)]);
+        let edge_face = Connection::from_vec(vec![(0, 0), (1, 0)]);
+        let mesh = Mesh::from_connections(vertex_edge, edge_face);
+        assert_symmetric_without_diagonal(mesh.vertex_adjacency());
+        assert_eq!(&mesh.vertex_adjacency_with(false), mesh.vertex_adjacency());
+
+        let adjacency = mesh.vertex_adjacency_with(true);
+        assert_eq!(adjacency, adjacency.transpose());
+        assert_eq!(
+            adjacency.indices().collect::<Vec<_>>(),
+            vec![(0, 0), (0, 1), (1, 0)]
+        );
+    }
+
+    #[test]
+    fn cube_face_vertices() {
+        let mesh = fixtures::CUBE.mesh();
+        let adjacency = mesh.vertex_adjacency();
+        for f in 0..mesh.num_faces() {
+            let vertices = mesh.face_vertices(f);
+            assert_eq!(vertices.len(), 4);
+            for i in 0..4 {
+                let (a, b) = (vertices[i], vertices[(i + 1) % 4]);
+                assert!(adjacency.get_connected(a).contains(&to_index(b)));
+            }
+        }
+    }
+
+    #[test]
+    fn cube_face_adjacency() {
+        let mesh = fixtures::CUBE.mesh();
+        let adjacency = mesh.face_adjacency();
+        assert_eq!(adjacency.shape(), (6, 6));
+        assert_symmetric_without_diagonal(adjacency);
+        for f in 0..6 {
+            assert_eq!(adjacency.get_connected(f).len(), 4);
+        }
+    }
+
+    #[test]
+    fn derived_cache() {
+        let mut mesh = fixtures::CUBE.mesh();
+        let first: *const Connection = mesh.vertex_adjacency();
+        assert!(core::ptr::eq(first, mesh.vertex_adjacency()));
+        assert!(mesh.cache.face_adjacency.get().is_none());
+
+        let vertex_face = mesh.vertex_face();
+        assert_eq!(vertex_face.shape(), (8, 6));
+        for v in 0..8 {
+            assert_eq!(vertex_face.get_connected(v).len(), 3);
+        }
+
+        let permutation = mesh.permutation().unwrap();
+        let prev = mesh.prev_map().unwrap();
+        for h in 0..permutation.len() {
+            assert_eq!(to_usize(prev[to_usize(permutation[h])]), h);
+        }
+        assert_eq!(grid(2, 2).prev_map(), None);
+
+        mesh.clear_cache();
+        assert!(mesh.cache.vertex_adjacency.get().is_none());
+        assert!(mesh.cache.prev.get().is_none());
+        assert_eq!(mesh.vertex_adjacency().shape(), (8, 8));
+    }
+
+    #[test]
+    fn mesh_is_sync() {
+        fn assert_sync<T: Send + Sync>() {}
+        assert_sync::<Mesh>();
+    }
+
+    #[test]
+    fn empty() {
+        let mesh = Mesh::from_permutation(&[]);
+        assert_eq!(
+            (mesh.num_vertices(), mesh.num_edges(), mesh.num_faces()),
+            (0, 0, 0)
+        );
+        assert!(mesh.check_invariants().is_ok());
+        assert_eq!(mesh.genus(), Some(0));
+    }
+
+    /// Footprint of a torus of 5M faces, run with `--release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn memory_usage_large_torus() {
+        let permutation = crate::shapes::torus(2500, 2000).unwrap();
+        let mesh = Mesh::from_permutation(&permutation);
+        assert_eq!(mesh.num_faces(), 5_000_000);
+        let bytes = mesh.memory_usage().allocated();
+        println!(
+            "{} MiB with {}-byte indices",
+            bytes >> 20,
+            std::mem::size_of::<Index>()
+        );
+        // 26 indices per face: 6 in the row offsets, 16 in the incidences, and 4 in the permutation
+        assert!(bytes < 28 * mesh.num_faces() * std::mem::size_of::<Index>());
     }
 }
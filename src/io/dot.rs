@@ -0,0 +1,318 @@
+/*!
+[Graphviz Dot](https://graphviz.org/doc/info/lang.html) export of a mesh
+
+By default the vertex graph is written as an undirected `graph`.
+With [DotStyle::face_clusters] the half-edges of each face are written instead
+as a `digraph` in which each face is a `subgraph cluster_*`,
+which helps debugging orientation problems.
+*/
+
+use crate::{connection_matrix::widen, half_edge::Mesh, permutation::*};
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+/// Built-in palette used for coloring nodes and edges by class
+pub const PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+
+/// Labels of nodes
+pub enum NodeLabel<'a> {
+    /// Vertex id
+    Id,
+    /// Vertex orbit in cycle notation, e.g. `(0 3 5)`.
+    /// Requires the mesh to be created from a permutation.
+    Orbit,
+    /// No label
+    None,
+    /// Label created by a closure from the vertex id
+    Custom(&'a dyn Fn(usize) -> String),
+}
+
+/// Style of the Dot output
+pub struct DotStyle<'a> {
+    /// Node shape, e.g. `box` or `point`
+    pub node_shape: Option<&'a str>,
+    pub node_label: NodeLabel<'a>,
+    /// Label edges by edge id
+    pub edge_label: bool,
+    /// Graph direction, e.g. `LR`
+    pub rankdir: Option<&'a str>,
+    /// Class per vertex, colored through [PALETTE]
+    pub node_colors: Option<&'a [usize]>,
+    /// Class per edge, colored through [PALETTE]
+    pub edge_colors: Option<&'a [usize]>,
+    /// Write half-edges grouped into a cluster per face.
+    /// Requires the mesh to be created from a permutation.
+    pub face_clusters: bool,
+}
+
+impl Default for DotStyle<'_> {
+    fn default() -> Self {
+        DotStyle {
+            node_shape: None,
+            node_label: NodeLabel::Id,
+            edge_label: false,
+            rankdir: None,
+            node_colors: None,
+            edge_colors: None,
+            face_clusters: false,
+        }
+    }
+}
+
+/// Write the mesh in Dot format
+///
+/// Returns an `InvalidInput` error if the style requires the permutation
+/// but the mesh is not created from it, or if the lengths of color classes do not match.
+pub fn write_dot<W: Write>(mesh: &Mesh, mut writer: W, style: &DotStyle) -> io::Result<()> {
+    if let Some(colors) = style.node_colors {
+        super::check_len("node_colors", colors.len(), mesh.num_vertices())?;
+    }
+    if let Some(colors) = style.edge_colors {
+        super::check_len("edge_colors", colors.len(), mesh.num_edges())?;
+    }
+    if style.face_clusters {
+        write_face_clusters(mesh, writer, style)
+    } else {
+        write_vertex_graph(mesh, &mut writer, style)
+    }
+}
+
+fn write_header<W: Write>(writer: &mut W, style: &DotStyle) -> io::Result<()> {
+    if let Some(rankdir) = style.rankdir {
+        writeln!(writer, "    rankdir={}", rankdir)?;
+    }
+    if let Some(shape) = style.node_shape {
+        writeln!(writer, "    node [ shape = {} ]", shape)?;
+    }
+    Ok(())
+}
+
+fn write_vertex_graph<W: Write>(mesh: &Mesh, writer: &mut W, style: &DotStyle) -> io::Result<()> {
+    let orbits = match style.node_label {
+        NodeLabel::Orbit => Some(gather_vertices(&require_permutation(mesh)?)),
+        _ => None,
+    };
+
+    writeln!(writer, "graph {{")?;
+    write_header(writer, style)?;
+    for v in 0..mesh.num_vertices() {
+        let label = match &style.node_label {
+            NodeLabel::Id => v.to_string(),
+            NodeLabel::Orbit => orbits.as_ref().unwrap()[v].to_string(),
+            NodeLabel::None => String::new(),
+            NodeLabel::Custom(f) => f(v),
+        };
+        write!(writer, "    {} [ label = {}", v, quote(&label))?;
+        if let Some(colors) = style.node_colors {
+            write!(writer, " color = \"{}\"", color(colors[v]))?;
+        }
+        writeln!(writer, " ]")?;
+    }
+    for e in 0..mesh.num_edges() {
+        let (a, b) = mesh.edge_ends(e);
+        write!(writer, "    {} -- {} [", a, b)?;
+        if style.edge_label {
+            write!(writer, " label = \"{}\"", e)?;
+        }
+        if let Some(colors) = style.edge_colors {
+            write!(writer, " color = \"{}\"", color(colors[e]))?;
+        }
+        writeln!(writer, " ]")?;
+    }
+    writeln!(writer, "}}")
+}
+
+fn write_face_clusters<W: Write>(mesh: &Mesh, mut writer: W, style: &DotStyle) -> io::Result<()> {
+    let permutation = require_permutation(mesh)?;
+    writeln!(writer, "digraph {{")?;
+    write_header(&mut writer, style)?;
+    for (f, orbit) in gather_faces(&permutation).iter().enumerate() {
+        writeln!(writer, "    subgraph cluster_{} {{", f)?;
+        writeln!(writer, "        label = \"face {}\"", f)?;
+        for h in orbit.indices() {
+            write!(writer, "        h{} [ label = \"{}\"", h, h)?;
+            if let Some(colors) = style.edge_colors {
+                write!(writer, " color = \"{}\"", color(colors[h / 2]))?;
+            }
+            writeln!(writer, " ]")?;
+        }
+        for h in orbit.indices() {
+            writeln!(writer, "        h{} -> h{}", h, permutation[h])?;
+        }
+        writeln!(writer, "    }}")?;
+    }
+    // twin pairs
+    for h in (0..permutation.len()).step_by(2) {
+        write!(
+            writer,
+            "    h{} -> h{} [ style = dashed dir = none",
+            h,
+            h + 1
+        )?;
+        if style.edge_label {
+            write!(writer, " label = \"{}\"", h / 2)?;
+        }
+        writeln!(writer, " ]")?;
+    }
+    writeln!(writer, "}}")
+}
+
+fn require_permutation(mesh: &Mesh) -> io::Result<Cow<'_, [usize]>> {
+    mesh.permutation().map(widen).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "This Dot style requires a mesh created from a permutation",
+        )
+    })
+}
+
+fn color(class: usize) -> &'static str {
+    PALETTE[class % PALETTE.len()]
+}
+
+fn quote(label: &str) -> String {
+    format!("\"{}\"", label.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection_matrix::Connection;
+    use crate::fixtures;
+
+    fn tetrahedron_dot(style: &DotStyle) -> String {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        let mut buf = Vec::new();
+        write_dot(&mesh, &mut buf, style).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn tetrahedron_ids() {
+        let expected = r#"graph {
+    0 [ label = "0" ]
+    1 [ label = "1" ]
+    2 [ label = "2" ]
+    3 [ label = "3" ]
+    0 -- 1 [ ]
+    0 -- 2 [ ]
+    1 -- 2 [ ]
+    0 -- 3 [ ]
+    2 -- 3 [ ]
+    1 -- 3 [ ]
+}
+"#;
+        assert_eq!(tetrahedron_dot(&DotStyle::default()), expected);
+    }
+
+    #[test]
+    fn tetrahedron_orbits() {
+        let colors = [0, 1, 2, 3];
+        let style = DotStyle {
+            node_shape: Some("box"),
+            node_label: NodeLabel::Orbit,
+            edge_label: true,
+            node_colors: Some(&colors),
+            ..Default::default()
+        };
+        let expected = r##"graph {
+    node [ shape = box ]
+    0 [ label = "(0 6 3)" color = "#4e79a7" ]
+    1 [ label = "(1 5 10)" color = "#f28e2b" ]
+    2 [ label = "(2 8 4)" color = "#e15759" ]
+    3 [ label = "(7 11 9)" color = "#76b7b2" ]
+    0 -- 1 [ label = "0" ]
+    0 -- 2 [ label = "1" ]
+    1 -- 2 [ label = "2" ]
+    0 -- 3 [ label = "3" ]
+    2 -- 3 [ label = "4" ]
+    1 -- 3 [ label = "5" ]
+}
+"##;
+        assert_eq!(tetrahedron_dot(&style), expected);
+    }
+
+    #[test]
+    fn tetrahedron_face_clusters() {
+        let style = DotStyle {
+            rankdir: Some("LR"),
+            face_clusters: true,
+            ..Default::default()
+        };
+        let expected = r#"digraph {
+    rankdir=LR
+    subgraph cluster_0 {
+        label = "face 0"
+        h0 [ label = "0" ]
+        h5 [ label = "5" ]
+        h2 [ label = "2" ]
+        h0 -> h5
+        h5 -> h2
+        h2 -> h0
+    }
+    subgraph cluster_1 {
+        label = "face 1"
+        h1 [ label = "1" ]
+        h6 [ label = "6" ]
+        h11 [ label = "11" ]
+        h1 -> h6
+        h6 -> h11
+        h11 -> h1
+    }
+    subgraph cluster_2 {
+        label = "face 2"
+        h3 [ label = "3" ]
+        h8 [ label = "8" ]
+        h7 [ label = "7" ]
+        h3 -> h8
+        h8 -> h7
+        h7 -> h3
+    }
+    subgraph cluster_3 {
+        label = "face 3"
+        h4 [ label = "4" ]
+        h10 [ label = "10" ]
+        h9 [ label = "9" ]
+        h4 -> h10
+        h10 -> h9
+        h9 -> h4
+    }
+    h0 -> h1 [ style = dashed dir = none ]
+    h2 -> h3 [ style = dashed dir = none ]
+    h4 -> h5 [ style = dashed dir = none ]
+    h6 -> h7 [ style = dashed dir = none ]
+    h8 -> h9 [ style = dashed dir = none ]
+    h10 -> h11 [ style = dashed dir = none ]
+}
+"#;
+        assert_eq!(tetrahedron_dot(&style), expected);
+    }
+
+    #[test]
+    fn custom_label() {
+        let label = |v: usize| format!("v\"{}\"", v);
+        let style = DotStyle {
+            node_label: NodeLabel::Custom(&label),
+            ..Default::default()
+        };
+        assert!(tetrahedron_dot(&style).contains(r#"    2 [ label = "v\"2\"" ]"#));
+    }
+
+    #[test]
+    fn requires_permutation() {
+        // single triangle
+        let vertex_edge =
+            Connection::from_vec(vec![(0, 0), (0, 2), (1, 0), (1, 1), (2, 1), (2, 2)]);
+        let edge_face = Connection::from_vec(vec![(0, 0), (1, 0), (2, 0)]);
+        let mesh = Mesh::from_connections(vertex_edge, edge_face);
+        let style = DotStyle {
+            face_clusters: true,
+            ..Default::default()
+        };
+        let err = write_dot(&mesh, Vec::new(), &style).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(write_dot(&mesh, Vec::new(), &DotStyle::default()).is_ok());
+    }
+}
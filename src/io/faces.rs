@@ -0,0 +1,109 @@
+/*!
+Plain text format of a list of faces
+
+Each line lists the vertex ids of a face in order, separated by whitespace.
+Empty lines and lines starting with `#` are skipped.
+Converting the list into a permutation by [crate::from_faces] requires a closed,
+consistently oriented surface, while this format can hold any polygon soup.
+[FaceReader] streams the faces into [crate::permutation::from_face_iter]
+without holding the whole list.
+
+```text
+# tetrahedron
+0 2 1
+0 1 3
+1 2 3
+0 3 2
+```
+*/
+
+use super::permutation::{parse_line, ReadError};
+use std::io::{self, BufRead, Write};
+
+/// Iterator reading faces line by line, without keeping the faces already read
+pub struct FaceReader<R> {
+    reader: R,
+    buf: String,
+    line: usize,
+}
+
+impl<R: BufRead> FaceReader<R> {
+    pub fn new(reader: R) -> Self {
+        FaceReader {
+            reader,
+            buf: String::new(),
+            line: 0,
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for FaceReader<R> {
+    type Item = Result<Vec<usize>, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.reader.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => {}
+                Err(e) => return Some(Err(e.into())),
+            }
+            self.line += 1;
+            if self.buf.trim().is_empty() || self.buf.trim_start().starts_with('#') {
+                continue;
+            }
+            let mut face = Vec::new();
+            return Some(parse_line(&self.buf, self.line, &mut face).map(|_| face));
+        }
+    }
+}
+
+/// Read all faces from a buffered reader
+pub fn read_faces<R: BufRead>(reader: R) -> Result<Vec<Vec<usize>>, ReadError> {
+    FaceReader::new(reader).collect()
+}
+
+/// Write faces with a `#` comment line, one face per line
+pub fn write_faces<W: Write>(faces: &[Vec<usize>], mut writer: W, comment: &str) -> io::Result<()> {
+    writeln!(writer, "# {}", comment)?;
+    for face in faces {
+        let tokens: Vec<_> = face.iter().map(|v| v.to_string()).collect();
+        writeln!(writer, "{}", tokens.join(" "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let faces = vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 2, 3], vec![0, 3, 2]];
+        let mut buf = Vec::new();
+        write_faces(&faces, &mut buf, "tetrahedron").unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert!(text.starts_with("# tetrahedron\n0 2 1\n"));
+        assert_eq!(read_faces(text.as_bytes()).unwrap(), faces);
+        assert_eq!(
+            read_faces("\n 0 1 2\n\n3 x".as_bytes())
+                .unwrap_err()
+                .to_string(),
+            "Cannot read 'x' as an integer at line 4, column 3"
+        );
+    }
+
+    #[test]
+    fn stream() {
+        let text = "# tetrahedron\n0 2 1\n0 1 3\n1 2 3\n0 3 2\n";
+        let permutation = crate::permutation::from_face_iter(
+            FaceReader::new(text.as_bytes()).map(Result::unwrap),
+        )
+        .unwrap();
+        assert_eq!(permutation.len(), 12);
+        let mut reader = FaceReader::new("0 1\n-1".as_bytes());
+        assert_eq!(reader.next().unwrap().unwrap(), vec![0, 1]);
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
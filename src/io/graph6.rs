@@ -0,0 +1,455 @@
+/*!
+[graph6 and sparse6](https://users.cecs.anu.edu.au/~bdm/data/formats.txt) formats
+used by nauty and SageMath
+
+- graph6 encodes the upper triangle of the adjacency matrix of a simple graph.
+- sparse6 encodes the edge list, and also allows self-loops and multiple edges.
+
+Both formats are printable ASCII strings,
+and the optional `>>graph6<<`/`>>sparse6<<` header and trailing newline are accepted by the parsers.
+*/
+
+use crate::half_edge::Mesh;
+use std::fmt;
+
+/// Undirected graph decoded from graph6 or sparse6
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Graph {
+    pub num_vertices: usize,
+    /// Edges `(u, v)` with `u <= v`
+    pub edges: Vec<(usize, usize)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Graph6Error {
+    /// Byte outside of the printable range `63..=126`
+    InvalidCharacter { position: usize, byte: u8 },
+    /// Input ends before the number of vertices is complete
+    UnexpectedEnd,
+    /// Length of graph6 body does not match the number of vertices
+    InvalidLength { expected: usize, actual: usize },
+    /// sparse6 string does not start with `:`
+    MissingPrefix,
+}
+
+impl fmt::Display for Graph6Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Graph6Error::InvalidCharacter { position, byte } => {
+                write!(f, "Invalid byte {:#04x} at position {}", byte, position)
+            }
+            Graph6Error::UnexpectedEnd => write!(f, "Unexpected end of input"),
+            Graph6Error::InvalidLength { expected, actual } => write!(
+                f,
+                "Expected {} bytes of adjacency data, but found {}",
+                expected, actual
+            ),
+            Graph6Error::MissingPrefix => write!(f, "sparse6 must start with ':'"),
+        }
+    }
+}
+
+impl std::error::Error for Graph6Error {}
+
+/// Encode a simple graph in graph6
+///
+/// Self-loops are ignored since graph6 cannot represent them.
+pub fn to_graph6(num_vertices: usize, edges: &[(usize, usize)]) -> String {
+    let n = num_vertices;
+    let mut adjacency = vec![false; n * n.saturating_sub(1) / 2];
+    for &(a, b) in edges {
+        let (u, v) = if a < b { (a, b) } else { (b, a) };
+        assert!(v < n, "Vertex {} is out of range", v);
+        if u != v {
+            // column-wise upper triangle
+            adjacency[v * (v - 1) / 2 + u] = true;
+        }
+    }
+    let mut out = Vec::new();
+    encode_size(n, &mut out);
+    let mut bits = BitWriter::new(out);
+    for bit in adjacency {
+        bits.push(bit);
+    }
+    bits.finish(false)
+}
+
+/// Decode a graph6 string
+pub fn parse_graph6(input: &str) -> Result<Graph, Graph6Error> {
+    let input = input.trim_end();
+    let input = input.strip_prefix(">>graph6<<").unwrap_or(input);
+    let bytes = check_bytes(input.as_bytes())?;
+    let (n, offset) = decode_size(bytes)?;
+    let body = &bytes[offset..];
+    // the upper triangle of 2^36 vertices does not fit in memory
+    let expected = n
+        .checked_mul(n.saturating_sub(1))
+        .map(|num_bits| (num_bits / 2).div_ceil(6));
+    if expected != Some(body.len()) {
+        return Err(Graph6Error::InvalidLength {
+            expected: expected.unwrap_or(usize::MAX),
+            actual: body.len(),
+        });
+    }
+    let mut bits = BitReader::new(body);
+    let mut edges = Vec::new();
+    for v in 1..n {
+        for u in 0..v {
+            if bits.read(1).unwrap() == 1 {
+                edges.push((u, v));
+            }
+        }
+    }
+    edges.sort_unstable();
+    Ok(Graph {
+        num_vertices: n,
+        edges,
+    })
+}
+
+/// Encode a graph in sparse6
+///
+/// Self-loops and multiple edges are kept.
+pub fn to_sparse6(num_vertices: usize, edges: &[(usize, usize)]) -> String {
+    let n = num_vertices;
+    let k = num_bits(n);
+    let mut edges: Vec<(usize, usize)> = edges
+        .iter()
+        .map(|&(a, b)| if a <= b { (a, b) } else { (b, a) })
+        .collect();
+    for &(_, v) in &edges {
+        assert!(v < n, "Vertex {} is out of range", v);
+    }
+    edges.sort_unstable_by_key(|&(u, v)| (v, u));
+
+    let mut out = vec![b':'];
+    encode_size(n, &mut out);
+    let mut bits = BitWriter::new(out);
+    let mut current = 0;
+    for (u, v) in edges {
+        if v == current {
+            bits.push(false);
+            bits.push_int(u, k);
+        } else if v == current + 1 {
+            current = v;
+            bits.push(true);
+            bits.push_int(u, k);
+        } else {
+            current = v;
+            bits.push(true);
+            bits.push_int(v, k);
+            bits.push(false);
+            bits.push_int(u, k);
+        }
+    }
+    // Padding with 1-bits would be read as an edge `(n - 1, n - 1)`
+    // if the last vertex were `n - 2` and `n` is a power of two
+    let padding = (6 - bits.len % 6) % 6;
+    if k < 6 && n == 1 << k && padding > k && current + 2 == n {
+        bits.push(false);
+    }
+    bits.finish(true)
+}
+
+/// Decode a sparse6 string
+pub fn parse_sparse6(input: &str) -> Result<Graph, Graph6Error> {
+    let input = input.trim_end();
+    let input = input.strip_prefix(">>sparse6<<").unwrap_or(input);
+    let input = input.strip_prefix(':').ok_or(Graph6Error::MissingPrefix)?;
+    let bytes = check_bytes(input.as_bytes())?;
+    let (n, offset) = decode_size(bytes)?;
+    let k = num_bits(n);
+    let mut bits = BitReader::new(&bytes[offset..]);
+    let mut edges = Vec::new();
+    let mut v = 0;
+    while let (Some(b), Some(x)) = (bits.read(1), bits.read(k)) {
+        if b == 1 {
+            v += 1;
+        }
+        if x >= n || v >= n {
+            break;
+        }
+        if x > v {
+            v = x;
+        } else {
+            edges.push((x, v));
+        }
+    }
+    edges.sort_unstable();
+    Ok(Graph {
+        num_vertices: n,
+        edges,
+    })
+}
+
+impl Mesh {
+    /// Vertex graph of the mesh in graph6 format
+    ///
+    /// Since graph6 only represents simple graphs, this is the [Mesh::vertex_adjacency].
+    pub fn vertex_graph_to_graph6(&self) -> String {
+        let adjacency = self.vertex_adjacency();
+        let edges: Vec<_> = adjacency.indices().filter(|(u, v)| u < v).collect();
+        to_graph6(self.num_vertices(), &edges)
+    }
+
+    /// Vertex graph of the mesh in sparse6 format, keeping self-loops and multiple edges
+    pub fn vertex_graph_to_sparse6(&self) -> String {
+        let edges: Vec<_> = (0..self.num_edges()).map(|e| self.edge_ends(e)).collect();
+        to_sparse6(self.num_vertices(), &edges)
+    }
+}
+
+/// Number of bits to represent `n - 1`, at least 1
+fn num_bits(n: usize) -> usize {
+    let mut k = 1;
+    while (1 << k) < n {
+        k += 1;
+    }
+    k
+}
+
+fn check_bytes(bytes: &[u8]) -> Result<&[u8], Graph6Error> {
+    if let Some(position) = bytes.iter().position(|b| !(63..=126).contains(b)) {
+        return Err(Graph6Error::InvalidCharacter {
+            position,
+            byte: bytes[position],
+        });
+    }
+    Ok(bytes)
+}
+
+/// `N(n)` in the format description
+fn encode_size(n: usize, out: &mut Vec<u8>) {
+    let groups = if n <= 62 {
+        1
+    } else if n <= 258047 {
+        out.push(126);
+        3
+    } else {
+        assert!(n < 1 << 36, "Too many vertices for graph6/sparse6");
+        out.push(126);
+        out.push(126);
+        6
+    };
+    for i in (0..groups).rev() {
+        out.push(((n >> (6 * i)) & 0x3f) as u8 + 63);
+    }
+}
+
+/// Decode `N(n)`, returning `n` and the number of consumed bytes
+fn decode_size(bytes: &[u8]) -> Result<(usize, usize), Graph6Error> {
+    let (offset, groups) = match bytes {
+        [] => return Err(Graph6Error::UnexpectedEnd),
+        [126, 126, ..] => (2, 6),
+        [126, ..] => (1, 3),
+        _ => (0, 1),
+    };
+    if bytes.len() < offset + groups {
+        return Err(Graph6Error::UnexpectedEnd);
+    }
+    let n = bytes[offset..offset + groups]
+        .iter()
+        .fold(0, |n, &b| (n << 6) | (b - 63) as usize);
+    Ok((n, offset + groups))
+}
+
+/// Packs bits into printable bytes of 6 bits each
+struct BitWriter {
+    out: Vec<u8>,
+    current: u8,
+    len: usize,
+}
+
+impl BitWriter {
+    fn new(out: Vec<u8>) -> Self {
+        BitWriter {
+            out,
+            current: 0,
+            len: 0,
+        }
+    }
+
+    fn push(&mut self, bit: bool) {
+        self.current = (self.current << 1) | bit as u8;
+        self.len += 1;
+        if self.len.is_multiple_of(6) {
+            self.out.push(self.current + 63);
+            self.current = 0;
+        }
+    }
+
+    /// Push lower `k` bits of `x`, most significant first
+    fn push_int(&mut self, x: usize, k: usize) {
+        for i in (0..k).rev() {
+            self.push((x >> i) & 1 == 1);
+        }
+    }
+
+    fn finish(mut self, padding: bool) -> String {
+        while !self.len.is_multiple_of(6) {
+            self.push(padding);
+        }
+        String::from_utf8(self.out).unwrap()
+    }
+}
+
+/// Reads bits from printable bytes of 6 bits each
+struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        BitReader { bytes, position: 0 }
+    }
+
+    /// Read `k` bits as an integer, most significant first
+    fn read(&mut self, k: usize) -> Option<usize> {
+        if self.position + k > self.bytes.len() * 6 {
+            return None;
+        }
+        let mut x = 0;
+        for _ in 0..k {
+            let byte = self.bytes[self.position / 6] - 63;
+            let bit = (byte >> (5 - self.position % 6)) & 1;
+            x = (x << 1) | bit as usize;
+            self.position += 1;
+        }
+        Some(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn complete(n: usize) -> Vec<(usize, usize)> {
+        (0..n).flat_map(|v| (0..v).map(move |u| (u, v))).collect()
+    }
+
+    fn cycle(n: usize) -> Vec<(usize, usize)> {
+        (0..n).map(|i| (i, (i + 1) % n)).collect()
+    }
+
+    fn petersen() -> Vec<(usize, usize)> {
+        (0..5)
+            .flat_map(|i| vec![(i, (i + 1) % 5), (i, i + 5), (i + 5, (i + 2) % 5 + 5)])
+            .collect()
+    }
+
+    fn sorted(edges: &[(usize, usize)]) -> Vec<(usize, usize)> {
+        let mut edges: Vec<_> = edges
+            .iter()
+            .map(|&(a, b)| if a <= b { (a, b) } else { (b, a) })
+            .collect();
+        edges.sort_unstable();
+        edges
+    }
+
+    fn check(n: usize, edges: &[(usize, usize)], graph6: &str, sparse6: &str) {
+        let graph = Graph {
+            num_vertices: n,
+            edges: sorted(edges),
+        };
+        assert_eq!(to_graph6(n, edges), graph6);
+        assert_eq!(to_sparse6(n, edges), sparse6);
+        assert_eq!(parse_graph6(graph6).unwrap(), graph);
+        assert_eq!(parse_sparse6(sparse6).unwrap(), graph);
+    }
+
+    #[test]
+    fn reference_strings() {
+        check(0, &[], "?", ":?");
+        check(1, &[], "@", ":@");
+        check(4, &complete(4), "C~", ":CcKI");
+        check(5, &cycle(5), "Dhc", ":DaY_~");
+        check(10, &petersen(), "IheA@GUAo", ":I`ES@obGkqegW~");
+        // examples in the format description
+        check(5, &[(0, 2), (0, 4), (1, 3), (3, 4)], "DQc", ":DgH_~");
+        check(7, &[(0, 1), (0, 2), (1, 2), (5, 6)], "Fw??G", ":Fa@x^");
+    }
+
+    #[test]
+    fn large_size() {
+        let path: Vec<_> = (0..99).map(|i| (i, i + 1)).collect();
+        let graph6 = to_graph6(100, &path);
+        assert!(graph6.starts_with("~?@c"));
+        assert_eq!(parse_graph6(&graph6).unwrap().edges, path);
+        let sparse6 = to_sparse6(100, &path);
+        assert!(sparse6.starts_with(":~?@c"));
+        assert_eq!(parse_sparse6(&sparse6).unwrap().edges, path);
+
+        let mut out = Vec::new();
+        encode_size(258048, &mut out);
+        assert_eq!(out, b"~~???~??");
+        assert_eq!(decode_size(&out).unwrap(), (258048, 8));
+        out.clear();
+        encode_size(63, &mut out);
+        assert_eq!(out, b"~??~");
+    }
+
+    #[test]
+    fn sparse6_loops_and_multiple_edges() {
+        let edges = [(0, 0), (0, 1), (0, 1), (2, 2), (1, 3)];
+        let sparse6 = to_sparse6(4, &edges);
+        assert_eq!(parse_sparse6(&sparse6).unwrap().edges, sorted(&edges));
+        // vertex n-2 is the last one with an edge, and n is a power of two
+        assert_eq!(to_sparse6(2, &[(0, 0)]), ":AF");
+        assert_eq!(parse_sparse6(":AF").unwrap().edges, vec![(0, 0)]);
+        assert_eq!(to_sparse6(8, &[(5, 6)]), ":GxV");
+        assert_eq!(parse_sparse6(":GxV").unwrap().edges, vec![(5, 6)]);
+        assert_eq!(to_sparse6(4, &[(0, 1), (1, 2)]), ":Cd");
+    }
+
+    #[test]
+    fn header_and_newline() {
+        assert_eq!(
+            parse_graph6(">>graph6<<C~\n").unwrap().edges,
+            sorted(&complete(4))
+        );
+        assert_eq!(
+            parse_sparse6(">>sparse6<<:CcKI\n").unwrap().edges,
+            sorted(&complete(4))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse_graph6(""), Err(Graph6Error::UnexpectedEnd));
+        assert_eq!(parse_graph6("~?"), Err(Graph6Error::UnexpectedEnd));
+        assert_eq!(
+            parse_graph6("C~~"),
+            Err(Graph6Error::InvalidLength {
+                expected: 1,
+                actual: 2
+            })
+        );
+        assert_eq!(
+            parse_graph6("C 1"),
+            Err(Graph6Error::InvalidCharacter {
+                position: 1,
+                byte: b' '
+            })
+        );
+        assert_eq!(parse_sparse6("CcKI"), Err(Graph6Error::MissingPrefix));
+        // size close to 2^36 must not overflow
+        assert!(matches!(
+            parse_graph6("~~Hubec`_x"),
+            Err(Graph6Error::InvalidLength { actual: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn mesh_vertex_graph() {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        assert_eq!(mesh.vertex_graph_to_graph6(), "C~");
+        assert_eq!(
+            parse_sparse6(&mesh.vertex_graph_to_sparse6())
+                .unwrap()
+                .edges,
+            sorted(&complete(4))
+        );
+    }
+}
@@ -0,0 +1,133 @@
+/*!
+[GraphML](http://graphml.graphdrawing.org/) export of the vertex graph of a mesh
+
+Each vertex becomes a `<node>` and each edge an undirected `<edge>`.
+Scalar values per vertex or per edge are written as `<data>` entries
+declared by `<key>` headers, which Gephi and yEd read directly.
+*/
+
+use super::{check_attributes, escape_xml, Attribute, Domain};
+use crate::half_edge::Mesh;
+use std::io::{self, Write};
+
+/// Write the vertex graph of the mesh as GraphML
+///
+/// The document is streamed into `writer` element by element.
+/// Returns an `InvalidInput` error if the length of an attribute does not match
+/// the number of vertices or edges.
+pub fn write_graphml<W: Write>(
+    mesh: &Mesh,
+    mut writer: W,
+    attributes: &[Attribute],
+) -> io::Result<()> {
+    check_attributes(mesh, attributes)?;
+
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        writer,
+        r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+    )?;
+    for (i, attr) in attributes.iter().enumerate() {
+        let domain = match attr.domain {
+            Domain::Vertex => "node",
+            Domain::Edge => "edge",
+        };
+        writeln!(
+            writer,
+            r#"  <key id="d{}" for="{}" attr.name="{}" attr.type="double"/>"#,
+            i,
+            domain,
+            escape_xml(attr.name)
+        )?;
+    }
+    writeln!(writer, r#"  <graph id="G" edgedefault="undirected">"#)?;
+
+    for v in 0..mesh.num_vertices() {
+        writeln!(writer, r#"    <node id="n{}">"#, v)?;
+        write_data(&mut writer, attributes, Domain::Vertex, v)?;
+        writeln!(writer, "    </node>")?;
+    }
+    for e in 0..mesh.num_edges() {
+        let vertices = mesh.edge_vertices(e);
+        let source = vertices[0];
+        let target = *vertices.last().unwrap();
+        writeln!(
+            writer,
+            r#"    <edge id="e{}" source="n{}" target="n{}">"#,
+            e, source, target
+        )?;
+        write_data(&mut writer, attributes, Domain::Edge, e)?;
+        writeln!(writer, "    </edge>")?;
+    }
+
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    Ok(())
+}
+
+/// Write `<data>` entries of the element
+fn write_data<W: Write>(
+    writer: &mut W,
+    attributes: &[Attribute],
+    domain: Domain,
+    index: usize,
+) -> io::Result<()> {
+    for (i, attr) in attributes.iter().enumerate() {
+        if attr.domain == domain {
+            writeln!(
+                writer,
+                r#"      <data key="d{}">{}</data>"#,
+                i, attr.values[index]
+            )?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn cube_graphml(attributes: &[Attribute]) -> String {
+        let mesh = fixtures::CUBE.mesh();
+        let mut buf = Vec::new();
+        write_graphml(&mesh, &mut buf, attributes).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn cube_valence() {
+        let mesh = fixtures::CUBE.mesh();
+        let adjacency = mesh.vertex_adjacency();
+        let valence: Vec<f64> = (0..mesh.num_vertices())
+            .map(|v| adjacency.get_connected(v).len() as f64)
+            .collect();
+        let xml = cube_graphml(&[Attribute::vertex("valence", &valence)]);
+
+        assert!(xml.starts_with("<?xml"));
+        assert_eq!(xml.matches("<key ").count(), 1);
+        assert!(xml.contains(r#"<key id="d0" for="node" attr.name="valence" attr.type="double"/>"#));
+        assert_eq!(xml.matches("<node ").count(), 8);
+        assert_eq!(xml.matches("</node>").count(), 8);
+        assert_eq!(xml.matches("<edge ").count(), 12);
+        assert_eq!(xml.matches("</edge>").count(), 12);
+        assert_eq!(xml.matches(r#"<data key="d0">3</data>"#).count(), 8);
+        assert!(xml.trim_end().ends_with("</graphml>"));
+    }
+
+    #[test]
+    fn escape_name() {
+        let weight = vec![1.5; 12];
+        let xml = cube_graphml(&[Attribute::edge("a<b & \"c\"", &weight)]);
+        assert!(xml.contains(r#"attr.name="a&lt;b &amp; &quot;c&quot;""#));
+        assert_eq!(xml.matches(r#"<data key="d0">1.5</data>"#).count(), 12);
+    }
+
+    #[test]
+    fn length_mismatch() {
+        let mesh = fixtures::CUBE.mesh();
+        let err = write_graphml(&mesh, Vec::new(), &[Attribute::vertex("x", &[0.0])]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
@@ -0,0 +1,183 @@
+/*!
+Node-link JSON export of the vertex graph of a mesh
+
+The output follows the node-link format used by d3.js and networkx:
+
+```text
+{"directed":false,"multigraph":false,"graph":{},
+ "nodes":[{"id":0,...},...],
+ "links":[{"id":0,"source":0,"target":1,...},...]}
+```
+
+Positions and named scalars are written as extra fields of nodes and links,
+and faces are optionally written as an additional `"faces"` array of vertex ids.
+Non-finite values are written as `null`.
+*/
+
+use super::{check_attributes, check_len, quote_json, Attribute, Domain};
+use crate::half_edge::Mesh;
+use std::io::{self, Write};
+
+/// Optional contents of the node-link output
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NodeLinkOptions<'a> {
+    /// Vertex positions written as `"position":[x,y,z]`
+    pub positions: Option<&'a [[f64; 3]]>,
+    /// Scalars per vertex or per edge written as extra fields
+    pub attributes: &'a [Attribute<'a>],
+    /// Write `"faces"` with the vertices of each face (see [Mesh::face_vertices])
+    pub faces: bool,
+}
+
+/// Write the vertex graph of the mesh in node-link JSON format
+///
+/// The document is streamed into `writer` and is deterministic for a given mesh and options.
+pub fn write_node_link<W: Write>(
+    mesh: &Mesh,
+    mut writer: W,
+    options: &NodeLinkOptions,
+) -> io::Result<()> {
+    check_attributes(mesh, options.attributes)?;
+    if let Some(positions) = options.positions {
+        check_len("position", positions.len(), mesh.num_vertices())?;
+    }
+
+    write!(
+        writer,
+        r#"{{"directed":false,"multigraph":false,"graph":{{}},"nodes":["#
+    )?;
+    for v in 0..mesh.num_vertices() {
+        if v > 0 {
+            write!(writer, ",")?;
+        }
+        write!(writer, r#"{{"id":{}"#, v)?;
+        if let Some(positions) = options.positions {
+            let [x, y, z] = positions[v];
+            write!(writer, r#","position":["#)?;
+            write_number(&mut writer, x)?;
+            write!(writer, ",")?;
+            write_number(&mut writer, y)?;
+            write!(writer, ",")?;
+            write_number(&mut writer, z)?;
+            write!(writer, "]")?;
+        }
+        write_fields(&mut writer, options.attributes, Domain::Vertex, v)?;
+        write!(writer, "}}")?;
+    }
+
+    write!(writer, r#"],"links":["#)?;
+    for e in 0..mesh.num_edges() {
+        if e > 0 {
+            write!(writer, ",")?;
+        }
+        let vertices = mesh.edge_vertices(e);
+        write!(
+            writer,
+            r#"{{"id":{},"source":{},"target":{}"#,
+            e,
+            vertices[0],
+            vertices.last().unwrap()
+        )?;
+        write_fields(&mut writer, options.attributes, Domain::Edge, e)?;
+        write!(writer, "}}")?;
+    }
+    write!(writer, "]")?;
+
+    if options.faces {
+        write!(writer, r#","faces":["#)?;
+        for f in 0..mesh.num_faces() {
+            if f > 0 {
+                write!(writer, ",")?;
+            }
+            write!(writer, "[")?;
+            for (i, v) in mesh.face_vertices(f).iter().enumerate() {
+                if i > 0 {
+                    write!(writer, ",")?;
+                }
+                write!(writer, "{}", v)?;
+            }
+            write!(writer, "]")?;
+        }
+        write!(writer, "]")?;
+    }
+    writeln!(writer, "}}")
+}
+
+fn write_fields<W: Write>(
+    writer: &mut W,
+    attributes: &[Attribute],
+    domain: Domain,
+    index: usize,
+) -> io::Result<()> {
+    for attr in attributes.iter().filter(|attr| attr.domain == domain) {
+        write!(writer, ",{}:", quote_json(attr.name))?;
+        write_number(writer, attr.values[index])?;
+    }
+    Ok(())
+}
+
+fn write_number<W: Write>(writer: &mut W, value: f64) -> io::Result<()> {
+    if value.is_finite() {
+        write!(writer, "{}", value)
+    } else {
+        write!(writer, "null")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    fn cube_json(options: &NodeLinkOptions) -> String {
+        let mesh = fixtures::CUBE.mesh();
+        let mut buf = Vec::new();
+        write_node_link(&mesh, &mut buf, options).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn cube() {
+        let json = cube_json(&NodeLinkOptions::default());
+        assert!(json.starts_with(r#"{"directed":false,"multigraph":false,"graph":{},"nodes":["#));
+        assert_eq!(json.matches(r#"{"id":"#).count(), 8 + 12);
+        assert_eq!(json.matches(r#""source":"#).count(), 12);
+        // edge 0 consists of half-edges 0 and 1, starting at vertex 0 and 1
+        assert!(json.contains(r#"{"id":0,"source":0,"target":1}"#));
+        assert!(!json.contains("faces"));
+        assert_eq!(json, cube_json(&NodeLinkOptions::default()));
+    }
+
+    #[test]
+    fn cube_with_options() {
+        let positions = vec![[0.0, 0.5, 1.0]; 8];
+        let valence = vec![3.0; 8];
+        let length = vec![f64::NAN; 12];
+        let attributes = [
+            Attribute::vertex("valence", &valence),
+            Attribute::edge("length", &length),
+        ];
+        let json = cube_json(&NodeLinkOptions {
+            positions: Some(&positions),
+            attributes: &attributes,
+            faces: true,
+        });
+        assert!(json.contains(r#"{"id":0,"position":[0,0.5,1],"valence":3}"#));
+        assert!(json.contains(r#"{"id":0,"source":0,"target":1,"length":null}"#));
+        let faces = &json[json.find(r#""faces":"#).unwrap()..];
+        assert_eq!(faces.matches('[').count(), 1 + 6);
+        assert_eq!(faces.matches(',').count(), 5 + 6 * 3);
+    }
+
+    #[test]
+    fn positions_mismatch() {
+        let mesh = fixtures::CUBE.mesh();
+        let positions = vec![[0.0; 3]; 7];
+        let options = NodeLinkOptions {
+            positions: Some(&positions),
+            ..Default::default()
+        };
+        let err = write_node_link(&mesh, Vec::new(), &options).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
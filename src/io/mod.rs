@@ -0,0 +1,110 @@
+/*!
+Exchange meshes with external graph formats
+*/
+
+pub mod dot;
+pub mod faces;
+pub mod graph6;
+pub mod graphml;
+pub mod json_graph;
+pub mod permutation;
+pub mod selection;
+pub mod svg;
+
+use crate::half_edge::Mesh;
+use std::io;
+
+/// Element kind an [Attribute] is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Domain {
+    Vertex,
+    Edge,
+}
+
+/// Named scalar values per vertex or per edge
+#[derive(Debug, Clone, Copy)]
+pub struct Attribute<'a> {
+    pub name: &'a str,
+    pub domain: Domain,
+    pub values: &'a [f64],
+}
+
+impl<'a> Attribute<'a> {
+    /// Values per vertex, indexed by vertex id
+    pub fn vertex(name: &'a str, values: &'a [f64]) -> Self {
+        Attribute {
+            name,
+            domain: Domain::Vertex,
+            values,
+        }
+    }
+
+    /// Values per edge, indexed by edge id
+    pub fn edge(name: &'a str, values: &'a [f64]) -> Self {
+        Attribute {
+            name,
+            domain: Domain::Edge,
+            values,
+        }
+    }
+}
+
+/// Check that the attributes have one value per element of the mesh
+pub(crate) fn check_attributes(mesh: &Mesh, attributes: &[Attribute]) -> io::Result<()> {
+    for attr in attributes {
+        let expected = match attr.domain {
+            Domain::Vertex => mesh.num_vertices(),
+            Domain::Edge => mesh.num_edges(),
+        };
+        check_len(attr.name, attr.values.len(), expected)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn check_len(name: &str, len: usize, expected: usize) -> io::Result<()> {
+    if len != expected {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Attribute '{}' has {} values, but {} are expected",
+                name, len, expected
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Escape a string for XML attribute values and text
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Quote and escape a string as a JSON string literal
+pub fn quote_json(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
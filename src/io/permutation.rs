@@ -0,0 +1,175 @@
+/*!
+Plain text format of a permutation on half-edges
+
+The permutation is a sequence of non-negative integers separated by whitespace or newlines,
+where the `h`-th integer is the next half-edge of the half-edge `h` (see [crate::permutation]).
+Lines starting with `#` are comments.
+
+```text
+# tetrahedron
+5 6 0 8 10 2
+11 3 7 4 9 1
+```
+*/
+
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug)]
+pub enum ReadError {
+    Io(io::Error),
+    /// Token which is not a non-negative integer, with 1-based line and column
+    Parse {
+        line: usize,
+        column: usize,
+        token: String,
+    },
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "{}", e),
+            ReadError::Parse {
+                line,
+                column,
+                token,
+            } => write!(
+                f,
+                "Cannot read '{}' as an integer at line {}, column {}",
+                token, line, column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ReadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReadError::Io(e) => Some(e),
+            ReadError::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+/// Read a permutation line by line from a buffered reader
+pub fn read_permutation<R: BufRead>(mut reader: R) -> Result<Vec<usize>, ReadError> {
+    let mut permutation = Vec::new();
+    let mut buf = String::new();
+    let mut line = 0;
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            break;
+        }
+        line += 1;
+        if buf.trim_start().starts_with('#') {
+            continue;
+        }
+        parse_line(&buf, line, &mut permutation)?;
+    }
+    Ok(permutation)
+}
+
+/// Parse a permutation from a string, e.g. command line arguments joined by spaces
+pub fn parse_permutation(input: &str) -> Result<Vec<usize>, ReadError> {
+    read_permutation(input.as_bytes())
+}
+
+/// Write a permutation with a `#` comment line, twelve half-edges per line
+pub fn write_permutation<W: Write>(
+    permutation: &[usize],
+    mut writer: W,
+    comment: &str,
+) -> io::Result<()> {
+    writeln!(writer, "# {}", comment)?;
+    for line in permutation.chunks(12) {
+        let tokens: Vec<_> = line.iter().map(|h| h.to_string()).collect();
+        writeln!(writer, "{}", tokens.join(" "))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn parse_line(
+    buf: &str,
+    line: usize,
+    permutation: &mut Vec<usize>,
+) -> Result<(), ReadError> {
+    let mut chars = buf.char_indices().peekable();
+    let mut column = 0;
+    while let Some((start, c)) = chars.next() {
+        column += 1;
+        if c.is_whitespace() {
+            continue;
+        }
+        let token_column = column;
+        let mut end = start + c.len_utf8();
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() {
+                break;
+            }
+            column += 1;
+            end = i + c.len_utf8();
+            chars.next();
+        }
+        let token = &buf[start..end];
+        let value = token.parse().map_err(|_| ReadError::Parse {
+            line,
+            column: token_column,
+            token: token.to_string(),
+        })?;
+        permutation.push(value);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read() {
+        let input = "# tetrahedron\n5 6 0 8 10 2\n\n  11 3\t7 4 9 1";
+        assert_eq!(
+            parse_permutation(input).unwrap(),
+            vec![5, 6, 0, 8, 10, 2, 11, 3, 7, 4, 9, 1]
+        );
+        assert_eq!(parse_permutation("").unwrap(), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn parse_error() {
+        match parse_permutation("1 0\n3 -2 x\n") {
+            Err(ReadError::Parse {
+                line,
+                column,
+                token,
+            }) => {
+                assert_eq!((line, column), (2, 3));
+                assert_eq!(token, "-2");
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+        let err = parse_permutation("0 1 é2").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Cannot read 'é2' as an integer at line 1, column 5"
+        );
+    }
+
+    #[test]
+    fn write() {
+        let permutation: Vec<usize> = (0..14).rev().collect();
+        let mut buf = Vec::new();
+        write_permutation(&permutation, &mut buf, "reversed").unwrap();
+        let text = String::from_utf8(buf).unwrap();
+        assert_eq!(text, "# reversed\n13 12 11 10 9 8 7 6 5 4 3 2\n1 0\n");
+        assert_eq!(parse_permutation(&text).unwrap(), permutation);
+    }
+}
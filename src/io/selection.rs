@@ -0,0 +1,144 @@
+/*!
+Text format of a selection of simplices
+
+The selection consists of groups separated by `/`, each of which starts with
+`V`, `E`, or `F` followed by the ids of vertices, edges, or faces.
+Groups may appear in any order, and an omitted group selects nothing.
+
+```
+use umesh::{io::selection::*, shapes, Mesh};
+
+let mesh = Mesh::from_permutation(&shapes::cube());
+let selection = parse_selection(&mesh, "V 3 / E / F 1 2").unwrap();
+assert_eq!(format_selection(&selection), "V 3 / E / F 1 2");
+```
+*/
+
+use crate::half_edge::{Mesh, Simplices};
+use crate::simplex_set::SimplexSet;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SelectionError {
+    /// The text does not follow the format
+    Syntax(String),
+    /// Id which is not less than the number of the simplices
+    OutOfRange {
+        kind: &'static str,
+        id: usize,
+        count: usize,
+    },
+}
+
+impl fmt::Display for SelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SelectionError::Syntax(message) => write!(f, "{}", message),
+            SelectionError::OutOfRange { kind, id, count } => write!(
+                f,
+                "{} {} does not exist, ids must be less than {}",
+                kind, id, count
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SelectionError {}
+
+/// Parse a selection, and check the ids against the mesh
+pub fn parse_selection<'mesh>(
+    mesh: &'mesh Mesh,
+    text: &str,
+) -> Result<Simplices<'mesh>, SelectionError> {
+    let mut groups: [Option<Vec<usize>>; 3] = [None, None, None];
+    for group in text.split('/') {
+        let mut tokens = group.split_whitespace();
+        let tag = match tokens.next() {
+            Some(tag) => tag,
+            None => continue,
+        };
+        let (index, kind, count) = match tag {
+            "V" => (0, "vertex", mesh.num_vertices()),
+            "E" => (1, "edge", mesh.num_edges()),
+            "F" => (2, "face", mesh.num_faces()),
+            _ => {
+                return Err(SelectionError::Syntax(format!(
+                    "Unknown group '{}', expected V, E, or F",
+                    tag
+                )))
+            }
+        };
+        if groups[index].is_some() {
+            return Err(SelectionError::Syntax(format!(
+                "Group {} appears twice",
+                tag
+            )));
+        }
+        let mut ids = Vec::new();
+        for token in tokens {
+            let id: usize = token.parse().map_err(|_| {
+                SelectionError::Syntax(format!("Cannot read '{}' as a {} id", token, kind))
+            })?;
+            if id >= count {
+                return Err(SelectionError::OutOfRange { kind, id, count });
+            }
+            ids.push(id);
+        }
+        groups[index] = Some(ids);
+    }
+    let [vertices, edges, faces] = groups;
+    Ok(mesh.simplicies(
+        &vertices.unwrap_or_default(),
+        &edges.unwrap_or_default(),
+        &faces.unwrap_or_default(),
+    ))
+}
+
+/// Format a selection with all three groups, e.g. `V 0 1 / E 0 / F`
+pub fn format_selection(simplices: &Simplices) -> String {
+    let group = |tag: &str, ids: &SimplexSet| {
+        ids.iter()
+            .fold(tag.to_string(), |group, id| format!("{} {}", group, id))
+    };
+    format!(
+        "{} / {} / {}",
+        group("V", simplices.vertices()),
+        group("E", simplices.edges()),
+        group("F", simplices.faces())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+
+    #[test]
+    fn round_trip() {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        let selection = parse_selection(&mesh, " F 3 1 /V 2 ").unwrap();
+        assert_eq!(format_selection(&selection), "V 2 / E / F 1 3");
+        assert!(parse_selection(&mesh, "").unwrap().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        assert_eq!(
+            parse_selection(&mesh, "E 6").unwrap_err().to_string(),
+            "edge 6 does not exist, ids must be less than 6"
+        );
+        assert!(matches!(
+            parse_selection(&mesh, "V 0 / V 1"),
+            Err(SelectionError::Syntax(_))
+        ));
+        assert!(matches!(
+            parse_selection(&mesh, "X 0"),
+            Err(SelectionError::Syntax(_))
+        ));
+        assert!(matches!(
+            parse_selection(&mesh, "F -1"),
+            Err(SelectionError::Syntax(_))
+        ));
+    }
+}
@@ -0,0 +1,339 @@
+/*!
+SVG drawing of a mesh in the plane
+
+Faces are drawn as filled `<polygon>`s, edges as `<line>`s with thicker boundary edges,
+and vertex ids optionally as `<text>`s.
+Without explicit positions the [Tutte embedding](Mesh::tutte_embedding) is used,
+in which the outer face of a closed mesh is drawn under the others.
+
+[write_loops_svg] draws only closed loops of vertices, e.g. [Mesh::boundary_loops], as `<polyline>`s.
+*/
+
+use super::{check_len, dot::PALETTE};
+use crate::half_edge::Mesh;
+use std::io::{self, Write};
+
+const MARGIN: f64 = 10.0;
+const FACE_FILL: &str = "#dddddd";
+
+/// Style of the SVG output
+#[derive(Debug, Clone, Copy)]
+pub struct SvgStyle<'a> {
+    /// Vertex positions in the plane, e.g. a parameterization.
+    /// The Tutte embedding is used if `None`.
+    pub positions: Option<&'a [[f64; 2]]>,
+    /// Class per face, colored through [PALETTE]
+    pub face_colors: Option<&'a [usize]>,
+    /// Label vertices by vertex id
+    pub vertex_labels: bool,
+    /// Width and height of the image
+    pub size: f64,
+}
+
+impl Default for SvgStyle<'_> {
+    fn default() -> Self {
+        SvgStyle {
+            positions: None,
+            face_colors: None,
+            vertex_labels: false,
+            size: 400.0,
+        }
+    }
+}
+
+/// Write the mesh as an SVG image
+///
+/// Returns an `InvalidInput` error if the lengths of positions or color classes do not match,
+/// or if positions are not given and the mesh has no Tutte embedding.
+pub fn write_svg<W: Write>(mesh: &Mesh, mut writer: W, style: &SvgStyle) -> io::Result<()> {
+    if let Some(colors) = style.face_colors {
+        check_len("face_colors", colors.len(), mesh.num_faces())?;
+    }
+    let embedding;
+    let positions = match style.positions {
+        Some(positions) => {
+            check_len("positions", positions.len(), mesh.num_vertices())?;
+            positions
+        }
+        None => {
+            embedding = mesh.tutte_embedding().ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "Only a connected mesh of genus zero with at most one boundary loop can be drawn without positions",
+                )
+            })?;
+            &embedding
+        }
+    };
+    let points = fit(positions, style.size);
+
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+        style.size
+    )?;
+    for f in 0..mesh.num_faces() {
+        let vertices: Vec<String> = mesh
+            .face_vertices(f)
+            .iter()
+            .map(|&v| format!("{:.3},{:.3}", points[v][0], points[v][1]))
+            .collect();
+        let fill = match style.face_colors {
+            Some(colors) => PALETTE[colors[f] % PALETTE.len()],
+            None => FACE_FILL,
+        };
+        writeln!(
+            writer,
+            r#"  <polygon points="{}" fill="{}" />"#,
+            vertices.join(" "),
+            fill
+        )?;
+    }
+    let boundary = mesh.boundary_edges();
+    for e in 0..mesh.num_edges() {
+        let (a, b) = mesh.edge_ends(e);
+        let (a, b) = (points[a], points[b]);
+        let width = if boundary.binary_search(&e).is_ok() {
+            3
+        } else {
+            1
+        };
+        writeln!(
+            writer,
+            r#"  <line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}" stroke="black" stroke-width="{}" />"#,
+            a[0], a[1], b[0], b[1], width
+        )?;
+    }
+    if style.vertex_labels {
+        for (v, p) in points.iter().enumerate() {
+            writeln!(
+                writer,
+                r#"  <text x="{:.3}" y="{:.3}" font-size="12">{}</text>"#,
+                p[0], p[1], v
+            )?;
+        }
+    }
+    writeln!(writer, "</svg>")
+}
+
+/// Write closed loops of vertices as `<polyline>`s colored through [PALETTE] per loop
+///
+/// The vertices are placed at [SvgStyle::positions], which are indexed by vertex id.
+/// Without positions each loop is drawn as a regular polygon, side by side in a row.
+/// [SvgStyle::face_colors] is not used.
+///
+/// Returns an `InvalidInput` error if a vertex of the loops has no position.
+pub fn write_loops_svg<W: Write>(
+    loops: &[Vec<usize>],
+    mut writer: W,
+    style: &SvgStyle,
+) -> io::Result<()> {
+    let mut positions = Vec::new();
+    for (i, vertices) in loops.iter().enumerate() {
+        for (j, &v) in vertices.iter().enumerate() {
+            positions.push(match style.positions {
+                Some(positions) => *positions.get(v).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Vertex {} of loop {} has no position", v, i),
+                    )
+                })?,
+                None => {
+                    let angle = 2.0 * std::f64::consts::PI * j as f64 / vertices.len() as f64;
+                    [3.0 * i as f64 + angle.cos(), angle.sin()]
+                }
+            });
+        }
+    }
+    let points = fit(&positions, style.size);
+
+    writeln!(
+        writer,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{0}" viewBox="0 0 {0} {0}">"#,
+        style.size
+    )?;
+    let mut start = 0;
+    for (i, vertices) in loops.iter().enumerate() {
+        let loop_points = &points[start..start + vertices.len()];
+        start += vertices.len();
+        // repeat the first point to close the loop
+        let coordinates: Vec<String> = loop_points
+            .iter()
+            .chain(loop_points.first())
+            .map(|p| format!("{:.3},{:.3}", p[0], p[1]))
+            .collect();
+        writeln!(
+            writer,
+            r#"  <polyline points="{}" fill="none" stroke="{}" stroke-width="3" />"#,
+            coordinates.join(" "),
+            PALETTE[i % PALETTE.len()]
+        )?;
+        if style.vertex_labels {
+            for (p, v) in loop_points.iter().zip(vertices) {
+                writeln!(
+                    writer,
+                    r#"  <text x="{:.3}" y="{:.3}" font-size="12">{}</text>"#,
+                    p[0], p[1], v
+                )?;
+            }
+        }
+    }
+    writeln!(writer, "</svg>")
+}
+
+/// Scale positions uniformly into the image, flipping the y-axis downward
+fn fit(positions: &[[f64; 2]], size: f64) -> Vec<[f64; 2]> {
+    let mut min = [f64::INFINITY; 2];
+    let mut max = [f64::NEG_INFINITY; 2];
+    for p in positions {
+        for i in 0..2 {
+            min[i] = min[i].min(p[i]);
+            max[i] = max[i].max(p[i]);
+        }
+    }
+    let extent = (max[0] - min[0]).max(max[1] - min[1]);
+    let scale = if extent > 0.0 {
+        (size - 2.0 * MARGIN) / extent
+    } else {
+        1.0
+    };
+    positions
+        .iter()
+        .map(|p| {
+            [
+                MARGIN + (p[0] - min[0]) * scale,
+                size - MARGIN - (p[1] - min[1]) * scale,
+            ]
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection_matrix::Connection;
+    use crate::fixtures;
+
+    /// Quad grid with `nx * ny` vertices
+    fn grid(nx: usize, ny: usize) -> Mesh {
+        let horizontal = (nx - 1) * ny;
+        let mut vertex_edge = Vec::new();
+        let mut edge_face = Vec::new();
+        for j in 0..ny {
+            for i in 0..nx - 1 {
+                let e = i + (nx - 1) * j;
+                vertex_edge.push((i + nx * j, e));
+                vertex_edge.push((i + 1 + nx * j, e));
+            }
+        }
+        for j in 0..ny - 1 {
+            for i in 0..nx {
+                let e = horizontal + i + nx * j;
+                vertex_edge.push((i + nx * j, e));
+                vertex_edge.push((i + nx * (j + 1), e));
+            }
+        }
+        for j in 0..ny - 1 {
+            for i in 0..nx - 1 {
+                let f = i + (nx - 1) * j;
+                edge_face.push((i + (nx - 1) * j, f));
+                edge_face.push((i + (nx - 1) * (j + 1), f));
+                edge_face.push((horizontal + i + nx * j, f));
+                edge_face.push((horizontal + i + 1 + nx * j, f));
+            }
+        }
+        Mesh::from_connections(
+            Connection::from_vec(vertex_edge),
+            Connection::from_vec(edge_face),
+        )
+    }
+
+    fn svg(mesh: &Mesh, style: &SvgStyle) -> String {
+        let mut buf = Vec::new();
+        write_svg(mesh, &mut buf, style).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn grid_counts() {
+        let mesh = grid(4, 3);
+        let colors: Vec<usize> = mesh
+            .face_coloring(PALETTE.len())
+            .unwrap()
+            .into_iter()
+            .map(usize::from)
+            .collect();
+        let style = SvgStyle {
+            face_colors: Some(&colors),
+            vertex_labels: true,
+            ..Default::default()
+        };
+        let svg = svg(&mesh, &style);
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<polygon ").count(), mesh.num_faces());
+        assert_eq!(svg.matches("<line ").count(), mesh.num_edges());
+        assert_eq!(
+            svg.matches(r#"stroke-width="3""#).count(),
+            mesh.boundary_edges().len()
+        );
+        assert_eq!(svg.matches("<text ").count(), mesh.num_vertices());
+        assert!(!svg.contains(FACE_FILL));
+    }
+
+    #[test]
+    fn tetrahedron() {
+        let mesh = fixtures::TETRAHEDRON.mesh();
+        let svg = svg(&mesh, &SvgStyle::default());
+        assert_eq!(svg.matches("<polygon ").count(), 4);
+        assert_eq!(svg.matches("<line ").count(), 6);
+        assert!(!svg.contains(r#"stroke-width="3""#));
+        assert!(!svg.contains("NaN"));
+    }
+
+    #[test]
+    fn loops() {
+        let mesh = fixtures::ANNULUS.mesh();
+        let loops = mesh.boundary_loops();
+        let style = SvgStyle {
+            vertex_labels: true,
+            ..Default::default()
+        };
+        let mut buf = Vec::new();
+        write_loops_svg(&loops, &mut buf, &style).unwrap();
+        let svg = String::from_utf8(buf).unwrap();
+        assert_eq!(svg.matches("<polyline ").count(), 2);
+        assert_eq!(svg.matches("<text ").count(), 8);
+        assert!(svg.contains(PALETTE[1]));
+        assert!(!svg.contains("NaN"));
+
+        let positions = [[0.0, 0.0]; 4];
+        let style = SvgStyle {
+            positions: Some(&positions),
+            ..Default::default()
+        };
+        let err = write_loops_svg(&loops, Vec::new(), &style).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn positions() {
+        let mesh = grid(2, 2);
+        let positions = [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]];
+        let style = SvgStyle {
+            positions: Some(&positions),
+            size: 100.0,
+            ..Default::default()
+        };
+        assert!(svg(&mesh, &style).contains(
+            r#"<line x1="10.000" y1="90.000" x2="90.000" y2="90.000" stroke="black" stroke-width="3" />"#
+        ));
+
+        let style = SvgStyle {
+            positions: Some(&positions[..3]),
+            ..Default::default()
+        };
+        let err = write_svg(&mesh, Vec::new(), &style).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
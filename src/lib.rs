@@ -1,3 +1,119 @@
+/*!
+umesh: micro mesher
+
+A crate for discrete differential geometry (DDG) based on [the textbook by Keenan Crane][DDG].
+
+Meshes are described combinatorially, either by the two connection matrices `A0` and `A1`
+(see [Connection]) or by a permutation on half-edges (see DDG §2.5 and [permutation]).
+Half-edges `2k` and `2k + 1` are twins, and `permutation[h]` is the next half-edge
+in the face of `h`.
+
+```
+use umesh::{gather_faces, gather_vertices, Mesh};
+
+// tetrahedron
+let permutation = [5, 6, 0, 8, 10, 2, 11, 3, 7, 4, 9, 1];
+assert_eq!(gather_vertices(&permutation).len(), 4);
+assert_eq!(gather_faces(&permutation).len(), 4);
+
+let mesh = Mesh::from_permutation(&permutation);
+let vertex = mesh.simplicies(&[0], &[], &[]);
+assert!(vertex.is_complex());
+assert!(!vertex.star().is_complex());
+```
+
+# Features
+
+- `std` (default): everything except the topology core, i.e. [connection_matrix], [permutation],
+  [half_edge], [simplex_set], [memory], [flat], and [builder], which only require `alloc` and can be used in
+  `no_std` contexts.
+  Without `std`, [builder::MeshBuilder] pairs the half-edges by a `BTreeMap` instead of a `HashMap`,
+  and timings are not recorded.
+- `u32-index`: store the connection matrices and the permutation of [Mesh] with `u32` indices
+  instead of `usize` (see [Index]). The footprint of a torus of 5M faces is reduced
+  from 991 MiB to 495 MiB. The public API is the same with and without the feature;
+  a mesh with more than `u32::MAX` incidences panics in `from_*`, and `try_from_*` return [Error::OutOfRange].
+- `parallel`: gather the simplices of [Simplices::star] and [Simplices::closure] for large selections,
+  and the orbits of [Mesh::from_permutation] for large permutations, on several threads
+  (see the `parallel` module). Implies `std`.
+- `fixtures`: the embedded test meshes of the `fixtures` module, e.g. an annulus and a genus 2 surface,
+  with their known counts and invariants. Implies `std`.
+
+[DDG]: https://www.cs.cmu.edu/~kmcrane/Projects/DDG
+*/
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+pub mod builder;
+#[cfg(feature = "std")]
+pub mod check;
 pub mod connection_matrix;
+#[cfg(feature = "std")]
+pub mod curvature;
+#[cfg(feature = "std")]
+pub mod embedding;
+pub mod error;
+#[cfg(all(feature = "std", any(test, feature = "fixtures")))]
+pub mod fixtures;
+pub mod flat;
 pub mod half_edge;
+#[cfg(feature = "std")]
+pub mod io;
+pub mod memory;
+#[cfg(feature = "std")]
+pub mod operators;
+#[cfg(feature = "parallel")]
+pub mod parallel;
 pub mod permutation;
+#[cfg(feature = "std")]
+pub mod predicates;
+#[cfg(feature = "std")]
+pub mod repair;
+#[cfg(feature = "std")]
+pub mod shapes;
+pub mod simplex_set;
+#[cfg(feature = "std")]
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod subdivision;
+#[cfg(feature = "std")]
+pub mod timing;
+
+/// Timers are not recorded without std
+#[cfg(not(feature = "std"))]
+mod timing {
+    pub(crate) struct Timer;
+
+    impl Timer {
+        pub(crate) fn start(_name: &'static str) -> Self {
+            Timer
+        }
+    }
+
+    pub(crate) fn record_memory(_report: &crate::memory::MemoryReport) {}
+}
+
+/// Items of the std prelude which are not in scope without std
+#[cfg(not(feature = "std"))]
+mod prelude {
+    pub(crate) use alloc::{
+        format,
+        string::{String, ToString},
+        vec,
+        vec::Vec,
+    };
+}
+
+pub use crate::connection_matrix::{Connection, Index};
+pub use crate::error::Error;
+pub use crate::flat::FlatHalfEdgeMesh;
+pub use crate::half_edge::{Mesh, Simplices};
+pub use crate::memory::MemoryReport;
+pub use crate::permutation::{
+    from_faces, gather_faces, gather_vertices, try_gather_faces, try_gather_vertices, Orbit,
+};
+pub use crate::simplex_set::SimplexSet;
+#[cfg(feature = "std")]
+pub use crate::stats::MeshStats;
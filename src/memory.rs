@@ -0,0 +1,140 @@
+/*!
+Heap memory of the buffers of a data structure
+
+[Mesh::memory_usage](crate::Mesh::memory_usage) lists each buffer with its used and allocated bytes,
+including the cached structures computed so far, and `shrink_to_fit` releases the difference:
+
+```
+# #[cfg(feature = "std")] {
+use umesh::{shapes, Mesh};
+
+let mut mesh = Mesh::from_permutation(&shapes::cube());
+mesh.vertex_adjacency();
+let report = mesh.memory_usage();
+assert!(report.buffers.iter().any(|buffer| buffer.name == "vertex_adjacency.to"));
+assert!(report.used() <= report.allocated());
+
+mesh.shrink_to_fit();
+assert_eq!(mesh.memory_usage().allocated(), mesh.memory_usage().used());
+println!("{}", report);
+# }
+```
+*/
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use core::fmt;
+
+/// Bytes of a buffer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Buffer {
+    pub name: String,
+    /// Bytes of the elements
+    pub used: usize,
+    /// Bytes of the capacity
+    pub allocated: usize,
+}
+
+/// Bytes of the buffers of a data structure, displayed as a table
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryReport {
+    pub buffers: Vec<Buffer>,
+}
+
+impl MemoryReport {
+    /// Total bytes of the elements
+    pub fn used(&self) -> usize {
+        self.buffers.iter().map(|buffer| buffer.used).sum()
+    }
+
+    /// Total bytes of the capacities
+    pub fn allocated(&self) -> usize {
+        self.buffers.iter().map(|buffer| buffer.allocated).sum()
+    }
+
+    pub(crate) fn push<T>(&mut self, name: &str, buffer: &Vec<T>) {
+        let size = core::mem::size_of::<T>();
+        self.buffers.push(Buffer {
+            name: name.to_string(),
+            used: buffer.len() * size,
+            allocated: buffer.capacity() * size,
+        });
+    }
+
+    /// Append the buffers of `other` with names prefixed by `prefix.`
+    pub(crate) fn extend(&mut self, prefix: &str, other: MemoryReport) {
+        self.buffers
+            .extend(other.buffers.into_iter().map(|buffer| Buffer {
+                name: format!("{}.{}", prefix, buffer.name),
+                ..buffer
+            }));
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let total = ("total", self.used(), self.allocated());
+        let rows = self
+            .buffers
+            .iter()
+            .map(|buffer| (buffer.name.as_str(), buffer.used, buffer.allocated))
+            .chain(Some(total));
+        let name_width = rows
+            .clone()
+            .map(|row| row.0.len())
+            .max()
+            .unwrap_or(0)
+            .max("buffer".len());
+        let value_width = rows
+            .clone()
+            .map(|row| row.2.to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("allocated".len());
+        writeln!(
+            f,
+            "{:<nw$}  {:>vw$}  {:>vw$}",
+            "buffer",
+            "used",
+            "allocated",
+            nw = name_width,
+            vw = value_width
+        )?;
+        for (name, used, allocated) in rows {
+            writeln!(
+                f,
+                "{:<nw$}  {:>vw$}  {:>vw$}",
+                name,
+                used,
+                allocated,
+                nw = name_width,
+                vw = value_width
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table() {
+        let mut report = MemoryReport::default();
+        report.push("a", &Vec::<u64>::with_capacity(4));
+        let mut inner = MemoryReport::default();
+        inner.push("to", &vec![0_u32; 3]);
+        report.extend("matrix", inner);
+        assert_eq!((report.used(), report.allocated()), (12, 44));
+        assert_eq!(
+            report.to_string(),
+            "\
+buffer          used  allocated
+a                  0         32
+matrix.to         12         12
+total             12         44
+"
+        );
+    }
+}
@@ -0,0 +1,272 @@
+/*!
+Dense exterior derivatives for prototyping on small meshes
+
+`d0` maps values on vertices to values on edges, and `d1` maps values on edges to values on faces
+(DDG §3.6). They are returned as row-major [DenseMatrix], which is easy to pass to other tools,
+but takes `rows × columns` elements even though each row has at most a few non-zeros.
+The functions refuse to allocate more than [DEFAULT_MAX_ELEMENTS] elements,
+and the `*_with_limit` variants take another limit.
+
+# Orientation
+
+An edge of a mesh created from a permutation is oriented along its half-edge `2k`,
+and a face along its half-edges. Otherwise an edge is oriented from its smaller vertex to the larger one,
+and a face along the walk of its boundary starting from the first end of its first edge.
+`d1 · d0 = 0` holds in both cases.
+*/
+
+use crate::connection_matrix::{to_usize, widen};
+use crate::error::Error;
+use crate::half_edge::Mesh;
+use crate::permutation::origins;
+
+/// Largest number of elements allocated by [Mesh::d0_dense] and [Mesh::d1_dense], i.e. 128 MiB
+pub const DEFAULT_MAX_ELEMENTS: usize = 1 << 24;
+
+/// Matrix stored in the row-major order
+#[derive(Debug, Clone, PartialEq)]
+pub struct DenseMatrix {
+    /// (rows, columns)
+    pub shape: (usize, usize),
+    /// `data[i * columns + j]` is the element at row `i` and column `j`
+    pub data: Vec<f64>,
+}
+
+impl DenseMatrix {
+    /// Zero matrix, or [Error::TooLarge] if it has more than `max_elements` elements
+    fn zeros(
+        what: &'static str,
+        shape: (usize, usize),
+        max_elements: usize,
+    ) -> Result<Self, Error> {
+        let elements = shape.0.saturating_mul(shape.1);
+        if elements > max_elements {
+            return Err(Error::TooLarge {
+                what,
+                elements,
+                limit: max_elements,
+            });
+        }
+        Ok(DenseMatrix {
+            shape,
+            data: vec![0.0; elements],
+        })
+    }
+
+    /// Element at row `i` and column `j`
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        assert!(i < self.shape.0 && j < self.shape.1);
+        self.data[i * self.shape.1 + j]
+    }
+
+    fn add(&mut self, i: usize, j: usize, value: f64) {
+        self.data[i * self.shape.1 + j] += value;
+    }
+}
+
+impl Mesh {
+    /// Exterior derivative on vertices as a dense `E × V` matrix,
+    /// `-1` at the tail and `+1` at the head of each edge (zero for a self-loop)
+    ///
+    /// Returns [Error::TooLarge] above [DEFAULT_MAX_ELEMENTS] elements.
+    pub fn d0_dense(&self) -> Result<DenseMatrix, Error> {
+        self.d0_dense_with_limit(DEFAULT_MAX_ELEMENTS)
+    }
+
+    /// [Mesh::d0_dense] returning [Error::TooLarge] above `max_elements` elements
+    pub fn d0_dense_with_limit(&self, max_elements: usize) -> Result<DenseMatrix, Error> {
+        let mut d0 =
+            DenseMatrix::zeros("d0", (self.num_edges(), self.num_vertices()), max_elements)?;
+        match self.permutation() {
+            Some(permutation) => {
+                let origin = origins(&widen(permutation));
+                for e in 0..self.num_edges() {
+                    d0.add(e, origin[2 * e], -1.0);
+                    d0.add(e, origin[2 * e + 1], 1.0);
+                }
+            }
+            None => {
+                for e in 0..self.num_edges() {
+                    let (tail, head) = self.edge_ends(e);
+                    d0.add(e, tail, -1.0);
+                    d0.add(e, head, 1.0);
+                }
+            }
+        }
+        Ok(d0)
+    }
+
+    /// Exterior derivative on edges as a dense `F × E` matrix,
+    /// `+1` for an edge along the boundary of the face and `-1` against it
+    ///
+    /// Returns [Error::TooLarge] above [DEFAULT_MAX_ELEMENTS] elements.
+    pub fn d1_dense(&self) -> Result<DenseMatrix, Error> {
+        self.d1_dense_with_limit(DEFAULT_MAX_ELEMENTS)
+    }
+
+    /// [Mesh::d1_dense] returning [Error::TooLarge] above `max_elements` elements
+    pub fn d1_dense_with_limit(&self, max_elements: usize) -> Result<DenseMatrix, Error> {
+        let mut d1 = DenseMatrix::zeros("d1", (self.num_faces(), self.num_edges()), max_elements)?;
+        match self.permutation() {
+            Some(permutation) => {
+                let permutation = widen(permutation);
+                for (f, orbit) in crate::permutation::gather_faces(&permutation)
+                    .iter()
+                    .enumerate()
+                {
+                    for h in orbit.indices() {
+                        d1.add(f, h / 2, if h % 2 == 0 { 1.0 } else { -1.0 });
+                    }
+                }
+            }
+            None => {
+                for f in 0..self.num_faces() {
+                    for (e, sign) in self.boundary_signs(f) {
+                        d1.add(f, e, sign);
+                    }
+                }
+            }
+        }
+        Ok(d1)
+    }
+
+    /// Edges of the face with their signs along the walk of [Mesh::face_vertices]
+    fn boundary_signs(&self, face: usize) -> Vec<(usize, f64)> {
+        let edges: Vec<usize> = self.face_edges(face).iter().map(|&e| to_usize(e)).collect();
+        let mut signs = Vec::with_capacity(edges.len());
+        let mut used = vec![false; edges.len()];
+        let mut current = match edges.first() {
+            Some(&e) => self.edge_ends(e).0,
+            None => return signs,
+        };
+        while let Some(i) = (0..edges.len()).find(|&i| {
+            let (a, b) = self.edge_ends(edges[i]);
+            !used[i] && (a == current || b == current)
+        }) {
+            used[i] = true;
+            let (a, b) = self.edge_ends(edges[i]);
+            if a == current {
+                signs.push((edges[i], 1.0));
+                current = b;
+            } else {
+                signs.push((edges[i], -1.0));
+                current = a;
+            }
+        }
+        signs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{connection_matrix::Connection, shapes};
+
+    fn product(a: &DenseMatrix, b: &DenseMatrix) -> DenseMatrix {
+        assert_eq!(a.shape.1, b.shape.0);
+        let mut c = DenseMatrix::zeros("product", (a.shape.0, b.shape.1), usize::MAX).unwrap();
+        for i in 0..a.shape.0 {
+            for k in 0..a.shape.1 {
+                for j in 0..b.shape.1 {
+                    c.add(i, j, a.get(i, k) * b.get(k, j));
+                }
+            }
+        }
+        c
+    }
+
+    /// Same incidences without the permutation
+    fn unoriented(mesh: &Mesh) -> Mesh {
+        let vertex_edge = Connection::from_iter(
+            (0..mesh.num_edges())
+                .flat_map(|e| mesh.edge_vertices(e).iter().map(move |&v| (to_usize(v), e))),
+        );
+        let edge_face = Connection::from_iter(
+            (0..mesh.num_edges())
+                .flat_map(|e| mesh.edge_faces(e).iter().map(move |&f| (e, to_usize(f)))),
+        );
+        Mesh::from_connections(vertex_edge, edge_face)
+    }
+
+    /// The non-zeros of `d0` and `d1` are the vertex-edge and edge-face incidences
+    fn assert_incidences(mesh: &Mesh) {
+        let d0 = mesh.d0_dense().unwrap();
+        assert_eq!(d0.shape, (mesh.num_edges(), mesh.num_vertices()));
+        for e in 0..mesh.num_edges() {
+            let vertices = widen(mesh.edge_vertices(e));
+            for v in 0..mesh.num_vertices() {
+                // zero for a self-loop
+                let expected = if vertices.len() == 2 && vertices.contains(&v) {
+                    1.0
+                } else {
+                    0.0
+                };
+                assert_eq!(d0.get(e, v).abs(), expected);
+            }
+            assert_eq!(
+                (0..mesh.num_vertices()).map(|v| d0.get(e, v)).sum::<f64>(),
+                0.0
+            );
+        }
+
+        let d1 = mesh.d1_dense().unwrap();
+        assert_eq!(d1.shape, (mesh.num_faces(), mesh.num_edges()));
+        for e in 0..mesh.num_edges() {
+            let faces = widen(mesh.edge_faces(e));
+            // zero if both half-edges are on the face
+            let both_sides = mesh.permutation().is_some() && faces.len() == 1;
+            for f in 0..mesh.num_faces() {
+                let expected = if faces.contains(&f) && !both_sides {
+                    1.0
+                } else {
+                    0.0
+                };
+                assert_eq!(d1.get(f, e).abs(), expected);
+            }
+        }
+
+        let zero = product(&d1, &d0);
+        assert!(zero.data.iter().all(|&x| x == 0.0));
+    }
+
+    #[test]
+    fn cube() {
+        let mesh = Mesh::from_permutation(&shapes::cube());
+        assert_incidences(&mesh);
+        assert_incidences(&unoriented(&mesh));
+
+        // an edge is traversed in the opposite directions by its two faces
+        let d1 = mesh.d1_dense().unwrap();
+        for e in 0..mesh.num_edges() {
+            assert_eq!(
+                (0..mesh.num_faces()).map(|f| d1.get(f, e)).sum::<f64>(),
+                0.0
+            );
+        }
+    }
+
+    #[test]
+    fn fixtures() {
+        for fixture in &crate::fixtures::ALL {
+            let mesh = fixture.mesh();
+            assert_incidences(&mesh);
+            assert_incidences(&unoriented(&mesh));
+        }
+    }
+
+    #[test]
+    fn limit() {
+        let mesh = Mesh::from_permutation(&shapes::cube());
+        assert!(mesh.d0_dense_with_limit(12 * 8).is_ok());
+        let e = mesh.d0_dense_with_limit(12 * 8 - 1).unwrap_err();
+        assert!(matches!(
+            e,
+            Error::TooLarge {
+                what: "d0",
+                elements: 96,
+                limit: 95
+            }
+        ));
+        assert!(mesh.d1_dense_with_limit(0).is_err());
+    }
+}
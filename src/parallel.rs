@@ -0,0 +1,228 @@
+/*!
+Parallel gather of connected simplices for large selections
+
+[crate::Simplices::star] and [crate::Simplices::closure] collect the simplices connected
+to a selection row by row. With the `parallel` feature, a selection of at least [DEFAULT_THRESHOLD]
+simplices, or of the threshold given to [crate::Simplices::star_with_threshold]
+and [crate::Simplices::closure_with_threshold], is split into a chunk per thread if there are several cores, each chunk is gathered into a sorted buffer,
+and the buffers are merged. The result is the same as the sequential gather.
+
+```
+use umesh::{shapes, Mesh};
+
+let mesh = Mesh::from_permutation(&shapes::torus(50, 50).unwrap());
+let half: Vec<usize> = (0..mesh.num_vertices()).step_by(2).collect();
+let selection = mesh.simplicies(&half, &[], &[]);
+
+let sequential = selection.star_with_threshold(usize::MAX);
+let parallel = selection.star_with_threshold(0);
+assert_eq!(parallel.faces(), sequential.faces());
+```
+
+[gather_vertices] and [gather_faces] split the half-edges into a range per thread.
+Each thread traces the orbits whose minimum is in its range, and stops tracing an orbit
+at the first half-edge below its range, which belongs to the thread owning the minimum.
+The orbits of the ranges are concatenated in order, which is the result of [crate::gather_vertices]
+and [crate::gather_faces]. [crate::Mesh::from_permutation] takes this path from
+[DEFAULT_ORBIT_THRESHOLD] half-edges, see [crate::Mesh::from_permutation_with_threshold].
+*/
+
+use crate::connection_matrix::{to_usize, Connection};
+use crate::permutation::{gather_orbits_in, twin, Orbit};
+
+/// Number of selected simplices from which the gather runs in parallel by default,
+/// below which spawning threads costs more than the gather
+pub const DEFAULT_THRESHOLD: usize = 1 << 16;
+
+/// Number of half-edges from which [crate::Mesh::from_permutation] gathers the orbits in parallel
+pub const DEFAULT_ORBIT_THRESHOLD: usize = 1 << 18;
+
+/// [crate::gather_vertices] on `threads` threads, with the same result
+pub fn gather_vertices(permutation: &[usize], threads: usize) -> Vec<Orbit> {
+    assert_eq!(permutation.len() % 2, 0);
+    gather_orbits(permutation.len(), |h| permutation[twin(h)], threads)
+}
+
+/// [crate::gather_faces] on `threads` threads, with the same result
+pub fn gather_faces(permutation: &[usize], threads: usize) -> Vec<Orbit> {
+    assert_eq!(permutation.len() % 2, 0);
+    gather_orbits(permutation.len(), |h| permutation[h], threads)
+}
+
+/// Orbits of `next` on `0..n` sorted by their minimum, gathered by a range of `0..n` per thread
+fn gather_orbits(n: usize, next: impl Fn(usize) -> usize + Sync, threads: usize) -> Vec<Orbit> {
+    let chunk = n.div_ceil(threads.max(1)).max(1);
+    let next = &next;
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..n)
+            .step_by(chunk)
+            .map(|start| {
+                scope.spawn(move || gather_orbits_in(n, start..n.min(start + chunk), next))
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("gather thread panicked"))
+            .collect()
+    })
+}
+
+/// Sorted ids without duplicates connected to any of `ids`, using `threads` threads
+pub(crate) fn gather(connection: &Connection, ids: &[usize], threads: usize) -> Vec<usize> {
+    let chunk = ids.len().div_ceil(threads.max(1)).max(1);
+    let buffers: Vec<Vec<usize>> = std::thread::scope(|scope| {
+        let handles: Vec<_> = ids
+            .chunks(chunk)
+            .map(|ids| {
+                scope.spawn(move || {
+                    let mut buffer: Vec<usize> = ids
+                        .iter()
+                        .flat_map(|&id| connection.get_connected(id).iter())
+                        .map(|&i| to_usize(i))
+                        .collect();
+                    buffer.sort_unstable();
+                    buffer.dedup();
+                    buffer
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("gather thread panicked"))
+            .collect()
+    });
+    merge(buffers)
+}
+
+/// Number of threads of the parallel gather
+pub(crate) fn num_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// k-way merge of sorted buffers, dropping duplicates
+fn merge(buffers: Vec<Vec<usize>>) -> Vec<usize> {
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    let mut merged = Vec::with_capacity(buffers.iter().map(Vec::len).max().unwrap_or(0));
+    let mut heads: BinaryHeap<_> = buffers
+        .iter()
+        .enumerate()
+        .filter_map(|(k, buffer)| Some(Reverse((*buffer.first()?, k, 0))))
+        .collect();
+    while let Some(Reverse((id, k, i))) = heads.pop() {
+        if merged.last() != Some(&id) {
+            merged.push(id);
+        }
+        if let Some(&next) = buffers[k].get(i + 1) {
+            heads.push(Reverse((next, k, i + 1)));
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{shapes, Mesh};
+
+    #[test]
+    fn merge_buffers() {
+        let merged = merge(vec![vec![1, 4, 9], vec![], vec![0, 4, 5, 10], vec![9]]);
+        assert_eq!(merged, [0, 1, 4, 5, 9, 10]);
+        assert!(merge(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn same_as_sequential() {
+        let mesh = Mesh::from_permutation(&shapes::random_triangulation(200, 1).unwrap());
+        let vertex_edge = Connection::from_iter(
+            (0..mesh.num_vertices())
+                .flat_map(|v| mesh.vertex_edges(v).iter().map(move |&e| (v, to_usize(e)))),
+        );
+        for step in 1..5 {
+            let ids: Vec<usize> = (0..mesh.num_vertices()).step_by(step).collect();
+            let expected: Vec<usize> = vertex_edge
+                .gather_connected(ids.iter().cloned())
+                .into_iter()
+                .collect();
+            for threads in [1, 2, 3, 8, 1000] {
+                assert_eq!(gather(&vertex_edge, &ids, threads), expected);
+            }
+        }
+        assert!(gather(&vertex_edge, &[], 4).is_empty());
+    }
+
+    /// Random permutation on `n` half-edges, i.e. a random rotation system
+    fn random_permutation(n: usize, seed: u64) -> Vec<usize> {
+        let mut permutation: Vec<usize> = (0..n).collect();
+        let mut state = seed;
+        for i in (1..n).rev() {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            permutation.swap(i, (state >> 33) as usize % (i + 1));
+        }
+        permutation
+    }
+
+    #[test]
+    fn orbits_same_as_sequential() {
+        for seed in 0..20 {
+            for &n in &[0, 2, 10, 64, 1000] {
+                let permutation = random_permutation(n, seed);
+                let vertices = crate::gather_vertices(&permutation);
+                let faces = crate::gather_faces(&permutation);
+                for threads in [1, 2, 3, 7, 64, 5000] {
+                    assert_eq!(gather_vertices(&permutation, threads), vertices);
+                    assert_eq!(gather_faces(&permutation, threads), faces);
+                }
+            }
+        }
+        // a single orbit over all threads
+        let cycle: Vec<usize> = (0..1000).map(|h| (h + 1) % 1000).collect();
+        assert_eq!(gather_faces(&cycle, 8), crate::gather_faces(&cycle));
+    }
+
+    #[test]
+    fn from_permutation_threshold() {
+        let permutation = shapes::torus(30, 20).unwrap();
+        let sequential = Mesh::from_permutation_with_threshold(&permutation, usize::MAX);
+        let parallel = Mesh::from_permutation_with_threshold(&permutation, 0);
+        for v in 0..sequential.num_vertices() {
+            assert_eq!(parallel.vertex_edges(v), sequential.vertex_edges(v));
+        }
+        for e in 0..sequential.num_edges() {
+            assert_eq!(parallel.edge_faces(e), sequential.edge_faces(e));
+        }
+    }
+
+    #[test]
+    fn star_and_closure() {
+        let mesh = Mesh::from_permutation(&shapes::torus(20, 30).unwrap());
+        let every = |n: usize, step: usize| -> Vec<usize> { (0..n).step_by(step).collect() };
+        let selection = mesh.simplicies(
+            &every(mesh.num_vertices(), 2),
+            &every(mesh.num_edges(), 7),
+            &every(mesh.num_faces(), 3),
+        );
+        let ids = |s: &crate::Simplices| {
+            [
+                s.vertices().to_btree_set(),
+                s.edges().to_btree_set(),
+                s.faces().to_btree_set(),
+            ]
+        };
+        let sequential = [
+            selection.star_with_threshold(usize::MAX),
+            selection.closure_with_threshold(usize::MAX),
+        ];
+        let parallel = [
+            selection.star_with_threshold(0),
+            selection.closure_with_threshold(0),
+        ];
+        for (s, p) in sequential.iter().zip(&parallel) {
+            assert_eq!(ids(s), ids(p));
+        }
+    }
+}
@@ -1,107 +1,997 @@
 /*!
 Utility for permutation (see DDG §2.5 for detail)
+
+# Numbering
+
+Ids are derived from the half-edge ids alone, so the same permutation always gives the same ids:
+
+- edge `k` consists of the half-edges `2k` and `2k + 1`,
+- vertices and faces are numbered in the ascending order of their smallest half-edge,
+  see [gather_vertices] and [gather_faces].
+
+Appending half-edges which extend existing orbits or form new orbits never renumbers
+the existing vertices, edges, and faces, since the new orbits have larger minimums.
+[crate::Mesh::from_permutation] uses this numbering, and [from_faces] numbers the edges
+in the order of their first appearance, so rebuilding from the same faces gives the same ids.
 */
 
-#[derive(Debug, Clone, PartialOrd, Ord, PartialEq, Eq)]
+use crate::builder::{FaceError, MeshBuilder};
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use crate::timing::Timer;
+use alloc::collections::BTreeMap;
+use core::fmt;
+
+/// Number of half-edges stored without heap allocation,
+/// which covers the valences and face degrees of usual meshes
+const INLINE_LEN: usize = 8;
+
+/// Inline half-edges are stored as `u32` to keep an [Orbit] within 40 bytes on 64-bit targets,
+/// and orbits with a half-edge beyond `u32::MAX` are moved to the heap
+#[derive(Debug, Clone)]
+enum Storage {
+    Inline { len: u8, edges: [u32; INLINE_LEN] },
+    Heap(Vec<usize>),
+}
+
+#[derive(Debug, Clone)]
 pub struct Orbit {
     /// rotated to the minimum comes first
-    edges: Vec<usize>,
+    edges: Storage,
+}
+
+/// Error of [Orbit::try_new] for no half-edges
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyOrbit;
+
+impl fmt::Display for EmptyOrbit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Orbit must contain at least one half-edge")
+    }
 }
 
+impl core::error::Error for EmptyOrbit {}
+
 impl Orbit {
-    fn new(edges: &[usize]) -> Self {
-        assert!(!edges.is_empty());
-        // take argmin
-        let mut argmin = 0;
-        let mut min = edges[0];
-        for (i, e) in edges.iter().enumerate() {
-            if min > *e {
-                argmin = i;
-                min = *e;
+    /// Orbit visiting the half-edges in the given cyclic order
+    pub fn try_new(edges: &[usize]) -> Result<Self, EmptyOrbit> {
+        if edges.is_empty() {
+            return Err(EmptyOrbit);
+        }
+        Ok(Self::new_nonempty(edges))
+    }
+
+    /// Orbit of a half-edge fixed by the permutation
+    pub fn singleton(edge: usize) -> Self {
+        Self::new_nonempty(&[edge])
+    }
+
+    /// Orbit exchanging two half-edges
+    pub fn pair(a: usize, b: usize) -> Self {
+        Self::new_nonempty(&[a, b])
+    }
+
+    /// `edges` must not be empty, which holds in [gather_orbits]
+    /// since each orbit contains the half-edge it starts from
+    fn new_nonempty(edges: &[usize]) -> Self {
+        debug_assert!(!edges.is_empty());
+        let argmin = (0..edges.len()).min_by_key(|&i| edges[i]).unwrap();
+        let rotated = edges[argmin..].iter().chain(&edges[..argmin]);
+        if edges.len() <= INLINE_LEN && edges.iter().all(|&h| h <= u32::MAX as usize) {
+            let mut inline = [0; INLINE_LEN];
+            for (slot, &h) in inline.iter_mut().zip(rotated) {
+                *slot = h as u32;
+            }
+            Orbit {
+                edges: Storage::Inline {
+                    len: edges.len() as u8,
+                    edges: inline,
+                },
+            }
+        } else {
+            Orbit {
+                edges: Storage::Heap(rotated.copied().collect()),
             }
         }
-        // rotate
-        let (a, b) = edges.split_at(argmin);
-        let rotated: Vec<_> = b.iter().chain(a.iter()).cloned().collect();
-        Orbit { edges: rotated }
     }
 
-    pub fn indices(&self) -> &[usize] {
-        &self.edges
+    /// Half-edges in the cyclic order starting from the minimum
+    pub fn indices(&self) -> Indices<'_> {
+        match &self.edges {
+            Storage::Inline { len, edges } => Indices::Inline(edges[..*len as usize].iter()),
+            Storage::Heap(edges) => Indices::Heap(edges.iter()),
+        }
+    }
+
+    /// Number of half-edges, i.e. the valence of a vertex or the degree of a face
+    pub fn len(&self) -> usize {
+        match &self.edges {
+            Storage::Inline { len, .. } => *len as usize,
+            Storage::Heap(edges) => edges.len(),
+        }
+    }
+
+    /// Always `false` since an orbit contains at least one half-edge
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// Smallest half-edge, which comes first and determines the id of the orbit
+    pub fn first(&self) -> usize {
+        match &self.edges {
+            Storage::Inline { edges, .. } => edges[0] as usize,
+            Storage::Heap(edges) => edges[0],
+        }
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Iterator over the half-edges of an [Orbit], see [Orbit::indices]
+#[derive(Debug, Clone)]
+pub enum Indices<'a> {
+    Inline(core::slice::Iter<'a, u32>),
+    Heap(core::slice::Iter<'a, usize>),
+}
 
-    #[test]
-    fn orbit_new() {
-        let v = Orbit::new(&[2, 1, 3]);
-        assert_eq!(
-            v,
-            Orbit {
-                edges: vec![1, 3, 2]
+impl Iterator for Indices<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match self {
+            Indices::Inline(iter) => iter.next().map(|&h| h as usize),
+            Indices::Heap(iter) => iter.next().copied(),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Indices::Inline(iter) => iter.size_hint(),
+            Indices::Heap(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Indices<'_> {}
+
+impl<'a> IntoIterator for &'a Orbit {
+    type Item = usize;
+    type IntoIter = Indices<'a>;
+
+    fn into_iter(self) -> Indices<'a> {
+        self.indices()
+    }
+}
+
+impl PartialEq for Orbit {
+    fn eq(&self, other: &Self) -> bool {
+        self.indices().eq(other.indices())
+    }
+}
+
+impl Eq for Orbit {}
+
+impl PartialOrd for Orbit {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Orbit {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.indices().cmp(other.indices())
+    }
+}
+
+/// Cycle notation, e.g. `(0 3 5)`
+impl fmt::Display for Orbit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "(")?;
+        for (i, e) in self.indices().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
             }
-        );
+            write!(f, "{}", e)?;
+        }
+        write!(f, ")")
     }
 }
 
-fn twin(index: usize) -> usize {
-    if index % 2 == 0 {
-        index + 1
-    } else {
-        index - 1
+/// Reason why a sequence is not a permutation on half-edges
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermutationError {
+    /// Half-edges must be paired with twins
+    OddLength(usize),
+    /// `permutation[index] = value` is not a half-edge
+    OutOfRange { index: usize, value: usize },
+    /// Both `permutation[first]` and `permutation[second]` are `value`
+    NotBijective {
+        first: usize,
+        second: usize,
+        value: usize,
+    },
+}
+
+impl fmt::Display for PermutationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PermutationError::OddLength(len) => {
+                write!(f, "Number of half-edges must be even, but got {}", len)
+            }
+            PermutationError::OutOfRange { index, value } => {
+                write!(f, "permutation[{}] = {} is out of range", index, value)
+            }
+            PermutationError::NotBijective {
+                first,
+                second,
+                value,
+            } => write!(
+                f,
+                "Not a bijection: permutation[{}] = permutation[{}] = {}",
+                first, second, value
+            ),
+        }
     }
 }
 
-pub fn gather_vertices(permutation: &[usize]) -> Vec<Orbit> {
-    // XXX More check?
-    assert_eq!(permutation.len() % 2, 0);
+impl core::error::Error for PermutationError {}
+
+/// Check that the input is a bijection on an even number of half-edges
+pub fn validate(permutation: &[usize]) -> Result<(), PermutationError> {
+    let _timer = Timer::start("validate");
+    let n = permutation.len();
+    if !n.is_multiple_of(2) {
+        return Err(PermutationError::OddLength(n));
+    }
+    let mut preimage = vec![None; n];
+    for (index, &value) in permutation.iter().enumerate() {
+        if value >= n {
+            return Err(PermutationError::OutOfRange { index, value });
+        }
+        if let Some(first) = preimage[value] {
+            return Err(PermutationError::NotBijective {
+                first,
+                second: index,
+                value,
+            });
+        }
+        preimage[value] = Some(index);
+    }
+    Ok(())
+}
+
+/// Reason why a list of faces does not form a closed oriented surface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FaceListError {
+    /// The face has less than two vertices
+    DegenerateFace(usize),
+    /// The directed edge `from -> to` appears twice
+    DuplicatedEdge { from: usize, to: usize },
+    /// The directed edge `from -> to` has no opposite `to -> from`
+    UnpairedEdge { from: usize, to: usize },
+}
+
+impl fmt::Display for FaceListError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FaceListError::DegenerateFace(face) => {
+                write!(f, "Face {} has less than two vertices", face)
+            }
+            FaceListError::DuplicatedEdge { from, to } => write!(
+                f,
+                "Directed edge {} -> {} appears twice, the surface is not oriented or not a manifold",
+                from, to
+            ),
+            FaceListError::UnpairedEdge { from, to } => write!(
+                f,
+                "Directed edge {} -> {} has no opposite, the surface is not closed",
+                from, to
+            ),
+        }
+    }
+}
+
+impl core::error::Error for FaceListError {}
+
+/// Permutation on half-edges of a closed oriented surface given by faces
+///
+/// Each face lists its vertices in the counter-clockwise order,
+/// and every directed edge `a -> b` must be paired with exactly one `b -> a` in another face.
+/// Edges are numbered in the order of first appearance.
+/// The vertex ids of the input are not kept, see [gather_vertices] for the numbering.
+pub fn from_faces(faces: &[Vec<usize>]) -> Result<Vec<usize>, FaceListError> {
+    from_face_iter(faces)
+}
+
+/// [from_faces] consuming the faces one by one
+///
+/// The faces are not kept, so they can be streamed e.g. from a file by [crate::io::faces::FaceReader].
+/// The memory is proportional to the number of half-edges.
+pub fn from_face_iter<I, F>(faces: I) -> Result<Vec<usize>, FaceListError>
+where
+    I: IntoIterator<Item = F>,
+    F: AsRef<[usize]>,
+{
+    let _timer = Timer::start("from_faces");
+    let faces = faces.into_iter();
+    let mut builder = MeshBuilder::default();
+    // most meshes are triangulated
+    builder.reserve(3 * faces.size_hint().0);
+    for (f, face) in faces.enumerate() {
+        builder
+            .insert_face(face.as_ref())
+            .map_err(|error| match error {
+                FaceError::Degenerate => FaceListError::DegenerateFace(f),
+                FaceError::Duplicated { from, to } => FaceListError::DuplicatedEdge { from, to },
+            })?;
+    }
+    if let Some((from, to)) = builder.unpaired_edge() {
+        return Err(FaceListError::UnpairedEdge { from, to });
+    }
+    Ok(builder.into_permutation())
+}
 
-    // FIXME we can implement by more efficient algorithm
-    let mut vs: Vec<_> = (0..permutation.len())
-        .map(|init| {
-            // iterator over twin-next orbit
-            let mut orbit = vec![init];
-            let mut current = init;
-            loop {
-                let t = twin(current);
-                current = permutation[t];
-                if current == init {
-                    break;
+/// Permutation of the dual surface, i.e. `h -> permutation[twin(h)]`
+///
+/// Faces of the dual are the vertex orbits of the input and vice versa,
+/// so vertex `i` of the dual corresponds to face `i` of the input.
+/// Taking the dual twice gives back the input.
+pub fn dual(permutation: &[usize]) -> Vec<usize> {
+    (0..permutation.len())
+        .map(|h| permutation[twin(h)])
+        .collect()
+}
+
+/// Split into the permutations of connected components
+///
+/// Components are ordered by their smallest half-edge, which is also the order of their smallest vertex id.
+/// Edges keep their relative order in each component.
+pub fn split_components(permutation: &[usize]) -> Vec<Vec<usize>> {
+    let _timer = Timer::start("split_components");
+    let num_edges = permutation.len() / 2;
+    let mut component = vec![None; num_edges];
+    let mut num_components = 0;
+    for start in 0..num_edges {
+        if component[start].is_some() {
+            continue;
+        }
+        component[start] = Some(num_components);
+        let mut stack = vec![start];
+        while let Some(e) = stack.pop() {
+            for &h in &[2 * e, 2 * e + 1] {
+                let next = permutation[h] / 2;
+                if component[next].is_none() {
+                    component[next] = Some(num_components);
+                    stack.push(next);
                 }
-                orbit.push(current);
             }
-            Orbit::new(&orbit)
+        }
+        num_components += 1;
+    }
+
+    // new id of each edge in its component
+    let mut counts = vec![0; num_components];
+    let new_edge: Vec<usize> = component
+        .iter()
+        .map(|c| {
+            let c = c.unwrap();
+            counts[c] += 1;
+            counts[c] - 1
+        })
+        .collect();
+    let mut components: Vec<Vec<usize>> = counts.iter().map(|&n| vec![0; 2 * n]).collect();
+    for (h, &next) in permutation.iter().enumerate() {
+        let c = component[h / 2].unwrap();
+        components[c][2 * new_edge[h / 2] + h % 2] = 2 * new_edge[next / 2] + next % 2;
+    }
+    components
+}
+
+/// Canonical relabeling of the half-edges, equal for isomorphic surfaces
+///
+/// Each component is relabeled by a breadth-first search from every half-edge of the rarest
+/// class of invariants (the degree of its face and the valence of its vertex),
+/// keeping the lexicographically smallest result, and the components are sorted.
+/// It takes `O(kn)` time for a component of `n` half-edges with `k` of them in the rarest class,
+/// which is still quadratic if all half-edges look alike, e.g. for a regular torus.
+/// [is_isomorphic] compares two surfaces without it.
+/// Only isomorphisms preserving the orientation are considered.
+pub fn canonical_form(permutation: &[usize]) -> Vec<usize> {
+    let mut components: Vec<Vec<usize>> = split_components(permutation)
+        .iter()
+        .map(|component| {
+            let invariants = invariants(component);
+            let rarest = rarest(&invariants);
+            (0..component.len())
+                .filter(|&start| invariants[start] == rarest)
+                .map(|start| relabel_from(component, start))
+                .min()
+                .unwrap_or_default()
         })
         .collect();
-    vs.sort_unstable();
-    vs.dedup();
-    vs
+    components.sort_by(|a, b| (a.len(), a).cmp(&(b.len(), b)));
+    let mut canonical = Vec::with_capacity(permutation.len());
+    for component in components {
+        let offset = canonical.len();
+        canonical.extend(component.iter().map(|h| h + offset));
+    }
+    canonical
+}
+
+/// Whether there is a relabeling of half-edges keeping twins, which maps one permutation to the other
+///
+/// Each component of `a` is matched against the components of `b` with the same histogram
+/// of invariants. A start in the rarest class of `a` is fixed, and the half-edges are paired
+/// by a breadth-first search from each start of `b` with the same invariant,
+/// which stops at the first conflict.
+pub fn is_isomorphic(a: &[usize], b: &[usize]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut unmatched: Vec<Option<Component>> = split_components(b)
+        .into_iter()
+        .map(|component| Some(Component::new(component)))
+        .collect();
+    split_components(a).into_iter().all(|component| {
+        let a = Component::new(component);
+        let found = unmatched.iter().position(|b| match b {
+            Some(b) => a.is_isomorphic(b),
+            None => false,
+        });
+        match found {
+            Some(i) => {
+                unmatched[i] = None;
+                true
+            }
+            None => false,
+        }
+    })
+}
+
+/// Degree of the face and valence of the vertex of each half-edge, kept by isomorphisms
+fn invariants(permutation: &[usize]) -> Vec<(usize, usize)> {
+    let mut invariants = vec![(0, 0); permutation.len()];
+    for orbit in gather_faces(permutation) {
+        for h in orbit.indices() {
+            invariants[h].0 = orbit.len();
+        }
+    }
+    for orbit in gather_vertices(permutation) {
+        for h in orbit.indices() {
+            invariants[h].1 = orbit.len();
+        }
+    }
+    invariants
+}
+
+fn histogram(invariants: &[(usize, usize)]) -> BTreeMap<(usize, usize), usize> {
+    let mut histogram = BTreeMap::new();
+    for &invariant in invariants {
+        *histogram.entry(invariant).or_insert(0) += 1;
+    }
+    histogram
+}
+
+/// Invariant shared by the fewest half-edges, the smallest one among ties
+fn rarest(invariants: &[(usize, usize)]) -> (usize, usize) {
+    histogram(invariants)
+        .into_iter()
+        .min_by_key(|&(invariant, count)| (count, invariant))
+        .map_or((0, 0), |(invariant, _)| invariant)
+}
+
+/// Connected permutation with the invariants of its half-edges
+struct Component {
+    permutation: Vec<usize>,
+    invariants: Vec<(usize, usize)>,
+    histogram: BTreeMap<(usize, usize), usize>,
+}
+
+impl Component {
+    fn new(permutation: Vec<usize>) -> Self {
+        let invariants = invariants(&permutation);
+        let histogram = histogram(&invariants);
+        Component {
+            permutation,
+            invariants,
+            histogram,
+        }
+    }
+
+    fn is_isomorphic(&self, other: &Component) -> bool {
+        if self.permutation.len() != other.permutation.len() || self.histogram != other.histogram {
+            return false;
+        }
+        if self.permutation.is_empty() {
+            return true;
+        }
+        let rarest = rarest(&self.invariants);
+        let start = self.invariants.iter().position(|&i| i == rarest).unwrap();
+        (0..other.permutation.len())
+            .filter(|&h| other.invariants[h] == rarest)
+            .any(|h| pair_from(&self.permutation, start, &other.permutation, h))
+    }
+}
+
+/// Whether pairing `start_a` with `start_b` extends to an isomorphism
+/// of two connected permutations of the same length
+///
+/// The pairs commute with the twins and the permutations, so they cover all of `b`
+/// and form a bijection once all of `a` is visited.
+fn pair_from(a: &[usize], start_a: usize, b: &[usize], start_b: usize) -> bool {
+    let mut map = vec![None; a.len()];
+    let mut queue = Vec::with_capacity(a.len());
+    let pair =
+        |x: usize, y: usize, map: &mut Vec<Option<usize>>, queue: &mut Vec<usize>| match map[x] {
+            Some(mapped) => mapped == y,
+            None => {
+                map[x] = Some(y);
+                map[twin(x)] = Some(twin(y));
+                queue.push(x);
+                queue.push(twin(x));
+                true
+            }
+        };
+    pair(start_a, start_b, &mut map, &mut queue);
+    let mut i = 0;
+    while i < queue.len() {
+        let x = queue[i];
+        let y = map[x].unwrap();
+        if !pair(a[x], b[y], &mut map, &mut queue) {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Whether `candidate` is the dual surface of `primal` up to isomorphism,
+/// i.e. the dual of `candidate` is isomorphic to `primal`
+///
+/// A candidate given by [dual] without relabeling is accepted by comparing the permutations,
+/// and only a relabeled candidate goes through [is_isomorphic].
+pub fn is_dual(primal: &[usize], candidate: &[usize]) -> bool {
+    let dual = dual(candidate);
+    dual == primal || is_isomorphic(&dual, primal)
+}
+
+/// Relabel a connected permutation in the order of a breadth-first search from `start`
+fn relabel_from(permutation: &[usize], start: usize) -> Vec<usize> {
+    let mut label = vec![None; permutation.len()];
+    let mut order = Vec::with_capacity(permutation.len());
+    let visit = |h: usize, label: &mut Vec<Option<usize>>, order: &mut Vec<usize>| {
+        if label[h].is_none() {
+            label[h] = Some(order.len());
+            label[twin(h)] = Some(order.len() + 1);
+            order.push(h);
+            order.push(twin(h));
+        }
+    };
+    visit(start, &mut label, &mut order);
+    let mut i = 0;
+    while i < order.len() {
+        visit(permutation[order[i]], &mut label, &mut order);
+        i += 1;
+    }
+    order
+        .iter()
+        .map(|&h| label[permutation[h]].unwrap())
+        .collect()
+}
+
+/// Vertex id of the origin of each half-edge, in the numbering of [gather_vertices]
+#[cfg(feature = "std")]
+pub(crate) fn origins(permutation: &[usize]) -> Vec<usize> {
+    let mut origin = vec![0; permutation.len()];
+    for (v, orbit) in gather_vertices(permutation).iter().enumerate() {
+        for h in orbit.indices() {
+            origin[h] = v;
+        }
+    }
+    origin
+}
+
+pub(crate) fn twin(index: usize) -> usize {
+    if index.is_multiple_of(2) {
+        index + 1
+    } else {
+        index - 1
+    }
 }
 
+/// Vertices as the orbits of `h -> permutation[twin(h)]`, sorted by their smallest half-edge
+///
+/// The `i`-th orbit is vertex `i`, see [Numbering](self#numbering).
+/// Panics if the input is not a permutation on half-edges, see [try_gather_vertices].
+pub fn gather_vertices(permutation: &[usize]) -> Vec<Orbit> {
+    assert_eq!(permutation.len() % 2, 0);
+    // twin-next orbit
+    gather_orbits(permutation.len(), |h| permutation[twin(h)])
+}
+
+/// Faces as the orbits of the permutation, sorted by their smallest half-edge
+///
+/// The `i`-th orbit is face `i`, see [Numbering](self#numbering).
+/// Panics if the input is not a permutation on half-edges, see [try_gather_faces].
 pub fn gather_faces(permutation: &[usize]) -> Vec<Orbit> {
-    // XXX More check?
     assert_eq!(permutation.len() % 2, 0);
+    // next orbit
+    gather_orbits(permutation.len(), |h| permutation[h])
+}
 
-    // FIXME we can implement by more efficient algorithm
-    let mut vs: Vec<_> = (0..permutation.len())
-        .map(|init| {
-            // iterator over next orbit
-            let mut orbit = vec![init];
-            let mut current = init;
-            loop {
-                current = permutation[current];
-                if current == init {
-                    break;
-                }
-                orbit.push(current);
+/// [gather_vertices] returning [PermutationError] if the input is not a permutation on half-edges
+pub fn try_gather_vertices(permutation: &[usize]) -> Result<Vec<Orbit>, PermutationError> {
+    validate(permutation)?;
+    Ok(gather_vertices(permutation))
+}
+
+/// [gather_faces] returning [PermutationError] if the input is not a permutation on half-edges
+pub fn try_gather_faces(permutation: &[usize]) -> Result<Vec<Orbit>, PermutationError> {
+    validate(permutation)?;
+    Ok(gather_faces(permutation))
+}
+
+/// Orbits of `next` on `0..n` sorted by their minimum, in a single pass
+///
+/// Each orbit is traced once from its minimum, which is the first unvisited element.
+fn gather_orbits(n: usize, next: impl Fn(usize) -> usize) -> Vec<Orbit> {
+    gather_orbits_in(n, 0..n, next)
+}
+
+/// Orbits of `next` on `0..n` whose minimum is in `range`, sorted by their minimum
+///
+/// A trace stops at the first element below `range`, since its orbit is gathered from an earlier range.
+/// Otherwise the first unvisited element of `range` is the minimum of its orbit,
+/// so the orbits of consecutive ranges concatenate to those of `0..n`.
+pub(crate) fn gather_orbits_in(
+    n: usize,
+    range: core::ops::Range<usize>,
+    next: impl Fn(usize) -> usize,
+) -> Vec<Orbit> {
+    let mut visited = vec![false; range.len()];
+    let mut orbits = Vec::new();
+    let mut scratch = Vec::new();
+    'orbits: for init in range.clone() {
+        if visited[init - range.start] {
+            continue;
+        }
+        scratch.clear();
+        let mut current = init;
+        loop {
+            if range.contains(&current) {
+                visited[current - range.start] = true;
             }
-            Orbit::new(&orbit)
-        })
-        .collect();
-    vs.sort_unstable();
-    vs.dedup();
-    vs
+            scratch.push(current);
+            current = next(current);
+            if current == init {
+                break;
+            }
+            if current < range.start {
+                continue 'orbits;
+            }
+            assert!(scratch.len() < n, "Input is not a permutation");
+        }
+        orbits.push(Orbit::new_nonempty(&scratch));
+    }
+    orbits
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::shapes;
+
+    #[test]
+    fn orbit_new() {
+        let v = Orbit::try_new(&[2, 1, 3]).unwrap();
+        assert_eq!(v.indices().collect::<Vec<_>>(), [1, 3, 2]);
+        assert_eq!((v.len(), v.first()), (3, 1));
+
+        // stored on the heap
+        let edges: Vec<usize> = (0..20).map(|i| (i + 7) % 20).collect();
+        let v = Orbit::try_new(&edges).unwrap();
+        assert!(v.indices().eq(0..20));
+        assert!(Orbit::pair(1, 0) < v && v < Orbit::pair(0, 2));
+
+        assert_eq!(Orbit::try_new(&[]), Err(EmptyOrbit));
+        assert!(Orbit::singleton(3).indices().eq([3]));
+        assert!(Orbit::pair(5, 2).indices().eq([2, 5]));
+
+        // ids beyond u32 are stored on the heap
+        let large = usize::MAX - 1;
+        let v = Orbit::try_new(&[large, 4, 2]).unwrap();
+        assert!(v.indices().eq([2, large, 4]));
+        assert!(Orbit::pair(2, 4) < v && v < Orbit::singleton(3));
+        assert!(core::mem::size_of::<Orbit>() <= 40);
+    }
+
+    #[test]
+    fn orbits_nonempty() {
+        // bijections with fixed points, transpositions, and a single long cycle
+        let n = 24;
+        let identity: Vec<usize> = (0..n).collect();
+        let swapped: Vec<usize> = (0..n).map(|h| h ^ 1).collect();
+        let reversed: Vec<usize> = (0..n).rev().collect();
+        let cycle: Vec<usize> = (0..n).map(|h| (h + 7) % n).collect();
+        for permutation in &[identity, swapped, reversed, cycle] {
+            for orbits in &[gather_vertices(permutation), gather_faces(permutation)] {
+                assert!(orbits.iter().all(|o| o.indices().len() > 0));
+                let total: usize = orbits.iter().map(|o| o.len()).sum();
+                assert_eq!(total, n);
+            }
+        }
+        assert_eq!(
+            gather_faces(&(0..n).collect::<Vec<_>>())[3],
+            Orbit::singleton(3)
+        );
+        assert_eq!(
+            gather_vertices(&(0..n).collect::<Vec<_>>())[1],
+            Orbit::pair(2, 3)
+        );
+    }
+
+    #[test]
+    fn large_orbits() {
+        // two 20-gons glued along their boundary, and its dual with two vertices of valence 20
+        let faces = vec![(0..20).collect::<Vec<_>>(), (0..20).rev().collect()];
+        let permutation = from_faces(&faces).unwrap();
+        let orbits = gather_faces(&permutation);
+        assert_eq!(orbits.len(), 2);
+        assert!(orbits.iter().all(|o| o.len() == 20));
+        assert_eq!(gather_vertices(&dual(&permutation)), orbits);
+        assert_eq!(gather_vertices(&permutation).len(), 20);
+    }
+
+    #[test]
+    fn validate_permutation() {
+        assert_eq!(validate(&[5, 6, 0, 8, 10, 2, 11, 3, 7, 4, 9, 1]), Ok(()));
+        assert_eq!(validate(&[]), Ok(()));
+        assert_eq!(validate(&[0, 1, 2]), Err(PermutationError::OddLength(3)));
+        assert_eq!(
+            validate(&[0, 2]),
+            Err(PermutationError::OutOfRange { index: 1, value: 2 })
+        );
+        assert_eq!(
+            validate(&[1, 0, 0, 3]),
+            Err(PermutationError::NotBijective {
+                first: 1,
+                second: 2,
+                value: 0
+            })
+        );
+    }
+
+    #[test]
+    fn numbering() {
+        let tetrahedron = shapes::tetrahedron();
+        let ids = |orbits: Vec<Orbit>| -> Vec<Vec<usize>> {
+            orbits.iter().map(|o| o.indices().collect()).collect()
+        };
+        assert_eq!(
+            ids(gather_vertices(&tetrahedron)),
+            [[0, 6, 3], [1, 5, 10], [2, 8, 4], [7, 11, 9]]
+        );
+        assert_eq!(
+            ids(gather_faces(&tetrahedron)),
+            [[0, 5, 2], [1, 6, 11], [3, 8, 7], [4, 10, 9]]
+        );
+
+        // pendant edge 12 -> 13 inserted after the half-edge 0, and a second tetrahedron
+        let mut extended = tetrahedron.clone();
+        extended[0] = 12;
+        extended.extend_from_slice(&[13, tetrahedron[0]]);
+        extended.extend(tetrahedron.iter().map(|h| h + 14));
+        for (old, new) in [
+            (gather_vertices(&tetrahedron), gather_vertices(&extended)),
+            (gather_faces(&tetrahedron), gather_faces(&extended)),
+        ] {
+            for (old, new) in old.iter().zip(&new) {
+                assert_eq!(old.first(), new.first());
+                assert!(old.indices().all(|h| new.indices().any(|k| k == h)));
+            }
+            assert!(new[old.len()..].iter().all(|o| o.first() >= 12));
+        }
+
+        // rebuilding from the same faces gives the same ids
+        let faces = vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 2, 3], vec![0, 3, 2]];
+        assert_eq!(from_faces(&faces), from_faces(&faces));
+        let mesh = crate::Mesh::from_permutation(&from_faces(&faces).unwrap());
+        let rebuilt = crate::Mesh::from_permutation(&from_faces(&faces).unwrap());
+        for e in 0..mesh.num_edges() {
+            assert_eq!(mesh.edge_vertices(e), rebuilt.edge_vertices(e));
+            assert_eq!(mesh.edge_faces(e), rebuilt.edge_faces(e));
+        }
+    }
+
+    #[test]
+    fn gather_order() {
+        let permutation = shapes::random_triangulation(40, 1).unwrap();
+        for orbits in &[gather_vertices(&permutation), gather_faces(&permutation)] {
+            let mins: Vec<usize> = orbits.iter().map(|o| o.first()).collect();
+            assert!(mins.windows(2).all(|w| w[0] < w[1]));
+            assert!(orbits.iter().all(|o| o.indices().all(|h| h >= o.first())));
+            let total: usize = orbits.iter().map(|o| o.len()).sum();
+            assert_eq!(total, permutation.len());
+        }
+    }
+
+    #[test]
+    fn try_gather() {
+        let permutation = shapes::tetrahedron();
+        assert_eq!(
+            try_gather_vertices(&permutation).unwrap(),
+            gather_vertices(&permutation)
+        );
+        assert_eq!(
+            try_gather_faces(&permutation).unwrap(),
+            gather_faces(&permutation)
+        );
+        assert_eq!(
+            try_gather_vertices(&[1, 0, 2]),
+            Err(PermutationError::OddLength(3))
+        );
+        assert_eq!(
+            try_gather_faces(&[1, 1]),
+            Err(PermutationError::NotBijective {
+                first: 0,
+                second: 1,
+                value: 1
+            })
+        );
+    }
+
+    #[test]
+    fn orbit_display() {
+        assert_eq!(Orbit::try_new(&[5, 0, 3]).unwrap().to_string(), "(0 3 5)");
+        assert_eq!(Orbit::singleton(7).to_string(), "(7)");
+    }
+
+    #[test]
+    fn faces() {
+        // tetrahedron
+        let faces = vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 2, 3], vec![0, 3, 2]];
+        let permutation = from_faces(&faces).unwrap();
+        assert_eq!(validate(&permutation), Ok(()));
+        // edges numbered in the order of first appearance
+        assert_eq!(permutation, [2, 9, 4, 10, 0, 6, 8, 3, 5, 11, 7, 1]);
+        assert_eq!(gather_vertices(&permutation).len(), 4);
+        assert_eq!(gather_faces(&permutation).len(), 4);
+
+        assert_eq!(
+            from_faces(&faces[..3]),
+            Err(FaceListError::UnpairedEdge { from: 0, to: 2 })
+        );
+        assert_eq!(
+            from_faces(&[vec![0, 1, 2], vec![0, 1, 3]]),
+            Err(FaceListError::DuplicatedEdge { from: 0, to: 1 })
+        );
+        assert_eq!(
+            from_faces(&[vec![0, 1], vec![2]]),
+            Err(FaceListError::DegenerateFace(1))
+        );
+        // the smallest edge repeated in a face, both for small and large faces
+        assert_eq!(
+            from_faces(&[vec![3, 4, 3, 4, 1, 2, 1, 2]]),
+            Err(FaceListError::DuplicatedEdge { from: 1, to: 2 })
+        );
+        assert_eq!(
+            from_faces(&[(0..20).chain(0..20).collect()]),
+            Err(FaceListError::DuplicatedEdge { from: 0, to: 1 })
+        );
+    }
+
+    /// Construction of a torus of 10M faces, run with `--release -- --ignored --nocapture`
+    #[test]
+    #[ignore]
+    fn from_faces_large_torus() {
+        let faces = shapes::torus_faces(3163, 3162).unwrap();
+        assert!(faces.len() >= 10_000_000);
+        let start = std::time::Instant::now();
+        let permutation = from_face_iter(&faces).unwrap();
+        let mesh = crate::Mesh::from_permutation(&permutation);
+        println!("{} faces in {:?}", mesh.num_faces(), start.elapsed());
+        assert_eq!(mesh.genus(), Some(1));
+    }
+
+    #[test]
+    fn dual_permutation() {
+        let cube = [
+            22, 5, 1, 18, 20, 7, 16, 2, 12, 17, 8, 19, 15, 21, 23, 10, 11, 4, 14, 6, 9, 0, 13, 3,
+        ];
+        let octahedron = dual(&cube);
+        assert_eq!(validate(&octahedron), Ok(()));
+        assert_eq!(gather_vertices(&octahedron), gather_faces(&cube));
+        assert_eq!(gather_faces(&octahedron), gather_vertices(&cube));
+        assert_eq!(dual(&octahedron), cube);
+    }
+
+    #[test]
+    fn split() {
+        let tetrahedron = [5, 6, 0, 8, 10, 2, 11, 3, 7, 4, 9, 1];
+        let mut permutation = tetrahedron.to_vec();
+        permutation.extend(tetrahedron.iter().map(|h| h + 12));
+        assert_eq!(
+            split_components(&permutation),
+            vec![tetrahedron.to_vec(), tetrahedron.to_vec()]
+        );
+        assert_eq!(split_components(&tetrahedron), vec![tetrahedron.to_vec()]);
+        assert!(split_components(&[]).is_empty());
+    }
+
+    #[test]
+    fn isomorphic() {
+        let tetrahedron = [5, 6, 0, 8, 10, 2, 11, 3, 7, 4, 9, 1];
+        // swap edges 0 and 4, and the two half-edges of edge 1
+        let relabel = |h: usize| match h {
+            0 | 1 => h + 8,
+            8 | 9 => h - 8,
+            2 => 3,
+            3 => 2,
+            _ => h,
+        };
+        let mut relabeled = [0; 12];
+        for (h, &next) in tetrahedron.iter().enumerate() {
+            relabeled[relabel(h)] = relabel(next);
+        }
+        assert_eq!(validate(&relabeled), Ok(()));
+        assert_ne!(relabeled, tetrahedron);
+        assert!(is_isomorphic(&tetrahedron, &relabeled));
+        assert_eq!(canonical_form(&relabeled), canonical_form(&tetrahedron));
+
+        let two: Vec<usize> = tetrahedron
+            .iter()
+            .cloned()
+            .chain(tetrahedron.iter().map(|h| h + 12))
+            .collect();
+        let mixed: Vec<usize> = relabeled
+            .iter()
+            .cloned()
+            .chain(tetrahedron.iter().map(|h| h + 12))
+            .collect();
+        assert!(is_isomorphic(&two, &mixed));
+        assert!(!is_isomorphic(&two, &shapes::cube()));
+        assert!(!is_isomorphic(&[1, 0, 3, 2], &[2, 3, 0, 1]));
+    }
+
+    #[test]
+    fn isomorphic_large() {
+        // relabel the edges of an icosphere of 15360 half-edges by a shuffle, and flip some twins
+        let icosphere = shapes::icosphere(4).unwrap();
+        let num_edges = icosphere.len() / 2;
+        let mut edges: Vec<usize> = (0..num_edges).collect();
+        let mut state = 1u64;
+        for i in (1..num_edges).rev() {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1);
+            edges.swap(i, (state >> 33) as usize % (i + 1));
+        }
+        let relabel =
+            |h: usize| 2 * edges[h / 2] + ((h % 2) ^ edges[h / 2].is_multiple_of(3) as usize);
+        let mut relabeled = vec![0; icosphere.len()];
+        for (h, &next) in icosphere.iter().enumerate() {
+            relabeled[relabel(h)] = relabel(next);
+        }
+        assert_eq!(validate(&relabeled), Ok(()));
+        assert!(is_isomorphic(&icosphere, &relabeled));
+        assert_eq!(canonical_form(&icosphere), canonical_form(&relabeled));
+
+        // same counts and invariants, but another surface
+        let torus = shapes::torus(16, 8).unwrap();
+        assert!(!is_isomorphic(&torus, &shapes::torus(32, 4).unwrap()));
+        assert_ne!(
+            canonical_form(&torus),
+            canonical_form(&shapes::torus(32, 4).unwrap())
+        );
+    }
+
+    #[test]
+    fn is_dual_up_to_isomorphism() {
+        let cube = shapes::cube();
+        let octahedron = dual(&cube);
+        assert!(is_dual(&cube, &octahedron));
+        assert!(is_dual(&octahedron, &cube));
+        assert!(is_dual(&octahedron, &canonical_form(&cube)));
+        // the cube is not self-dual, while the tetrahedron is
+        assert!(!is_dual(&cube, &cube));
+        assert!(!is_dual(&cube, &shapes::tetrahedron()));
+        let tetrahedron = shapes::tetrahedron();
+        assert!(is_dual(&tetrahedron, &canonical_form(&tetrahedron)));
+    }
 }
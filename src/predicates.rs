@@ -0,0 +1,276 @@
+/*!
+Exact orientation and in-circle tests for points in the plane
+
+Both predicates first evaluate the determinant in floating point with the error bound
+of Shewchuk's [Adaptive Precision Floating-Point Arithmetic][Shewchuk], and only if the sign
+is not certain, evaluate it exactly with floating-point expansions.
+The sign of the result is always exact, e.g. for points collinear to within an ulp,
+where the naive determinant may have either sign:
+
+```
+use umesh::predicates::orient2d;
+
+let a = [0.5, 0.5];
+let b = [12.0, 12.0];
+let c = [24.0, 24.0];
+assert_eq!(orient2d(a, b, c), 0.0);
+// one ulp above the line
+let a = [0.5, f64::from_bits(0.5_f64.to_bits() + 1)];
+assert!(orient2d(a, b, c) > 0.0);
+```
+
+[Shewchuk]: https://www.cs.cmu.edu/~quake/robust.html
+*/
+
+/// Half of the ulp of 1.0, i.e. the relative error of a rounded operation
+const EPSILON: f64 = f64::EPSILON / 2.0;
+const ORIENT_BOUND: f64 = (3.0 + 16.0 * EPSILON) * EPSILON;
+const INCIRCLE_BOUND: f64 = (10.0 + 96.0 * EPSILON) * EPSILON;
+/// `2^27 + 1` splitting a double into two halves of 26 bits
+const SPLITTER: f64 = 134_217_729.0;
+
+/// Twice the signed area of the triangle `a`, `b`, `c`
+///
+/// Positive if the points are in counterclockwise order, negative if clockwise,
+/// and zero if they are collinear. The sign is exact, the magnitude is approximate.
+pub fn orient2d(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    let left = (a[0] - c[0]) * (b[1] - c[1]);
+    let right = (a[1] - c[1]) * (b[0] - c[0]);
+    let det = left - right;
+    if det.abs() >= ORIENT_BOUND * (left.abs() + right.abs()) {
+        return det;
+    }
+
+    let acx = two_diff(a[0], c[0]);
+    let bcy = two_diff(b[1], c[1]);
+    let acy = two_diff(a[1], c[1]);
+    let bcx = two_diff(b[0], c[0]);
+    estimate(&sum(&product(&acx, &bcy), &negate(product(&acy, &bcx))))
+}
+
+/// Whether `d` is inside the circle through `a`, `b`, `c`
+///
+/// Positive if `d` is inside and `a`, `b`, `c` are in counterclockwise order,
+/// negative if `d` is outside, and zero if the four points are on a circle.
+/// The sign is reversed for clockwise `a`, `b`, `c`. The sign is exact, the magnitude is approximate.
+pub fn incircle(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> f64 {
+    let [adx, ady] = [a[0] - d[0], a[1] - d[1]];
+    let [bdx, bdy] = [b[0] - d[0], b[1] - d[1]];
+    let [cdx, cdy] = [c[0] - d[0], c[1] - d[1]];
+    let alift = adx * adx + ady * ady;
+    let blift = bdx * bdx + bdy * bdy;
+    let clift = cdx * cdx + cdy * cdy;
+    let (bc, cb) = (bdx * cdy, cdx * bdy);
+    let (ca, ac) = (cdx * ady, adx * cdy);
+    let (ab, ba) = (adx * bdy, bdx * ady);
+    let det = alift * (bc - cb) + blift * (ca - ac) + clift * (ab - ba);
+    let permanent = (bc.abs() + cb.abs()) * alift
+        + (ca.abs() + ac.abs()) * blift
+        + (ab.abs() + ba.abs()) * clift;
+    if det.abs() >= INCIRCLE_BOUND * permanent {
+        return det;
+    }
+
+    let [adx, ady] = [two_diff(a[0], d[0]), two_diff(a[1], d[1])];
+    let [bdx, bdy] = [two_diff(b[0], d[0]), two_diff(b[1], d[1])];
+    let [cdx, cdy] = [two_diff(c[0], d[0]), two_diff(c[1], d[1])];
+    let lift = |x: &[f64], y: &[f64]| sum(&product(x, x), &product(y, y));
+    let cross = |x1: &[f64], y2: &[f64], x2: &[f64], y1: &[f64]| {
+        sum(&product(x1, y2), &negate(product(x2, y1)))
+    };
+    let a = product(&lift(&adx, &ady), &cross(&bdx, &cdy, &cdx, &bdy));
+    let b = product(&lift(&bdx, &bdy), &cross(&cdx, &ady, &adx, &cdy));
+    let c = product(&lift(&cdx, &cdy), &cross(&adx, &bdy, &bdx, &ady));
+    estimate(&sum(&sum(&a, &b), &c))
+}
+
+// Expansions are sums of non-overlapping doubles in increasing magnitude without zeros,
+// see Shewchuk §2.
+
+/// `a + b` as `(rounded, error)`
+fn two_sum(a: f64, b: f64) -> (f64, f64) {
+    let x = a + b;
+    let bv = x - a;
+    let av = x - bv;
+    (x, (a - av) + (b - bv))
+}
+
+/// `a - b` as an expansion
+fn two_diff(a: f64, b: f64) -> Vec<f64> {
+    let (x, y) = two_sum(a, -b);
+    compress(vec![y, x])
+}
+
+fn split(a: f64) -> (f64, f64) {
+    let c = SPLITTER * a;
+    let high = c - (c - a);
+    (high, a - high)
+}
+
+/// `a * b` as `(rounded, error)`
+fn two_product(a: f64, b: f64) -> (f64, f64) {
+    let x = a * b;
+    let (ah, al) = split(a);
+    let (bh, bl) = split(b);
+    let error = x - ah * bh - al * bh - ah * bl;
+    (x, al * bl - error)
+}
+
+fn compress(e: Vec<f64>) -> Vec<f64> {
+    e.into_iter().filter(|&x| x != 0.0).collect()
+}
+
+/// `e + b`
+fn grow(e: &[f64], b: f64) -> Vec<f64> {
+    let mut h = Vec::with_capacity(e.len() + 1);
+    let mut q = b;
+    for &x in e {
+        let (sum, error) = two_sum(q, x);
+        if error != 0.0 {
+            h.push(error);
+        }
+        q = sum;
+    }
+    if q != 0.0 {
+        h.push(q);
+    }
+    h
+}
+
+/// `e + f`
+fn sum(e: &[f64], f: &[f64]) -> Vec<f64> {
+    f.iter().fold(e.to_vec(), |h, &x| grow(&h, x))
+}
+
+/// `e * b`
+fn scale(e: &[f64], b: f64) -> Vec<f64> {
+    e.iter().fold(Vec::new(), |h, &x| {
+        let (product, error) = two_product(x, b);
+        grow(&grow(&h, error), product)
+    })
+}
+
+/// `e * f`
+fn product(e: &[f64], f: &[f64]) -> Vec<f64> {
+    f.iter().fold(Vec::new(), |h, &x| sum(&h, &scale(e, x)))
+}
+
+fn negate(mut e: Vec<f64>) -> Vec<f64> {
+    for x in &mut e {
+        *x = -*x;
+    }
+    e
+}
+
+/// Approximation with the exact sign, since the largest component dominates the others
+fn estimate(e: &[f64]) -> f64 {
+    e.iter().sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn naive_orient2d(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+        (a[0] - c[0]) * (b[1] - c[1]) - (a[1] - c[1]) * (b[0] - c[0])
+    }
+
+    fn naive_incircle(a: [f64; 2], b: [f64; 2], c: [f64; 2], d: [f64; 2]) -> f64 {
+        let [adx, ady] = [a[0] - d[0], a[1] - d[1]];
+        let [bdx, bdy] = [b[0] - d[0], b[1] - d[1]];
+        let [cdx, cdy] = [c[0] - d[0], c[1] - d[1]];
+        (adx * adx + ady * ady) * (bdx * cdy - cdx * bdy)
+            + (bdx * bdx + bdy * bdy) * (cdx * ady - adx * cdy)
+            + (cdx * cdx + cdy * cdy) * (adx * bdy - bdx * ady)
+    }
+
+    fn ulps(x: f64, n: i64) -> f64 {
+        f64::from_bits((x.to_bits() as i64 + n) as u64)
+    }
+
+    fn sign(x: f64) -> i128 {
+        if x == 0.0 {
+            0
+        } else {
+            x.signum() as i128
+        }
+    }
+
+    /// Exact orientation of points on the grid of 2^-53, by integers
+    fn exact_orient2d(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> i128 {
+        let int = |x: f64| {
+            let scaled = x * (1u64 << 53) as f64;
+            assert_eq!(scaled.fract(), 0.0);
+            scaled as i128
+        };
+        let [ax, ay, bx, by, cx, cy] = [a[0], a[1], b[0], b[1], c[0], c[1]].map(int);
+        ((ax - cx) * (by - cy) - (ay - cy) * (bx - cx)).signum()
+    }
+
+    #[test]
+    fn orient2d_near_collinear() {
+        // Kettner et al., Classroom examples of robustness problems in geometric computations
+        let b = [12.0, 12.0];
+        let c = [24.0, 24.0];
+        let mut misclassified = 0;
+        for i in 0..64 {
+            for j in 0..64 {
+                let a = [ulps(0.5, i), ulps(0.5, j)];
+                let exact = exact_orient2d(a, b, c);
+                assert_eq!(sign(orient2d(a, b, c)), exact);
+                if sign(naive_orient2d(a, b, c)) != exact {
+                    misclassified += 1;
+                }
+            }
+        }
+        assert!(misclassified > 0);
+    }
+
+    #[test]
+    fn orient2d_simple() {
+        assert_eq!(orient2d([0.0, 0.0], [1.0, 0.0], [0.0, 1.0]), 1.0);
+        assert_eq!(orient2d([0.0, 0.0], [0.0, 1.0], [1.0, 0.0]), -1.0);
+        assert_eq!(orient2d([0.1, 0.1], [0.1, 0.1], [0.3, 0.7]), 0.0);
+    }
+
+    #[test]
+    fn incircle_near_cocircular() {
+        // a, b, c, d on a circle centered at the origin by symmetry,
+        // and d moved by an ulp outwards or inwards
+        let mut state = 1_u64;
+        let mut misclassified = 0;
+        for _ in 0..1000 {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            let x = 0.5 + (state >> 11) as f64 / (1u64 << 53) as f64;
+            let y = 1.0 + (state >> 13) as f64 / (1u64 << 51) as f64;
+            let (a, b, c) = ([x, y], [-x, y], [-x, -y]);
+            let on = [y, x];
+            let outside = [ulps(y, 1), x];
+            let inside = [ulps(y, -1), x];
+            assert_eq!(incircle(a, b, c, on), 0.0);
+            assert!(incircle(a, b, c, outside) < 0.0);
+            assert!(incircle(a, b, c, inside) > 0.0);
+            assert!(incircle(c, b, a, inside) < 0.0);
+            if naive_incircle(a, b, c, on) != 0.0
+                || naive_incircle(a, b, c, outside) >= 0.0
+                || naive_incircle(a, b, c, inside) <= 0.0
+            {
+                misclassified += 1;
+            }
+        }
+        assert!(misclassified > 0);
+    }
+
+    #[test]
+    fn expansions() {
+        let e = two_diff(1e20, -1.0);
+        assert_eq!(e, [1.0, 1e20]);
+        let p = product(&e, &e);
+        // (1e20 + 1)^2 = 1e40 + 2e20 + 1
+        assert_eq!(estimate(&p), 1e40);
+        assert_eq!(p[0], 1.0);
+        assert!(sum(&p, &negate(p.clone())).is_empty());
+    }
+}
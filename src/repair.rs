@@ -0,0 +1,305 @@
+/*!
+Repair of a list of faces into a closed surface
+
+The stages run in a fixed order, and each of them is optional:
+
+1. `remove_degenerate`: collapse repeated consecutive vertices of a face,
+   and remove faces with less than three vertices and duplicates of a face in either orientation
+2. `fix_orientation`: flip faces so that neighboring faces traverse their shared edge
+   in opposite directions, growing from the first face of each connected part
+3. `fill_holes`: close each boundary loop of at most the given number of edges by a new face
+
+```
+use umesh::repair::*;
+
+// tetrahedron with a flipped face and a missing face
+let faces = vec![vec![0, 2, 1], vec![3, 1, 0], vec![1, 2, 3]];
+let options = RepairOptions { fix_orientation: true, fill_holes: Some(3), ..Default::default() };
+let (faces, reports) = repair(&faces, &options);
+assert_eq!(reports[0].to_string(), "fix_orientation: 1 changes");
+assert_eq!(reports[1].to_string(), "fill_holes: 1 changes");
+assert!(umesh::from_faces(&faces).is_ok());
+```
+*/
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// Stages to run, see the module document for their order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairOptions {
+    pub remove_degenerate: bool,
+    pub fix_orientation: bool,
+    /// Maximal number of edges of a hole to be filled
+    pub fill_holes: Option<usize>,
+}
+
+/// Changes made by a stage, and the problems it could not resolve
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairReport {
+    pub stage: &'static str,
+    pub changes: usize,
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} changes", self.stage, self.changes)?;
+        for problem in &self.problems {
+            write!(f, "\n    {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the enabled stages, and returns the repaired faces with a report per stage
+pub fn repair(
+    faces: &[Vec<usize>],
+    options: &RepairOptions,
+) -> (Vec<Vec<usize>>, Vec<RepairReport>) {
+    let mut faces = faces.to_vec();
+    let mut reports = Vec::new();
+    if options.remove_degenerate {
+        reports.push(remove_degenerate(&mut faces));
+    }
+    if options.fix_orientation {
+        reports.push(fix_orientation(&mut faces));
+    }
+    if let Some(max_edges) = options.fill_holes {
+        reports.push(fill_holes(&mut faces, max_edges));
+    }
+    (faces, reports)
+}
+
+fn remove_degenerate(faces: &mut Vec<Vec<usize>>) -> RepairReport {
+    let mut changes = 0;
+    let mut seen = HashSet::new();
+    let mut repaired = Vec::with_capacity(faces.len());
+    for face in faces.iter() {
+        let mut collapsed = face.clone();
+        collapsed.dedup();
+        while collapsed.len() > 1 && collapsed.first() == collapsed.last() {
+            collapsed.pop();
+        }
+        if collapsed.len() != face.len() {
+            changes += 1;
+        }
+        if collapsed.len() < 3 || !seen.insert(canonical(&collapsed)) {
+            if collapsed.len() == face.len() {
+                changes += 1;
+            }
+            continue;
+        }
+        repaired.push(collapsed);
+    }
+    *faces = repaired;
+    RepairReport {
+        stage: "remove_degenerate",
+        changes,
+        problems: Vec::new(),
+    }
+}
+
+/// Rotation starting from the smallest vertex in the smaller direction
+fn canonical(face: &[usize]) -> Vec<usize> {
+    let start = (0..face.len()).min_by_key(|&i| face[i]).unwrap();
+    let k = face.len();
+    let forward: Vec<usize> = (0..k).map(|i| face[(start + i) % k]).collect();
+    let backward: Vec<usize> = (0..k).map(|i| face[(start + k - i) % k]).collect();
+    forward.min(backward)
+}
+
+fn directed_edges(face: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..face.len()).map(move |i| (face[i], face[(i + 1) % face.len()]))
+}
+
+fn fix_orientation(faces: &mut [Vec<usize>]) -> RepairReport {
+    // faces of each undirected edge, with whether the face goes from the smaller vertex
+    let mut edges: HashMap<(usize, usize), Vec<(usize, bool)>> = HashMap::new();
+    for (f, face) in faces.iter().enumerate() {
+        for (a, b) in directed_edges(face) {
+            edges
+                .entry((a.min(b), a.max(b)))
+                .or_default()
+                .push((f, a < b));
+        }
+    }
+
+    let mut flipped: Vec<Option<bool>> = vec![None; faces.len()];
+    let mut conflicts = BTreeSet::new();
+    for root in 0..faces.len() {
+        if flipped[root].is_some() {
+            continue;
+        }
+        flipped[root] = Some(false);
+        let mut queue = VecDeque::new();
+        queue.push_back(root);
+        while let Some(f) = queue.pop_front() {
+            for (a, b) in directed_edges(&faces[f]) {
+                let forward = (a < b) != flipped[f].unwrap();
+                for &(g, g_forward) in &edges[&(a.min(b), a.max(b))] {
+                    if g == f {
+                        continue;
+                    }
+                    // g must traverse the edge in the opposite direction
+                    let flip = g_forward == forward;
+                    match flipped[g] {
+                        None => {
+                            flipped[g] = Some(flip);
+                            queue.push_back(g);
+                        }
+                        Some(current) if current != flip => {
+                            conflicts.insert((a.min(b), a.max(b)));
+                        }
+                        Some(_) => {}
+                    }
+                }
+            }
+        }
+    }
+
+    let mut changes = 0;
+    for (face, flipped) in faces.iter_mut().zip(flipped) {
+        if flipped == Some(true) {
+            face.reverse();
+            changes += 1;
+        }
+    }
+    RepairReport {
+        stage: "fix_orientation",
+        changes,
+        problems: conflicts
+            .iter()
+            .map(|(a, b)| format!("edge {}-{} cannot be oriented consistently", a, b))
+            .collect(),
+    }
+}
+
+fn fill_holes(faces: &mut Vec<Vec<usize>>, max_edges: usize) -> RepairReport {
+    let directed: HashSet<(usize, usize)> = faces.iter().flat_map(|f| directed_edges(f)).collect();
+    // boundary edges going out of each vertex
+    let mut boundary: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for &(a, b) in &directed {
+        if !directed.contains(&(b, a)) {
+            boundary.entry(a).or_default().push(b);
+        }
+    }
+    for targets in boundary.values_mut() {
+        targets.sort_unstable();
+    }
+
+    let mut changes = 0;
+    let mut problems = Vec::new();
+    while let Some(&start) = boundary.keys().next() {
+        let mut hole = vec![start];
+        let mut v = start;
+        let closed = loop {
+            let targets = boundary.get_mut(&v).unwrap();
+            let next = targets.remove(0);
+            if targets.is_empty() {
+                boundary.remove(&v);
+            }
+            if next == start {
+                break true;
+            }
+            hole.push(next);
+            v = next;
+            if !boundary.contains_key(&v) {
+                break false;
+            }
+        };
+        if !closed {
+            problems.push(format!("boundary from vertex {} is not a loop", start));
+            continue;
+        }
+        if hole.len() > max_edges {
+            problems.push(format!("hole with {} edges is not filled", hole.len()));
+            continue;
+        }
+        hole.reverse();
+        faces.push(hole);
+        changes += 1;
+    }
+    RepairReport {
+        stage: "fill_holes",
+        changes,
+        problems,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::permutation::from_faces;
+
+    fn cube() -> Vec<Vec<usize>> {
+        vec![
+            vec![0, 3, 2, 1],
+            vec![4, 5, 6, 7],
+            vec![0, 1, 5, 4],
+            vec![1, 2, 6, 5],
+            vec![2, 3, 7, 6],
+            vec![3, 0, 4, 7],
+        ]
+    }
+
+    #[test]
+    fn degenerate() {
+        let mut faces = cube();
+        faces.push(vec![0, 0, 1]);
+        faces.push(vec![5, 4, 0, 1]);
+        faces[1] = vec![4, 5, 5, 6, 7, 4];
+        let (faces, reports) = repair(
+            &faces,
+            &RepairOptions {
+                remove_degenerate: true,
+                ..Default::default()
+            },
+        );
+        assert_eq!(faces, cube());
+        assert_eq!(reports[0].changes, 3);
+    }
+
+    #[test]
+    fn orientation() {
+        let mut faces = cube();
+        faces[2].reverse();
+        faces[4].reverse();
+        let options = RepairOptions {
+            fix_orientation: true,
+            ..Default::default()
+        };
+        let (repaired, reports) = repair(&faces, &options);
+        assert_eq!(reports[0].changes, 2);
+        assert!(reports[0].problems.is_empty());
+        assert!(from_faces(&repaired).is_ok());
+
+        // Möbius strip of three quads
+        let strip = vec![vec![0, 1, 4, 3], vec![1, 2, 5, 4], vec![2, 3, 0, 5]];
+        let (_, reports) = repair(&strip, &options);
+        assert!(!reports[0].problems.is_empty());
+    }
+
+    #[test]
+    fn holes() {
+        let faces: Vec<_> = cube().into_iter().skip(1).collect();
+        let (_, reports) = repair(
+            &faces,
+            &RepairOptions {
+                fill_holes: Some(3),
+                ..Default::default()
+            },
+        );
+        assert_eq!(reports[0].changes, 0);
+        assert_eq!(reports[0].problems, vec!["hole with 4 edges is not filled"]);
+
+        let (repaired, _) = repair(
+            &faces,
+            &RepairOptions {
+                fill_holes: Some(4),
+                ..Default::default()
+            },
+        );
+        assert!(from_faces(&repaired).is_ok());
+    }
+}
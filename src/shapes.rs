@@ -0,0 +1,258 @@
+/*!
+Built-in closed surfaces as permutations on half-edges, and an open grid as faces
+
+```
+use umesh::{shapes, Mesh};
+
+let torus = Mesh::from_permutation(&shapes::torus(8, 4).unwrap());
+assert_eq!(torus.genus(), Some(1));
+```
+*/
+
+use crate::permutation::{from_face_iter, from_faces};
+use crate::subdivision::{subdivide, Scheme};
+use std::fmt;
+
+/// Parameter of a shape is out of its valid range
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShapeError {
+    pub parameter: &'static str,
+    pub value: usize,
+    /// Requirement on the value, e.g. `must be at least 3`
+    pub requirement: String,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, but got {}",
+            self.parameter, self.requirement, self.value
+        )
+    }
+}
+
+impl std::error::Error for ShapeError {}
+
+/// Tetrahedron with 4 vertices, 6 edges and 4 faces
+pub fn tetrahedron() -> Vec<usize> {
+    vec![5, 6, 0, 8, 10, 2, 11, 3, 7, 4, 9, 1]
+}
+
+/// Cube with 8 vertices, 12 edges and 6 faces
+pub fn cube() -> Vec<usize> {
+    vec![
+        22, 5, 1, 18, 20, 7, 16, 2, 12, 17, 8, 19, 15, 21, 23, 10, 11, 4, 14, 6, 9, 0, 13, 3,
+    ]
+}
+
+/// Icosahedron with 12 vertices, 30 edges and 20 faces
+pub fn icosahedron() -> Vec<usize> {
+    vec![
+        5, 22, 0, 8, 10, 2, 25, 3, 7, 16, 13, 32, 15, 4, 46, 17, 18, 12, 9, 56, 35, 1, 21, 26, 36,
+        27, 28, 6, 23, 42, 11, 48, 30, 20, 40, 33, 39, 19, 45, 24, 51, 29, 41, 38, 59, 43, 55, 31,
+        47, 50, 52, 34, 49, 44, 58, 14, 37, 54, 57, 53,
+    ]
+}
+
+/// Largest `subdiv` of [icosphere], giving 1,310,720 faces
+pub const MAX_SUBDIV: usize = 8;
+
+/// Icosahedron whose triangles are split into four `subdiv` times
+///
+/// The number of faces is `20 * 4^subdiv`, so `subdiv` is at most [MAX_SUBDIV].
+pub fn icosphere(subdiv: usize) -> Result<Vec<usize>, ShapeError> {
+    at_most("subdiv", subdiv, MAX_SUBDIV)?;
+    let mut permutation = icosahedron();
+    for _ in 0..subdiv {
+        permutation =
+            subdivide(&permutation, Scheme::Loop).expect("Icosahedron is a triangle mesh");
+    }
+    Ok(permutation)
+}
+
+/// Quad torus with `major` quads around the hole and `minor` quads around the tube
+pub fn torus(major: usize, minor: usize) -> Result<Vec<usize>, ShapeError> {
+    at_least("major", major, 3)?;
+    at_least("minor", minor, 3)?;
+    Ok(from_face_iter(torus_quads(major, minor)).expect("Torus is closed"))
+}
+
+/// Faces of [torus] as vertex lists in the counter-clockwise order
+pub fn torus_faces(major: usize, minor: usize) -> Result<Vec<[usize; 4]>, ShapeError> {
+    at_least("major", major, 3)?;
+    at_least("minor", minor, 3)?;
+    Ok(torus_quads(major, minor).collect())
+}
+
+fn torus_quads(major: usize, minor: usize) -> impl Iterator<Item = [usize; 4]> {
+    let vertex = move |i: usize, j: usize| (i % major) * minor + j % minor;
+    (0..major * minor).map(move |k| {
+        let (i, j) = (k / minor, k % minor);
+        [
+            vertex(i, j),
+            vertex(i + 1, j),
+            vertex(i + 1, j + 1),
+            vertex(i, j + 1),
+        ]
+    })
+}
+
+/// Disk of `nx` x `ny` quads as vertex lists in the counter-clockwise order
+///
+/// The `(nx + 1) * (ny + 1)` vertices are numbered row by row.
+/// A permutation cannot hold the boundary, so build the mesh by [crate::builder::MeshBuilder].
+pub fn grid_faces(nx: usize, ny: usize) -> Result<Vec<[usize; 4]>, ShapeError> {
+    at_least("nx", nx, 1)?;
+    at_least("ny", ny, 1)?;
+    let vertex = |i: usize, j: usize| i + (nx + 1) * j;
+    Ok((0..nx * ny)
+        .map(|k| {
+            let (i, j) = (k % nx, k / nx);
+            [
+                vertex(i, j),
+                vertex(i + 1, j),
+                vertex(i + 1, j + 1),
+                vertex(i, j + 1),
+            ]
+        })
+        .collect())
+}
+
+/// Random triangulation of a sphere with `faces` triangles
+///
+/// Starting from a tetrahedron, a new vertex is inserted into a random face
+/// until the number of faces is reached, i.e. a random stacked triangulation.
+/// The result is determined by `seed`.
+pub fn random_triangulation(faces: usize, seed: u64) -> Result<Vec<usize>, ShapeError> {
+    at_least("faces", faces, 4)?;
+    if !faces.is_multiple_of(2) {
+        return Err(ShapeError {
+            parameter: "faces",
+            value: faces,
+            requirement: "must be even".to_string(),
+        });
+    }
+    let mut rng = XorShift::new(seed);
+    let mut triangles = vec![vec![0, 2, 1], vec![0, 1, 3], vec![1, 2, 3], vec![0, 3, 2]];
+    let mut num_vertices = 4;
+    while triangles.len() < faces {
+        let f = rng.next_below(triangles.len());
+        let (a, b, c) = (triangles[f][0], triangles[f][1], triangles[f][2]);
+        let v = num_vertices;
+        num_vertices += 1;
+        triangles[f] = vec![a, b, v];
+        triangles.push(vec![b, c, v]);
+        triangles.push(vec![c, a, v]);
+    }
+    Ok(from_faces(&triangles).expect("Stacked triangulation is closed"))
+}
+
+fn at_least(parameter: &'static str, value: usize, min: usize) -> Result<(), ShapeError> {
+    if value < min {
+        return Err(ShapeError {
+            parameter,
+            value,
+            requirement: format!("must be at least {}", min),
+        });
+    }
+    Ok(())
+}
+
+fn at_most(parameter: &'static str, value: usize, max: usize) -> Result<(), ShapeError> {
+    if value > max {
+        return Err(ShapeError {
+            parameter,
+            value,
+            requirement: format!("must be at most {}", max),
+        });
+    }
+    Ok(())
+}
+
+/// xorshift64* generator, enough for reproducible test data
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // the state must not be zero
+        XorShift(seed ^ 0x9e37_79b9_7f4a_7c15)
+    }
+
+    fn next_below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        (self.0.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 32) as usize % n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{half_edge::Mesh, permutation::validate};
+
+    fn assert_surface(permutation: &[usize], (v, e, f): (usize, usize, usize), genus: usize) {
+        assert_eq!(validate(permutation), Ok(()));
+        let mesh = Mesh::from_permutation(permutation);
+        assert_eq!(
+            (mesh.num_vertices(), mesh.num_edges(), mesh.num_faces()),
+            (v, e, f)
+        );
+        assert_eq!(mesh.num_components(), 1);
+        assert_eq!(mesh.genus(), Some(genus));
+    }
+
+    #[test]
+    fn platonic() {
+        assert_surface(&tetrahedron(), (4, 6, 4), 0);
+        assert_surface(&cube(), (8, 12, 6), 0);
+        assert_surface(&icosahedron(), (12, 30, 20), 0);
+    }
+
+    #[test]
+    fn icosphere_counts() {
+        assert_surface(&icosphere(0).unwrap(), (12, 30, 20), 0);
+        assert_surface(&icosphere(2).unwrap(), (162, 480, 320), 0);
+        assert_eq!(
+            icosphere(MAX_SUBDIV + 1).unwrap_err().to_string(),
+            "subdiv must be at most 8, but got 9"
+        );
+    }
+
+    #[test]
+    fn torus_counts() {
+        assert_surface(&torus(5, 3).unwrap(), (15, 30, 15), 1);
+        let faces = torus_faces(5, 3).unwrap();
+        assert_eq!(from_face_iter(&faces).unwrap(), torus(5, 3).unwrap());
+        assert_eq!(
+            torus(5, 2).unwrap_err().to_string(),
+            "minor must be at least 3, but got 2"
+        );
+    }
+
+    #[test]
+    fn grid() {
+        let faces: Vec<Vec<usize>> = grid_faces(2, 2)
+            .unwrap()
+            .iter()
+            .map(|face| face.to_vec())
+            .collect();
+        assert_eq!(Some(faces), crate::fixtures::OPEN_PATCH.faces());
+        assert_eq!(grid_faces(3, 1).unwrap().len(), 3);
+        assert_eq!(
+            grid_faces(0, 2).unwrap_err().to_string(),
+            "nx must be at least 1, but got 0"
+        );
+    }
+
+    #[test]
+    fn random() {
+        let permutation = random_triangulation(30, 7).unwrap();
+        assert_surface(&permutation, (17, 45, 30), 0);
+        assert_eq!(permutation, random_triangulation(30, 7).unwrap());
+        assert_ne!(permutation, random_triangulation(30, 8).unwrap());
+        assert!(random_triangulation(5, 0).is_err());
+        assert!(random_triangulation(2, 0).is_err());
+    }
+}
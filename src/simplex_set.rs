@@ -0,0 +1,328 @@
+/*!
+Set of simplex ids of one dimension, used by [crate::Simplices]
+
+A selection covering a small part of the mesh is kept in a [BTreeSet],
+and one covering a large part in a bitset sized to the number of simplices.
+Operations choose the representation of their result by its density,
+and every operation gives the same set regardless of the representations of the inputs.
+
+```
+# #[cfg(feature = "std")] {
+use umesh::{shapes, Mesh};
+
+let mesh = Mesh::from_permutation(&shapes::torus(20, 20).unwrap());
+let all = mesh.all_simplices();
+assert!(all.faces().is_dense());
+
+let region = mesh.simplicies(&[0], &[], &[]).star();
+let rest = all - region.clone();
+assert!(rest.faces().is_dense());
+assert!(!region.faces().is_dense());
+assert_eq!(rest.faces().len() + region.faces().len(), mesh.num_faces());
+# }
+```
+*/
+
+#[cfg(not(feature = "std"))]
+use crate::prelude::*;
+use alloc::collections::BTreeSet;
+use core::fmt;
+
+/// A set is stored as a bitset if it contains at least `1 / DENSITY` of all the simplices
+///
+/// A bitset takes a bit per simplex, while a [BTreeSet] takes more than eight bytes per id.
+const DENSITY: usize = 32;
+
+/// Ids of simplices of one dimension in ascending order
+#[derive(Clone, Default)]
+pub struct SimplexSet {
+    repr: Repr,
+}
+
+#[derive(Clone)]
+enum Repr {
+    Sparse(BTreeSet<usize>),
+    Dense(BitSet),
+}
+
+impl Default for Repr {
+    fn default() -> Self {
+        Repr::Sparse(BTreeSet::new())
+    }
+}
+
+impl SimplexSet {
+    /// Set of ids in any order with duplicates, among `universe` simplices
+    pub(crate) fn from_ids(mut ids: Vec<usize>, universe: usize) -> Self {
+        if ids.len() * DENSITY >= universe && !ids.is_empty() {
+            return SimplexSet {
+                repr: Repr::Dense(BitSet::from_ids(&ids, universe)),
+            };
+        }
+        ids.sort_unstable();
+        ids.dedup();
+        SimplexSet {
+            repr: Repr::Sparse(ids.into_iter().collect()),
+        }
+    }
+
+    /// The same set stored as a bitset over at least `universe` simplices
+    pub(crate) fn to_dense(&self, universe: usize) -> Self {
+        match &self.repr {
+            Repr::Dense(_) => self.clone(),
+            Repr::Sparse(set) => {
+                let ids: Vec<usize> = set.iter().cloned().collect();
+                SimplexSet {
+                    repr: Repr::Dense(BitSet::from_ids(&ids, universe)),
+                }
+            }
+        }
+    }
+
+    /// Result of a word-wise operation, which becomes sparse again if few bits are left
+    fn from_bits(bits: BitSet) -> Self {
+        let dense = SimplexSet {
+            repr: Repr::Dense(bits),
+        };
+        match &dense.repr {
+            Repr::Dense(bits) if bits.len == 0 || bits.len * DENSITY < bits.universe() => {
+                SimplexSet {
+                    repr: Repr::Sparse(dense.iter().collect()),
+                }
+            }
+            _ => dense,
+        }
+    }
+
+    /// Whether the set is stored as a bitset
+    pub fn is_dense(&self) -> bool {
+        matches!(self.repr, Repr::Dense(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match &self.repr {
+            Repr::Sparse(set) => set.len(),
+            Repr::Dense(bits) => bits.len,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, id: usize) -> bool {
+        match &self.repr {
+            Repr::Sparse(set) => set.contains(&id),
+            Repr::Dense(bits) => bits.contains(id),
+        }
+    }
+
+    /// Ids in ascending order
+    pub fn iter(&self) -> Iter<'_> {
+        match &self.repr {
+            Repr::Sparse(set) => Iter(IterRepr::Sparse(set.iter())),
+            Repr::Dense(bits) => Iter(IterRepr::Dense {
+                words: &bits.words,
+                index: 0,
+                word: bits.words.first().cloned().unwrap_or(0),
+            }),
+        }
+    }
+
+    pub fn to_btree_set(&self) -> BTreeSet<usize> {
+        self.iter().collect()
+    }
+
+    pub fn is_subset(&self, other: &SimplexSet) -> bool {
+        if let (Repr::Dense(a), Repr::Dense(b)) = (&self.repr, &other.repr) {
+            return a
+                .words
+                .iter()
+                .enumerate()
+                .all(|(i, &w)| w & !b.words.get(i).cloned().unwrap_or(0) == 0);
+        }
+        self.len() <= other.len() && self.iter().all(|id| other.contains(id))
+    }
+
+    /// Ids in either set
+    pub(crate) fn union(&self, other: &SimplexSet, universe: usize) -> SimplexSet {
+        if let (Repr::Dense(a), Repr::Dense(b)) = (&self.repr, &other.repr) {
+            return SimplexSet::from_bits(a.zip(b, |x, y| x | y));
+        }
+        SimplexSet::from_ids(self.iter().chain(other.iter()).collect(), universe)
+    }
+
+    /// Ids in both sets
+    pub(crate) fn intersection(&self, other: &SimplexSet, universe: usize) -> SimplexSet {
+        if let (Repr::Dense(a), Repr::Dense(b)) = (&self.repr, &other.repr) {
+            return SimplexSet::from_bits(a.zip(b, |x, y| x & y));
+        }
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        let ids = small.iter().filter(|&id| large.contains(id)).collect();
+        SimplexSet::from_ids(ids, universe)
+    }
+
+    /// Ids in this set but not in `other`
+    pub(crate) fn difference(&self, other: &SimplexSet, universe: usize) -> SimplexSet {
+        if let (Repr::Dense(a), Repr::Dense(b)) = (&self.repr, &other.repr) {
+            return SimplexSet::from_bits(a.zip(b, |x, y| x & !y));
+        }
+        let ids = self.iter().filter(|&id| !other.contains(id)).collect();
+        SimplexSet::from_ids(ids, universe)
+    }
+}
+
+impl From<BTreeSet<usize>> for SimplexSet {
+    fn from(set: BTreeSet<usize>) -> Self {
+        SimplexSet {
+            repr: Repr::Sparse(set),
+        }
+    }
+}
+
+impl PartialEq for SimplexSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for SimplexSet {}
+
+impl fmt::Debug for SimplexSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a SimplexSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the ids of a [SimplexSet] in ascending order
+pub struct Iter<'a>(IterRepr<'a>);
+
+enum IterRepr<'a> {
+    Sparse(alloc::collections::btree_set::Iter<'a, usize>),
+    Dense {
+        words: &'a [u64],
+        index: usize,
+        /// Bits of `words[index]` not visited yet
+        word: u64,
+    },
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = usize;
+    fn next(&mut self) -> Option<usize> {
+        match &mut self.0 {
+            IterRepr::Sparse(iter) => iter.next().cloned(),
+            IterRepr::Dense { words, index, word } => loop {
+                if *word != 0 {
+                    let bit = word.trailing_zeros() as usize;
+                    *word &= *word - 1;
+                    return Some(*index * 64 + bit);
+                }
+                *index += 1;
+                *word = *words.get(*index)?;
+            },
+        }
+    }
+}
+
+/// Fixed-size bitset with the number of bits set
+#[derive(Clone)]
+struct BitSet {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitSet {
+    /// Bitset of at least `universe` bits, extended to hold the largest id
+    fn from_ids(ids: &[usize], universe: usize) -> Self {
+        let universe = ids.iter().map(|&id| id + 1).fold(universe, usize::max);
+        let mut words = vec![0u64; universe.div_ceil(64)];
+        for &id in ids {
+            words[id / 64] |= 1 << (id % 64);
+        }
+        let len = words.iter().map(|w| w.count_ones() as usize).sum();
+        BitSet { words, len }
+    }
+
+    fn universe(&self) -> usize {
+        self.words.len() * 64
+    }
+
+    fn contains(&self, id: usize) -> bool {
+        self.words
+            .get(id / 64)
+            .is_some_and(|w| w & (1 << (id % 64)) != 0)
+    }
+
+    /// Word-wise operation, where the shorter bitset is padded by zeros
+    fn zip(&self, other: &BitSet, op: impl Fn(u64, u64) -> u64) -> BitSet {
+        let n = self.words.len().max(other.words.len());
+        let word = |bits: &BitSet, i: usize| bits.words.get(i).cloned().unwrap_or(0);
+        let words: Vec<u64> = (0..n).map(|i| op(word(self, i), word(other, i))).collect();
+        let len = words.iter().map(|w| w.count_ones() as usize).sum();
+        BitSet { words, len }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn both(ids: &[usize], universe: usize) -> [SimplexSet; 2] {
+        let sparse = SimplexSet::from(ids.iter().cloned().collect::<BTreeSet<_>>());
+        let dense = sparse.to_dense(universe);
+        assert!(!sparse.is_dense() && dense.is_dense());
+        [sparse, dense]
+    }
+
+    #[test]
+    fn representations_agree() {
+        let a_ids = [0, 3, 63, 64, 65, 127, 200];
+        let b_ids = [3, 4, 64, 128, 199, 200];
+        for a in &both(&a_ids, 201) {
+            assert_eq!(a.iter().collect::<Vec<_>>(), a_ids);
+            assert_eq!(a.len(), a_ids.len());
+            assert!(a.contains(63) && !a.contains(62) && !a.contains(1000));
+            for b in &both(&b_ids, 201) {
+                let union: Vec<usize> = a.union(b, 201).iter().collect();
+                assert_eq!(union, [0, 3, 4, 63, 64, 65, 127, 128, 199, 200]);
+                let intersection: Vec<usize> = a.intersection(b, 201).iter().collect();
+                assert_eq!(intersection, [3, 64, 200]);
+                let difference: Vec<usize> = a.difference(b, 201).iter().collect();
+                assert_eq!(difference, [0, 63, 65, 127]);
+                assert!(!a.is_subset(b));
+                assert!(a.intersection(b, 201).is_subset(b));
+            }
+        }
+        let [sparse, dense] = both(&a_ids, 201);
+        assert_eq!(sparse, dense);
+        assert_eq!(format!("{:?}", dense), "{0, 3, 63, 64, 65, 127, 200}");
+    }
+
+    #[test]
+    fn density() {
+        assert!(SimplexSet::from_ids(vec![5, 1, 5], 64).is_dense());
+        assert!(!SimplexSet::from_ids(vec![5, 1, 5], 1000).is_dense());
+        assert!(!SimplexSet::from_ids(Vec::new(), 0).is_dense());
+        assert_eq!(SimplexSet::from_ids(vec![5, 1, 5], 1000).len(), 2);
+
+        // a small difference of dense sets becomes sparse
+        let all = SimplexSet::from_ids((0..1000).collect(), 1000);
+        let most = SimplexSet::from_ids((1..1000).collect(), 1000);
+        let rest = all.difference(&most, 1000);
+        assert!(!rest.is_dense());
+        assert_eq!(rest.iter().collect::<Vec<_>>(), [0]);
+    }
+}
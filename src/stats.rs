@@ -0,0 +1,177 @@
+/*!
+Summary of the topology of a mesh
+*/
+
+use crate::half_edge::Mesh;
+use crate::timing::Timer;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Summary of a mesh, see [Mesh::stats]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeshStats {
+    pub num_vertices: usize,
+    pub num_edges: usize,
+    pub num_faces: usize,
+    pub euler_characteristic: isize,
+    /// `None` if the mesh is not a manifold
+    pub genus: Option<usize>,
+    pub closed: bool,
+    pub manifold: bool,
+    pub num_components: usize,
+    pub num_boundary_loops: usize,
+    /// Number of vertices for each number of incident edges
+    pub valence_histogram: BTreeMap<usize, usize>,
+    /// Number of faces for each number of edges of the face
+    pub face_degree_histogram: BTreeMap<usize, usize>,
+}
+
+impl Mesh {
+    /// Collect the summary of the mesh
+    pub fn stats(&self) -> MeshStats {
+        let _timer = Timer::start("stats");
+        let mut valence_histogram = BTreeMap::new();
+        for v in 0..self.num_vertices() {
+            *valence_histogram
+                .entry(self.vertex_edges(v).len())
+                .or_insert(0) += 1;
+        }
+        let mut face_degree_histogram = BTreeMap::new();
+        for f in 0..self.num_faces() {
+            *face_degree_histogram
+                .entry(self.face_edges(f).len())
+                .or_insert(0) += 1;
+        }
+        MeshStats {
+            num_vertices: self.num_vertices(),
+            num_edges: self.num_edges(),
+            num_faces: self.num_faces(),
+            euler_characteristic: self.euler_characteristic(),
+            genus: self.genus(),
+            closed: self.is_closed(),
+            manifold: self.is_manifold(),
+            num_components: self.num_components(),
+            num_boundary_loops: self.num_boundary_loops(),
+            valence_histogram,
+            face_degree_histogram,
+        }
+    }
+}
+
+impl MeshStats {
+    /// Serialize as a JSON object
+    ///
+    /// Keys of histograms are written as strings since JSON object keys must be strings.
+    pub fn to_json(&self) -> String {
+        format!("{{{}}}", self.to_json_members())
+    }
+
+    /// Members of [MeshStats::to_json] without the braces, to be embedded in another object
+    pub fn to_json_members(&self) -> String {
+        let genus = match self.genus {
+            Some(genus) => genus.to_string(),
+            None => "null".to_string(),
+        };
+        format!(
+            concat!(
+                r#""vertices":{},"edges":{},"faces":{},"euler_characteristic":{},"#,
+                r#""genus":{},"closed":{},"manifold":{},"components":{},"boundary_loops":{},"#,
+                r#""valence_histogram":{},"face_degree_histogram":{}"#
+            ),
+            self.num_vertices,
+            self.num_edges,
+            self.num_faces,
+            self.euler_characteristic,
+            genus,
+            self.closed,
+            self.manifold,
+            self.num_components,
+            self.num_boundary_loops,
+            histogram_json(&self.valence_histogram),
+            histogram_json(&self.face_degree_histogram),
+        )
+    }
+}
+
+fn histogram_json(histogram: &BTreeMap<usize, usize>) -> String {
+    let entries: Vec<_> = histogram
+        .iter()
+        .map(|(k, n)| format!(r#""{}":{}"#, k, n))
+        .collect();
+    format!("{{{}}}", entries.join(","))
+}
+
+fn histogram_text(histogram: &BTreeMap<usize, usize>) -> String {
+    let entries: Vec<_> = histogram
+        .iter()
+        .map(|(k, n)| format!("{}×{}", k, n))
+        .collect();
+    entries.join(" ")
+}
+
+fn yes_no(b: bool) -> &'static str {
+    if b {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+impl fmt::Display for MeshStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "vertices: {}", self.num_vertices)?;
+        writeln!(f, "edges: {}", self.num_edges)?;
+        writeln!(f, "faces: {}", self.num_faces)?;
+        writeln!(f, "χ = {}", self.euler_characteristic)?;
+        match self.genus {
+            Some(genus) => writeln!(f, "genus: {}", genus)?,
+            None => writeln!(f, "genus: undefined (non-manifold)")?,
+        }
+        writeln!(f, "closed: {}", yes_no(self.closed))?;
+        writeln!(f, "manifold: {}", yes_no(self.manifold))?;
+        writeln!(f, "components: {}", self.num_components)?;
+        writeln!(f, "boundary loops: {}", self.num_boundary_loops)?;
+        writeln!(
+            f,
+            "valence (valence×count): {}",
+            histogram_text(&self.valence_histogram)
+        )?;
+        writeln!(
+            f,
+            "face degree (degree×count): {}",
+            histogram_text(&self.face_degree_histogram)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fixtures;
+
+    #[test]
+    fn cube() {
+        let stats = fixtures::CUBE.mesh().stats();
+        assert_eq!(stats.num_vertices, 8);
+        assert_eq!(stats.num_edges, 12);
+        assert_eq!(stats.num_faces, 6);
+        assert_eq!(stats.euler_characteristic, 2);
+        assert_eq!(stats.genus, Some(0));
+        assert!(stats.closed && stats.manifold);
+        assert_eq!(stats.num_components, 1);
+        assert_eq!(stats.valence_histogram.get(&3), Some(&8));
+        assert_eq!(stats.face_degree_histogram.get(&4), Some(&6));
+
+        let text = stats.to_string();
+        assert!(text.contains("χ = 2"));
+        assert!(text.contains("valence (valence×count): 3×8"));
+
+        assert_eq!(
+            stats.to_json(),
+            concat!(
+                r#"{"vertices":8,"edges":12,"faces":6,"euler_characteristic":2,"#,
+                r#""genus":0,"closed":true,"manifold":true,"components":1,"boundary_loops":0,"#,
+                r#""valence_histogram":{"3":8},"face_degree_histogram":{"4":6}}"#
+            )
+        );
+    }
+}
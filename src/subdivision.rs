@@ -0,0 +1,135 @@
+/*!
+Subdivision of the connectivity of a closed surface
+
+Only the permutation on half-edges is refined, since the mesh has no positions.
+New vertices are numbered after the original ones:
+the midpoint of edge `e` is `V + e`, and the point of face `f` is `V + E + f`.
+*/
+
+use crate::permutation::{from_faces, gather_faces, origins, FaceListError};
+use crate::timing::Timer;
+use std::fmt;
+
+/// Subdivision scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    /// Split each triangle into four, which requires a triangle mesh
+    Loop,
+    /// Split each face into quads joining the face point and the edge midpoints
+    CatmullClark,
+    /// Cut each corner of a face at the edge midpoints, leaving the central face
+    Midpoint,
+}
+
+/// Reason why a surface cannot be subdivided
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubdivisionError {
+    /// The scheme requires triangles, but the face has another number of edges
+    NotTriangle { face: usize, degree: usize },
+    /// The subdivided faces do not form a closed surface,
+    /// e.g. a face is bounded by both sides of an edge
+    Degenerate(FaceListError),
+}
+
+impl fmt::Display for SubdivisionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SubdivisionError::NotTriangle { face, degree } => write!(
+                f,
+                "Loop subdivision requires triangles, but face {} has {} edges; triangulate the mesh first",
+                face, degree
+            ),
+            SubdivisionError::Degenerate(e) => write!(f, "Degenerate mesh: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for SubdivisionError {}
+
+/// Permutation of the subdivided surface
+pub fn subdivide(permutation: &[usize], scheme: Scheme) -> Result<Vec<usize>, SubdivisionError> {
+    let _timer = Timer::start("subdivide");
+    let origin = origins(permutation);
+    let num_vertices = origin.iter().max().map_or(0, |v| v + 1);
+    let num_edges = permutation.len() / 2;
+    let midpoint = |h: usize| num_vertices + h / 2;
+
+    let mut faces = Vec::new();
+    for (f, orbit) in gather_faces(permutation).iter().enumerate() {
+        let hs: Vec<usize> = orbit.indices().collect();
+        let k = hs.len();
+        let previous = |i: usize| hs[(i + k - 1) % k];
+        match scheme {
+            Scheme::Loop | Scheme::Midpoint => {
+                if scheme == Scheme::Loop && k != 3 {
+                    return Err(SubdivisionError::NotTriangle { face: f, degree: k });
+                }
+                for (i, &h) in hs.iter().enumerate() {
+                    faces.push(vec![midpoint(previous(i)), origin[h], midpoint(h)]);
+                }
+                faces.push(hs.iter().map(|&h| midpoint(h)).collect());
+            }
+            Scheme::CatmullClark => {
+                let center = num_vertices + num_edges + f;
+                for (i, &h) in hs.iter().enumerate() {
+                    faces.push(vec![origin[h], midpoint(h), center, midpoint(previous(i))]);
+                }
+            }
+        }
+    }
+    from_faces(&faces).map_err(SubdivisionError::Degenerate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixtures, half_edge::Mesh, permutation::validate};
+
+    fn counts(permutation: &[usize]) -> (usize, usize, usize) {
+        assert_eq!(validate(permutation), Ok(()));
+        let mesh = Mesh::from_permutation(permutation);
+        assert_eq!(mesh.genus(), Some(0));
+        (mesh.num_vertices(), mesh.num_edges(), mesh.num_faces())
+    }
+
+    #[test]
+    fn loop_scheme() {
+        let tetrahedron = fixtures::TETRAHEDRON.permutation().unwrap();
+        let once = subdivide(&tetrahedron, Scheme::Loop).unwrap();
+        assert_eq!(counts(&once), (10, 24, 16));
+        let twice = subdivide(&once, Scheme::Loop).unwrap();
+        assert_eq!(counts(&twice), (34, 96, 64));
+        assert_eq!(
+            subdivide(&fixtures::CUBE.permutation().unwrap(), Scheme::Loop),
+            Err(SubdivisionError::NotTriangle { face: 0, degree: 4 })
+        );
+    }
+
+    #[test]
+    fn catmull_clark() {
+        let cube = subdivide(&fixtures::CUBE.permutation().unwrap(), Scheme::CatmullClark).unwrap();
+        assert_eq!(counts(&cube), (26, 48, 24));
+        let mesh = Mesh::from_permutation(&cube);
+        assert!((0..24).all(|f| mesh.face_edges(f).len() == 4));
+    }
+
+    #[test]
+    fn midpoint() {
+        // 24 corner triangles and 6 central squares
+        let cube = subdivide(&fixtures::CUBE.permutation().unwrap(), Scheme::Midpoint).unwrap();
+        assert_eq!(counts(&cube), (20, 48, 30));
+        let tetrahedron = fixtures::TETRAHEDRON.permutation().unwrap();
+        assert_eq!(
+            subdivide(&tetrahedron, Scheme::Midpoint),
+            subdivide(&tetrahedron, Scheme::Loop)
+        );
+    }
+
+    #[test]
+    fn degenerate() {
+        assert!(matches!(
+            subdivide(&[1, 0], Scheme::Midpoint),
+            Err(SubdivisionError::Degenerate(_))
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+/*!
+Instrumentation of the major entry points of the library
+
+Recording is disabled by default, and then a [Timer] does not even read the clock.
+After [enable], the stages are recorded per thread in the order they start,
+and nested stages keep their depth:
+
+```
+use umesh::{shapes, timing, Mesh};
+
+timing::enable();
+let _mesh = Mesh::from_permutation(&shapes::cube());
+let report = timing::take();
+assert_eq!(report.stages[0].name, "from_permutation");
+assert_eq!(report.stages[1].name, "from_connections");
+assert_eq!(report.stages[1].depth, 1);
+assert!(report.peak_memory > 0);
+```
+*/
+
+use crate::memory::MemoryReport;
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static REPORT: RefCell<Report> = RefCell::new(Report::default());
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Start recording stages
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+/// Stop recording stages
+pub fn disable() {
+    ENABLED.store(false, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// Elapsed time of a stage
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stage {
+    pub name: &'static str,
+    /// Number of enclosing stages
+    pub depth: usize,
+    pub elapsed: Duration,
+}
+
+/// Stages recorded on the current thread
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Report {
+    pub stages: Vec<Stage>,
+    /// Largest allocated bytes of [crate::Mesh::memory_usage] of the meshes constructed
+    pub peak_memory: usize,
+    /// Buffers of the mesh of [Report::peak_memory]
+    pub memory: MemoryReport,
+}
+
+/// Take the report of the current thread, and start a new one
+pub fn take() -> Report {
+    REPORT.with(|report| std::mem::take(&mut *report.borrow_mut()))
+}
+
+/// Record the memory of a data structure, keeping the largest
+pub fn record_memory(memory: &MemoryReport) {
+    if is_enabled() {
+        REPORT.with(|report| {
+            let mut report = report.borrow_mut();
+            if memory.allocated() > report.peak_memory {
+                report.peak_memory = memory.allocated();
+                report.memory = memory.clone();
+            }
+        });
+    }
+}
+
+/// Scope guard recording the elapsed time of a stage when dropped
+#[must_use = "the stage ends when the timer is dropped"]
+pub struct Timer {
+    /// Index in the report and the start time, if enabled
+    started: Option<(usize, Instant)>,
+}
+
+impl Timer {
+    pub fn start(name: &'static str) -> Self {
+        if !is_enabled() {
+            return Timer { started: None };
+        }
+        let depth = DEPTH.with(|depth| depth.replace(depth.get() + 1));
+        let index = REPORT.with(|report| {
+            let stages = &mut report.borrow_mut().stages;
+            stages.push(Stage {
+                name,
+                depth,
+                elapsed: Duration::default(),
+            });
+            stages.len() - 1
+        });
+        Timer {
+            started: Some((index, Instant::now())),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        if let Some((index, start)) = self.started {
+            let elapsed = start.elapsed();
+            DEPTH.with(|depth| depth.set(depth.get() - 1));
+            REPORT.with(|report| {
+                // the report may have been taken while the stage is running
+                if let Some(stage) = report.borrow_mut().stages.get_mut(index) {
+                    stage.elapsed = elapsed;
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        enable();
+        take();
+        {
+            let _outer = Timer::start("outer");
+            let _inner = Timer::start("inner");
+        }
+        let _other = Timer::start("other");
+        let memory = |bytes: usize| {
+            let mut memory = MemoryReport::default();
+            memory.push("buffer", &vec![0_u8; bytes]);
+            memory
+        };
+        record_memory(&memory(10));
+        record_memory(&memory(5));
+        let report = take();
+        let stages: Vec<_> = report.stages.iter().map(|s| (s.name, s.depth)).collect();
+        assert_eq!(stages, vec![("outer", 0), ("inner", 1), ("other", 0)]);
+        assert_eq!(report.peak_memory, 10);
+        assert_eq!(report.memory, memory(10));
+    }
+}
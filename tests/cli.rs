@@ -0,0 +1,608 @@
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+const TETRAHEDRON: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/tetrahedron.txt");
+const CUBE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/cube.txt");
+const TWO_TETRAHEDRA: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/two_tetrahedra.txt");
+const OPEN_PATCH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/open_patch.txt");
+const ANNULUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/annulus.txt");
+const TETRAHEDRON_FACES: &str = concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/tests/data/tetrahedron_faces.txt"
+);
+const BROKEN_CUBE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/broken_cube.txt");
+
+fn umesh(args: &[&str], stdin: Option<&str>) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_umesh"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    {
+        let mut child_stdin = child.stdin.take().unwrap();
+        if let Some(input) = stdin {
+            child_stdin.write_all(input.as_bytes()).unwrap();
+        }
+    }
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+fn assert_tetrahedron_dot(output: &Output) {
+    assert!(output.status.success());
+    let stdout = stdout(output);
+    assert!(stdout.starts_with("graph {"));
+    assert_eq!(stdout.matches(" -- ").count(), 6);
+}
+
+fn assert_exit_code(output: &Output, code: i32) {
+    assert_eq!(output.status.code(), Some(code), "{}", stderr(output));
+    assert!(!stderr(output).contains("panicked"));
+}
+
+fn assert_usage_error(output: &Output) {
+    assert_exit_code(output, 1);
+    assert!(stderr(output).contains("Usage: umesh"));
+}
+
+#[test]
+fn dot_args() {
+    let mut args = vec!["dot"];
+    args.extend("5 6 0 8 10 2 11 3 7 4 9 1".split(' '));
+    assert_tetrahedron_dot(&umesh(&args, None));
+}
+
+#[test]
+fn dot_file() {
+    assert_tetrahedron_dot(&umesh(&["dot", TETRAHEDRON], None));
+}
+
+#[test]
+fn dot_stdin() {
+    let input = std::fs::read_to_string(TETRAHEDRON).unwrap();
+    assert_tetrahedron_dot(&umesh(&["dot", "-"], Some(&input)));
+}
+
+#[test]
+fn dot_labels() {
+    let output = umesh(&["dot", "--labels", "orbits", TETRAHEDRON], None);
+    assert!(output.status.success());
+    let expected = r#"graph {
+    0 [ label = "(0 6 3)" ]
+    1 [ label = "(1 5 10)" ]
+    2 [ label = "(2 8 4)" ]
+    3 [ label = "(7 11 9)" ]
+    0 -- 1 [ ]
+    0 -- 2 [ ]
+    1 -- 2 [ ]
+    0 -- 3 [ ]
+    2 -- 3 [ ]
+    1 -- 3 [ ]
+}
+"#;
+    assert_eq!(stdout(&output), expected);
+
+    let output = umesh(&["dot", "--labels", "none", TETRAHEDRON], None);
+    assert!(stdout(&output).contains("    3 [ label = \"\" ]\n"));
+    assert_usage_error(&umesh(&["dot", "--labels", "faces", TETRAHEDRON], None));
+    assert_usage_error(&umesh(&["render-svg", "--labels", "orbits", CUBE], None));
+}
+
+#[test]
+fn dot_output_file() {
+    let path = std::env::temp_dir().join(format!("umesh-cli-{}.dot", std::process::id()));
+    let output = umesh(&["dot", "-o", path.to_str().unwrap(), TETRAHEDRON], None);
+    assert!(output.status.success());
+    assert!(stdout(&output).is_empty());
+    let dot = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(dot.matches(" -- ").count(), 6);
+}
+
+#[test]
+fn stats() {
+    let output = umesh(&["stats", CUBE], None);
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    assert!(stdout.contains("vertices: 8"));
+    assert!(stdout.contains("edges: 12"));
+    assert!(stdout.contains("faces: 6"));
+    assert!(stdout.contains("χ = 2"));
+    assert!(stdout.contains("genus: 0"));
+}
+
+#[test]
+fn stats_json() {
+    let output = umesh(&["stats", "--json", CUBE], None);
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    let json = stdout.trim();
+    assert!(json.starts_with(r#"{"schema_version":1,"vertices":8,"#) && json.ends_with('}'));
+    assert!(json.contains(r#""vertices":8,"edges":12,"faces":6,"euler_characteristic":2"#));
+    assert!(json.contains(r#""valence_histogram":{"3":8}"#));
+}
+
+#[test]
+fn check() {
+    let output = umesh(&["check", TETRAHEDRON], None);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("permutation: ok"));
+
+    let output = umesh(&["check", "1", "0", "0", "3"], None);
+    assert_exit_code(&output, 2);
+    assert!(stdout(&output).contains("permutation: failed"));
+}
+
+#[test]
+fn check_cube() {
+    let output = umesh(&["check", CUBE], None);
+    assert_exit_code(&output, 0);
+    let stdout = stdout(&output);
+    for name in &["invariants", "manifold", "boundary", "degenerate", "euler"] {
+        assert!(stdout.contains(&format!("{}: ok", name)));
+    }
+}
+
+#[test]
+fn check_degenerate() {
+    // one face bounded by both sides of the edge 0
+    let output = umesh(&["check", "1", "0"], None);
+    assert_exit_code(&output, 2);
+    let report = stdout(&output);
+    assert!(report.contains("degenerate: failed\n"));
+    assert!(report.contains("    edge 0 has face 0 on both sides"));
+
+    let output = umesh(&["check", "--allow", "boundary,degenerate", "1", "0"], None);
+    assert_exit_code(&output, 0);
+    assert!(stdout(&output).contains("degenerate: failed (allowed)"));
+
+    assert_usage_error(&umesh(&["check", "--allow", "orientation", "1", "0"], None));
+}
+
+#[test]
+fn parse_error() {
+    let output = umesh(&["dot", "-"], Some("5 6 0\n8 ten 2\n"));
+    assert_exit_code(&output, 2);
+    let stderr = stderr(&output);
+    assert!(stderr.starts_with("error: <stdin>: "));
+    assert!(stderr.contains("'ten'"));
+    assert!(stderr.contains("line 2, column 3"));
+    assert!(stderr.contains("hint: The permutation is non-negative integers"));
+}
+
+#[test]
+fn invalid_permutation() {
+    let output = umesh(&["stats", "0", "2"], None);
+    assert_exit_code(&output, 2);
+    let stderr = stderr(&output);
+    assert!(stderr.contains("out of range"));
+    assert!(stderr.contains("hint: The h-th integer is the next half-edge of h"));
+}
+
+#[test]
+fn missing_file() {
+    let output = umesh(&["stats", "no-such-mesh.txt"], None);
+    assert_exit_code(&output, 3);
+    let stderr = stderr(&output);
+    assert!(stderr.starts_with("error: Cannot open 'no-such-mesh.txt'"));
+    assert!(stderr.contains("hint: Give a file, '-' to read stdin"));
+}
+
+#[test]
+fn usage_errors() {
+    assert_usage_error(&umesh(&[], None));
+    assert_usage_error(&umesh(&["render", TETRAHEDRON], None));
+    assert_usage_error(&umesh(&["dot"], None));
+    assert_usage_error(&umesh(&["dot", TETRAHEDRON, "-o"], None));
+
+    let output = umesh(&["stats", "--jsn", TETRAHEDRON], None);
+    assert_usage_error(&output);
+    assert!(stderr(&output).contains("unknown option --jsn"));
+}
+
+#[test]
+fn render_svg() {
+    let output = umesh(&["render-svg", "--color", "--labels", "ids", CUBE], None);
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with("<svg "));
+    assert_eq!(stdout.matches("<polygon ").count(), 6);
+    assert_eq!(stdout.matches("<line ").count(), 12);
+    assert_eq!(stdout.matches("<text ").count(), 8);
+}
+
+#[test]
+fn generate_torus() {
+    let output = umesh(&["generate", "torus", "--major", "6", "--minor", "3"], None);
+    assert!(output.status.success());
+    let torus = stdout(&output);
+    assert!(torus.starts_with("# torus\n"));
+
+    let output = umesh(&["stats", "-"], Some(&torus));
+    assert!(output.status.success());
+    let stdout = stdout(&output);
+    assert!(stdout.contains("faces: 18"));
+    assert!(stdout.contains("genus: 1"));
+}
+
+#[test]
+fn generate_grid() {
+    let output = umesh(&["generate", "grid", "--nx", "3", "--ny", "2"], None);
+    assert_exit_code(&output, 0);
+    let grid = stdout(&output);
+    assert!(grid.starts_with("# grid\n0 1 5 4\n"));
+    assert_eq!(grid.lines().count(), 7);
+
+    let output = umesh(&["boundary", "-"], Some(&grid));
+    assert_exit_code(&output, 0);
+    assert_eq!(stdout(&output), "loop 0: 10 edges: 0 1 2 3 7 11 10 9 8 4\n");
+}
+
+#[test]
+fn generate_errors() {
+    let output = umesh(&["generate", "torus", "--minor", "2"], None);
+    assert_exit_code(&output, 1);
+    assert!(stderr(&output).contains("--minor must be at least 3, but got 2"));
+
+    let output = umesh(&["generate", "icosphere", "--subdiv", "x"], None);
+    assert_exit_code(&output, 1);
+    assert!(stderr(&output).contains("--subdiv requires a non-negative integer"));
+
+    let output = umesh(&["generate", "icosphere", "--subdiv", "12"], None);
+    assert_exit_code(&output, 1);
+    assert!(stderr(&output).contains("--subdiv must be at most 8, but got 12"));
+
+    let output = umesh(&["generate", "grid", "--ny", "0"], None);
+    assert_exit_code(&output, 1);
+    assert!(stderr(&output).contains("--ny must be at least 1, but got 0"));
+
+    let output = umesh(&["generate", "sphere"], None);
+    assert_exit_code(&output, 1);
+    assert!(stderr(&output).contains("Unknown shape 'sphere'"));
+}
+
+#[test]
+fn dual() {
+    let output = umesh(&["dual", "--check", CUBE], None);
+    assert_exit_code(&output, 0);
+    let octahedron = stdout(&output);
+    assert!(octahedron.starts_with("# dual\n"));
+
+    let output = umesh(&["stats", "--json", "-"], Some(&octahedron));
+    assert_exit_code(&output, 0);
+    let json = stdout(&output);
+    assert!(json.contains(r#""vertices":6,"edges":12,"faces":8"#));
+    assert!(json.contains(r#""face_degree_histogram":{"3":8}"#));
+}
+
+#[test]
+fn subdivide() {
+    let icosahedron = stdout(&umesh(&["generate", "icosahedron"], None));
+    let output = umesh(
+        &["subdivide", "--scheme", "loop", "--steps", "2", "-"],
+        Some(&icosahedron),
+    );
+    assert_exit_code(&output, 0);
+    assert!(stderr(&output)
+        .contains("12 vertices, 30 edges, 20 faces -> 162 vertices, 480 edges, 320 faces"));
+
+    let output = umesh(&["stats", "-"], Some(&stdout(&output)));
+    assert!(stdout(&output).contains("faces: 320"));
+
+    let output = umesh(&["subdivide", CUBE], None);
+    assert_exit_code(&output, 2);
+    assert!(stderr(&output).contains("triangulate"));
+
+    let output = umesh(&["subdivide", "--scheme", "catmull-clark", CUBE], None);
+    assert_exit_code(&output, 0);
+
+    assert_usage_error(&umesh(&["subdivide", "--scheme", "sqrt3", CUBE], None));
+
+    let output = umesh(
+        &[
+            "subdivide",
+            "--scheme",
+            "catmull-clark",
+            "--steps",
+            "40",
+            CUBE,
+        ],
+        None,
+    );
+    assert_usage_error(&output);
+    assert!(stderr(&output).contains("--steps 40 would give more than 4194304 faces"));
+}
+
+#[test]
+fn boundary() {
+    let output = umesh(&["boundary", TETRAHEDRON_FACES], None);
+    assert_exit_code(&output, 0);
+    assert_eq!(stdout(&output), "closed surface, no boundary\n");
+
+    let output = umesh(&["boundary", "--json", TETRAHEDRON_FACES], None);
+    assert_exit_code(&output, 0);
+    assert_eq!(stdout(&output), "{\"schema_version\":1,\"loops\":[]}\n");
+
+    let output = umesh(&["boundary", OPEN_PATCH], None);
+    assert_exit_code(&output, 0);
+    assert_eq!(stdout(&output), "loop 0: 8 edges: 0 1 2 5 8 7 6 3\n");
+
+    let output = umesh(&["boundary", "--json", ANNULUS], None);
+    assert_exit_code(&output, 0);
+    assert_eq!(
+        stdout(&output),
+        r#"{"schema_version":1,"loops":[{"edges":4,"vertices":[0,1,2,3]},{"edges":4,"vertices":[4,5,6,7]}]}"#.to_string() + "\n"
+    );
+
+    let output = umesh(&["boundary", "-"], Some("0 1 2\n0 1 3\n"));
+    assert_exit_code(&output, 2);
+    assert!(stderr(&output).contains("<stdin>"));
+}
+
+#[test]
+fn boundary_export() {
+    for (input, loops) in &[(OPEN_PATCH, 1), (ANNULUS, 2)] {
+        let path = std::env::temp_dir().join(format!(
+            "umesh-cli-{}-loops-{}.svg",
+            std::process::id(),
+            loops
+        ));
+        let output = umesh(
+            &["boundary", "--export", path.to_str().unwrap(), input],
+            None,
+        );
+        assert_exit_code(&output, 0);
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(svg.starts_with("<svg "));
+        assert_eq!(svg.matches("<polyline ").count(), *loops);
+    }
+}
+
+#[test]
+fn components() {
+    let prefix = std::env::temp_dir().join(format!("umesh-cli-{}-component", std::process::id()));
+    let prefix = prefix.to_str().unwrap();
+    let output = umesh(
+        &["components", "--split-prefix", prefix, TWO_TETRAHEDRA],
+        None,
+    );
+    assert_exit_code(&output, 0);
+    let stdout = stdout(&output);
+    assert!(stdout.starts_with("components: 2\n"));
+    assert!(stdout.contains("component 1: 4 vertices, 6 edges, 4 faces, genus 0"));
+
+    for i in 0..2 {
+        let path = format!("{}_{:03}.txt", prefix, i);
+        assert_exit_code(&umesh(&["check", &path], None), 0);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+#[test]
+fn format_json() {
+    let output = umesh(&["check", "--format", "json", "1", "0"], None);
+    assert_exit_code(&output, 2);
+    assert!(stdout(&output).starts_with(
+        r#"{"schema_version":1,"passed":false,"checks":[{"name":"permutation","passed":true,"allowed":false,"problems":[]},"#
+    ));
+    assert!(stdout(&output).contains(
+        r#"{"name":"degenerate","passed":false,"allowed":false,"problems":["face 0 has 1 edges","edge 0 has face 0 on both sides"]}"#
+    ));
+
+    let output = umesh(&["check", "--format", "json", "1", "0", "0", "3"], None);
+    assert_exit_code(&output, 2);
+    assert!(stdout(&output).starts_with(
+        r#"{"schema_version":1,"passed":false,"checks":[{"name":"permutation","passed":false,"#
+    ));
+
+    let output = umesh(&["components", "--format", "json", TWO_TETRAHEDRA], None);
+    assert_exit_code(&output, 0);
+    let component = r#"{"vertices":4,"edges":6,"faces":4,"genus":0,"file":null}"#;
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{{\"schema_version\":1,\"components\":[{},{}]}}\n",
+            component, component
+        )
+    );
+
+    let output = umesh(&["boundary", "--format", "text", TETRAHEDRON_FACES], None);
+    assert_eq!(stdout(&output), "closed surface, no boundary\n");
+    assert_usage_error(&umesh(&["stats", "--format", "yaml", CUBE], None));
+    assert_usage_error(&umesh(&["stats", CUBE, "--format"], None));
+}
+
+#[test]
+fn timings() {
+    let output = umesh(&["stats", "--timings", CUBE], None);
+    assert_exit_code(&output, 0);
+    assert!(stdout(&output).contains("faces: 6"));
+    let text = stderr(&output);
+    let (table, memory) = text.split_once("\n\n").unwrap();
+    let names: Vec<&str> = table
+        .lines()
+        .map(|line| line.rsplitn(3, ' ').nth(2).unwrap().trim_end())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "parse",
+            "validate",
+            "from_permutation",
+            "  from_connections",
+            "stats",
+            "total",
+            "peak topology memory"
+        ]
+    );
+    assert!(table
+        .lines()
+        .all(|line| line.len() == table.lines().next().unwrap().len()));
+    // buffers of the mesh with the used and allocated bytes
+    let memory: Vec<&str> = memory.lines().collect();
+    assert!(memory[0].starts_with("buffer"));
+    assert!(memory
+        .iter()
+        .any(|line| line.starts_with("vertex_edge.fr ")));
+    assert!(memory.iter().any(|line| line.starts_with("permutation ")));
+    assert!(memory.last().unwrap().starts_with("total "));
+
+    let output = umesh(&["stats", CUBE], None);
+    assert_eq!(stderr(&output), "");
+}
+
+#[test]
+fn query() {
+    let output = umesh(&["query", "--select", "V 0", TETRAHEDRON, "link"], None);
+    assert_exit_code(&output, 0);
+    assert_eq!(stdout(&output), "V 1 2 3 / E 2 4 5 / F\n");
+
+    let output = umesh(&["query", "--select", "V 0", CUBE, "link", "--json"], None);
+    assert_exit_code(&output, 0);
+    let json = stdout(&output);
+    assert!(json.starts_with(r#"{"schema_version":1,"vertices":["#));
+    assert!(json.ends_with(
+        r#","faces":[]}
+"#
+    ));
+
+    let output = umesh(&["query", "--select", "F 0", CUBE, "boundary"], None);
+    assert_exit_code(&output, 2);
+    assert!(stderr(&output).contains("pure subcomplex"));
+    let output = umesh(&["query", "--select", "F 0", CUBE, "closure"], None);
+    let closure = stdout(&output);
+    let output = umesh(
+        &["query", "--select", closure.trim(), CUBE, "boundary"],
+        None,
+    );
+    assert_exit_code(&output, 0);
+    assert!(stdout(&output).ends_with(" / F\n"));
+
+    assert_usage_error(&umesh(&["query", "--select", "V 8", CUBE, "star"], None));
+    assert_usage_error(&umesh(&["query", "--select", "V 0", CUBE, "hodge"], None));
+    assert_usage_error(&umesh(&["query", CUBE, "star"], None));
+}
+
+#[test]
+fn curvature() {
+    let icosphere = stdout(&umesh(&["generate", "icosphere", "--subdiv", "2"], None));
+    let output = umesh(&["curvature", "-"], Some(&icosphere));
+    assert_exit_code(&output, 0);
+    let values: Vec<f64> = stdout(&output)
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix(r#"<data key="d0">"#))
+        .map(|value| value.trim_end_matches("</data>").parse().unwrap())
+        .collect();
+    assert_eq!(values.len(), 162);
+    let total: f64 = values.iter().sum();
+    assert!((total / (4.0 * std::f64::consts::PI) - 1.0).abs() < 0.01);
+    assert!(stderr(&output).contains("(χ = 2)"));
+
+    let output = umesh(&["curvature", "--kind", "mean", CUBE], None);
+    assert_exit_code(&output, 2);
+    assert!(stderr(&output).contains("positions"));
+    assert_usage_error(&umesh(&["curvature", "--kind", "ricci", CUBE], None));
+}
+
+#[test]
+fn repair() {
+    let output = umesh(
+        &[
+            "repair",
+            "--remove-degenerate",
+            "--fix-orientation",
+            "--fill-holes",
+            "4",
+            BROKEN_CUBE,
+        ],
+        None,
+    );
+    assert_exit_code(&output, 0);
+    let report = stderr(&output);
+    assert!(report.starts_with(
+        "remove_degenerate: 3 changes\nfix_orientation: 2 changes\nfill_holes: 1 changes\n"
+    ));
+    assert!(report.contains("euler: ok"));
+    let output = umesh(&["stats", "-"], Some(&stdout(&output)));
+    assert!(stdout(&output).contains("genus: 0\nclosed: yes\nmanifold: yes\n"));
+
+    // the collapsed and duplicated faces remain
+    let output = umesh(&["repair", "--fix-orientation", BROKEN_CUBE], None);
+    assert_exit_code(&output, 2);
+    assert!(stdout(&output).starts_with("# partially repaired\n"));
+    let output = umesh(&["repair", "--strict", BROKEN_CUBE], None);
+    assert_exit_code(&output, 2);
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn diff() {
+    let output = umesh(&["diff", CUBE, CUBE], None);
+    assert_exit_code(&output, 0);
+    assert_eq!(stdout(&output), "identical\n");
+
+    // the cube with edges 0 and 1 swapped
+    let cube = [
+        22, 5, 1, 18, 20, 7, 16, 2, 12, 17, 8, 19, 15, 21, 23, 10, 11, 4, 14, 6, 9, 0, 13, 3,
+    ];
+    let swap = |h: usize| match h / 2 {
+        0 => h + 2,
+        1 => h - 2,
+        _ => h,
+    };
+    let mut swapped = [0; 24];
+    for (h, &next) in cube.iter().enumerate() {
+        swapped[swap(h)] = swap(next);
+    }
+    let swapped: Vec<String> = swapped.iter().map(|h| h.to_string()).collect();
+    let output = umesh(&["diff", CUBE, "-"], Some(&swapped.join(" ")));
+    assert_exit_code(&output, 1);
+    assert!(stdout(&output).starts_with("isomorphic\nmismatched half-edges: "));
+
+    let output = umesh(&["diff", TETRAHEDRON, CUBE], None);
+    assert_exit_code(&output, 2);
+    let report = stdout(&output);
+    assert!(report.starts_with("different\n- vertices: 4\n+ vertices: 8\n"));
+    assert!(report.contains("- valence (valence×count): 3×4\n+ valence (valence×count): 3×8\n"));
+    assert!(!report.contains("genus"));
+
+    let output = umesh(&["diff", TWO_TETRAHEDRA, CUBE], None);
+    assert_exit_code(&output, 2);
+    assert!(stdout(&output).ends_with("mismatched half-edges: 24 of 24\n"));
+    // errors are not mistaken for the results
+    let output = umesh(&["diff", CUBE], None);
+    assert_exit_code(&output, 4);
+    assert!(stderr(&output).contains("Usage: umesh"));
+    assert_exit_code(&umesh(&["diff", "--bogus", CUBE, CUBE], None), 4);
+    let output = umesh(&["diff", CUBE, "-"], Some("1 0 0 3"));
+    assert_exit_code(&output, 4);
+    assert!(stderr(&output).contains("Invalid permutation"));
+}
+
+#[test]
+fn convert() {
+    let faces = "# cube\n0 3 2 1\n4 5 6 7\n0 1 5 4\n1 2 6 5\n2 3 7 6\n3 0 4 7\n";
+    let output = umesh(&["convert", "-"], Some(faces));
+    assert_exit_code(&output, 0);
+    assert!(stdout(&output).starts_with("# converted from <stdin>\n"));
+    let output = umesh(&["diff", CUBE, "-"], Some(&stdout(&output)));
+    // identical or isomorphic
+    assert!(matches!(output.status.code(), Some(0) | Some(1)));
+
+    let output = umesh(&["convert", BROKEN_CUBE], None);
+    assert_exit_code(&output, 2);
+    assert!(stderr(&output).contains("umesh repair"));
+    let output = umesh(&["convert", "-"], Some("0 1 2\n0 x\n"));
+    assert_exit_code(&output, 2);
+    assert!(stderr(&output).contains("line 2"));
+}
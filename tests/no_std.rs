@@ -0,0 +1,25 @@
+//! The topology core must build without std
+
+use std::process::Command;
+
+#[test]
+fn check_without_std() {
+    for features in &["", "u32-index"] {
+        let output = Command::new(env!("CARGO"))
+            .args(["check", "--lib", "--quiet", "--no-default-features"])
+            .args(["--features", features])
+            .args([
+                "--target-dir",
+                concat!(env!("CARGO_MANIFEST_DIR"), "/target/no_std"),
+            ])
+            .current_dir(env!("CARGO_MANIFEST_DIR"))
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "features '{}': {}",
+            features,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
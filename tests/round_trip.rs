@@ -0,0 +1,149 @@
+//! Round trips of random meshes through the text formats
+//!
+//! Cases are generated from the smallest size up, so the first failure is a minimal mesh.
+//! What a format cannot keep is asserted explicitly rather than skipped.
+
+#![cfg(feature = "std")]
+
+use std::collections::BTreeSet;
+use umesh::{
+    connection_matrix::to_usize,
+    gather_faces, gather_vertices,
+    io::{faces::*, graph6::*, permutation::*, selection::*},
+    permutation::is_isomorphic,
+    shapes::random_triangulation,
+    Mesh,
+};
+
+/// Run `check` on random triangulations of increasing size
+fn for_each_mesh(check: impl Fn(&[usize], &Mesh) -> Result<(), String>) {
+    for faces in (4..=64).step_by(2) {
+        for seed in 0..8 {
+            let permutation = random_triangulation(faces, seed).unwrap();
+            let mesh = Mesh::from_permutation(&permutation);
+            if let Err(message) = check(&permutation, &mesh) {
+                panic!(
+                    "{} with random_triangulation({}, {}): {:?}",
+                    message, faces, seed, permutation
+                );
+            }
+        }
+    }
+}
+
+/// Faces as the origins of their half-edges in the numbering of [gather_vertices]
+fn face_list(permutation: &[usize]) -> Vec<Vec<usize>> {
+    let mut origin = vec![0; permutation.len()];
+    for (v, orbit) in gather_vertices(permutation).iter().enumerate() {
+        for h in orbit.indices() {
+            origin[h] = v;
+        }
+    }
+    gather_faces(permutation)
+        .iter()
+        .map(|orbit| orbit.indices().map(|h| origin[h]).collect())
+        .collect()
+}
+
+fn edges(mesh: &Mesh) -> Vec<(usize, usize)> {
+    let mut edges: Vec<_> = (0..mesh.num_edges())
+        .map(|e| {
+            let vertices = mesh.edge_vertices(e);
+            let (a, b) = (to_usize(vertices[0]), to_usize(*vertices.last().unwrap()));
+            (a.min(b), a.max(b))
+        })
+        .collect();
+    edges.sort_unstable();
+    edges
+}
+
+#[test]
+fn permutation() {
+    // kept exactly
+    for_each_mesh(|permutation, _| {
+        let mut buf = Vec::new();
+        write_permutation(permutation, &mut buf, "random").unwrap();
+        let read = read_permutation(buf.as_slice()).map_err(|e| e.to_string())?;
+        if read != permutation {
+            return Err(format!("read back {:?}", read));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn faces() {
+    // the half-edge numbering is lost, so only the isomorphism class survives
+    for_each_mesh(|permutation, mesh| {
+        let faces = face_list(permutation);
+        let mut buf = Vec::new();
+        write_faces(&faces, &mut buf, "random").unwrap();
+        let read = read_faces(buf.as_slice()).map_err(|e| e.to_string())?;
+        if read != faces {
+            return Err(format!("read back faces {:?}", read));
+        }
+        let rebuilt = umesh::from_faces(&read).map_err(|e| e.to_string())?;
+        if !is_isomorphic(&rebuilt, permutation) {
+            return Err("rebuilt permutation is not isomorphic".to_string());
+        }
+        let counts = |m: &Mesh| (m.num_vertices(), m.num_edges(), m.num_faces());
+        if counts(&Mesh::from_permutation(&rebuilt)) != counts(mesh) {
+            return Err("counts differ".to_string());
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn sparse6() {
+    // the vertex graph with multiple edges is kept exactly
+    for_each_mesh(|_, mesh| {
+        let graph = parse_sparse6(&mesh.vertex_graph_to_sparse6()).map_err(|e| e.to_string())?;
+        if graph.num_vertices != mesh.num_vertices() || graph.edges != edges(mesh) {
+            return Err(format!("read back {:?}", graph));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn graph6() {
+    // multiple edges collapse into one, as graph6 only holds simple graphs
+    for_each_mesh(|_, mesh| {
+        let graph = parse_graph6(&mesh.vertex_graph_to_graph6()).map_err(|e| e.to_string())?;
+        let mut simple = edges(mesh);
+        simple.dedup();
+        if graph.num_vertices != mesh.num_vertices() || graph.edges != simple {
+            return Err(format!("read back {:?}", graph));
+        }
+        Ok(())
+    });
+}
+
+#[test]
+fn selection() {
+    // kept exactly, including empty groups
+    for_each_mesh(|_, mesh| {
+        let every = |n: usize, step: usize| -> Vec<usize> { (0..n).step_by(step).collect() };
+        for step in 1..4 {
+            let selected = mesh.simplicies(
+                &every(mesh.num_vertices(), step),
+                &every(mesh.num_edges(), step + 1),
+                &every(mesh.num_faces(), 2 * step + 1)[1..],
+            );
+            let text = format_selection(&selected);
+            let read = parse_selection(mesh, &text).map_err(|e| e.to_string())?;
+            let sets = |s: &umesh::Simplices| -> [BTreeSet<usize>; 3] {
+                [
+                    s.vertices().to_btree_set(),
+                    s.edges().to_btree_set(),
+                    s.faces().to_btree_set(),
+                ]
+            };
+            if sets(&read) != sets(&selected) {
+                return Err(format!("read back '{}' differently", text));
+            }
+        }
+        Ok(())
+    });
+}